@@ -1,20 +1,191 @@
 #import bevy_pbr::mesh_view_bindings
+#import bevy_pbr::mesh_view_bindings::globals
 #import bevy_pbr::mesh_bindings
 #import bevy_pbr::forward_io::VertexOutput
+#ifdef PORTAL_SHADOWS
+#import bevy_pbr::{mesh_view_bindings::{lights, view}, mesh_view_types, shadows}
+#endif
+
+struct PortalMaterial {
+    // world_from_clip of the portal camera moved to the portal's side
+    destination_world_from_clip: mat4x4<f32>,
+    // strength, frequency, speed, unused
+    ripple: vec4<f32>,
+    // threshold, edge width, unused, unused
+    dissolve: vec4<f32>,
+    dissolve_edge_color: vec4<f32>,
+    // strength, unused, scroll speed x, scroll speed y
+    heat_haze: vec4<f32>,
+    // scale, offset x, offset y, enabled
+    impostor: vec4<f32>,
+    // x, y, width, height of the viewport of the main camera in physical pixels, unused if width is 0
+    viewport: vec4<f32>,
+    openness: f32,
+    alpha: f32,
+    refraction: f32,
+    shadows_darkness: f32,
+}
 
 @group(2) @binding(0)
 var texture: texture_2d<f32>;
 @group(2) @binding(1)
 var texture_sampler: sampler;
+@group(2) @binding(2)
+var<uniform> material: PortalMaterial;
+@group(2) @binding(3)
+var back_texture: texture_2d<f32>;
+@group(2) @binding(4)
+var back_texture_sampler: sampler;
+@group(2) @binding(8)
+var dissolve_noise: texture_2d<f32>;
+@group(2) @binding(9)
+var dissolve_noise_sampler: sampler;
+@group(2) @binding(12)
+var heat_haze_noise: texture_2d<f32>;
+@group(2) @binding(13)
+var heat_haze_noise_sampler: sampler;
+@group(2) @binding(15)
+var refraction_normal_map: texture_2d<f32>;
+@group(2) @binding(16)
+var refraction_normal_map_sampler: sampler;
+@group(2) @binding(19)
+var destination_depth: texture_2d<f32>;
+
+struct FragmentOutput {
+    @location(0) color: vec4<f32>,
+#ifdef PORTAL_DESTINATION_DEPTH
+    @builtin(frag_depth) depth: f32,
+#endif
+}
+
+#ifdef PORTAL_SHADOWS
+// Visibility of the fragment from the directional lights casting shadows,
+// weighted by their luminance, 1 if there are none
+fn directional_shadow_visibility(world_position: vec4<f32>, world_normal: vec3<f32>) -> f32 {
+    let view_z = dot(vec4<f32>(
+        view.view_from_world[0].z,
+        view.view_from_world[1].z,
+        view.view_from_world[2].z,
+        view.view_from_world[3].z
+    ), world_position);
+    var visibility = 0.0;
+    var total = 0.0;
+    for (var i: u32 = 0u; i < lights.n_directional_lights; i = i + 1u) {
+        let light = &lights.directional_lights[i];
+        if (*light).skip != 0u
+                || ((*light).flags & mesh_view_types::DIRECTIONAL_LIGHT_FLAGS_SHADOWS_ENABLED_BIT) == 0u {
+            continue;
+        }
+        let luminance = dot((*light).color.rgb, vec3(0.2126, 0.7152, 0.0722));
+        visibility += luminance * shadows::fetch_directional_shadow(i, world_position, world_normal, view_z);
+        total += luminance;
+    }
+    if total <= 0.0 {
+        return 1.0;
+    }
+    return visibility / total;
+}
+#endif
 
 @fragment
 fn fragment(
-    in: VertexOutput
-) -> @location(0) vec4<f32> {
-    let dimensions = textureDimensions(texture);
-    let dimension_x: f32 = f32(dimensions.x);
-    let dimension_y: f32 = f32(dimensions.y);
-    let uv: vec2<f32> = vec2(in.position.x/dimension_x, in.position.y/dimension_y);// / in.world_position.w;
-    let color = textureSample(texture, texture_sampler, uv).rgb;
-    return vec4(color, 1.0);
+    in: VertexOutput,
+    @builtin(front_facing) is_front: bool,
+) -> FragmentOutput {
+    // The image covers the viewport of the main camera, which can be offset in its render target
+    var viewport_position = in.position.xy;
+    var viewport_size = vec2<f32>(textureDimensions(texture));
+    if material.viewport.z > 0.0 {
+        viewport_position -= material.viewport.xy;
+        viewport_size = material.viewport.zw;
+    }
+    var uv: vec2<f32> = viewport_position / viewport_size;
+    // The snapshot of a far away portal follows it on the screen
+    if material.impostor.w > 0.0 {
+        uv = uv * material.impostor.x + material.impostor.yz;
+    }
+#ifdef PORTAL_MESH_UV
+#ifdef VERTEX_UVS_A
+    uv = in.uv;
+#endif
+#endif
+#ifdef PORTAL_DISSOLVE
+#ifdef VERTEX_UVS_A
+    let noise_uv = in.uv;
+#else
+    let noise_uv = uv;
+#endif
+    // Closing the portal dissolves it too
+    let threshold = max(material.dissolve.x, 1.0 - material.openness);
+    let noise = textureSampleLevel(dissolve_noise, dissolve_noise_sampler, noise_uv, 0.0).r;
+    if noise < threshold {
+        discard;
+    }
+    let dissolve_edge = threshold > 0.0 && noise < threshold + material.dissolve.y;
+#else
+#ifdef VERTEX_UVS_A
+    // Opens like an iris from the center of the mesh UVs
+    if material.openness < 1.0 && length(in.uv - vec2(0.5)) > material.openness * 0.70710678 {
+        discard;
+    }
+#endif
+#endif
+    if material.ripple.x > 0.0 {
+        let p = in.world_position.xyz * material.ripple.y;
+        let t = globals.time * material.ripple.z;
+        uv += material.ripple.x * vec2(sin(p.x + p.y + t), cos(p.z - p.y + t * 1.3));
+    }
+#ifdef PORTAL_HEAT_HAZE
+#ifdef VERTEX_UVS_A
+    let haze_uv = in.uv + globals.time * material.heat_haze.zw;
+#else
+    let haze_uv = uv + globals.time * material.heat_haze.zw;
+#endif
+    let haze = textureSampleLevel(heat_haze_noise, heat_haze_noise_sampler, haze_uv, 0.0).rg;
+    uv += material.heat_haze.x * (haze * 2.0 - 1.0);
+#endif
+#ifdef PORTAL_REFRACTION
+#ifdef VERTEX_UVS_A
+    let normal_uv = in.uv;
+#else
+    let normal_uv = uv;
+#endif
+    let normal = textureSampleLevel(refraction_normal_map, refraction_normal_map_sampler, normal_uv, 0.0).rgb * 2.0 - 1.0;
+    uv += material.refraction * normal.xy;
+#endif
+    // The image has a single mip level, sampling it explicitly doesn't need screen-space derivatives
+    var color = textureSampleLevel(texture, texture_sampler, uv, 0.0).rgb;
+#ifdef PORTAL_BACK_TEXTURE
+    if !is_front {
+        color = textureSampleLevel(back_texture, back_texture_sampler, uv, 0.0).rgb;
+    }
+#endif
+#ifdef PORTAL_SHADOWS
+    let visibility = directional_shadow_visibility(in.world_position, normalize(in.world_normal));
+    color *= mix(1.0, visibility, material.shadows_darkness);
+#endif
+#ifdef PORTAL_DISSOLVE
+    if dissolve_edge {
+        color = material.dissolve_edge_color.rgb;
+    }
+#endif
+    var out: FragmentOutput;
+    out.color = vec4(color, material.alpha);
+#ifdef PORTAL_DESTINATION_DEPTH
+    // Reproject the depth seen by the portal camera for the main camera
+    let depth_dimensions = vec2<i32>(textureDimensions(destination_depth));
+    let depth_coords = clamp(vec2<i32>(viewport_position), vec2(0), depth_dimensions - 1);
+    let raw_depth = textureLoad(destination_depth, depth_coords, 0).r;
+    // Nothing rendered there, stays at the far plane (reverse Z)
+    out.depth = 0.0;
+    if raw_depth > 0.0 {
+        let depth_uv = viewport_position / vec2<f32>(depth_dimensions);
+        let ndc = vec4(depth_uv.x * 2.0 - 1.0, 1.0 - depth_uv.y * 2.0, raw_depth, 1.0);
+        let world_position = material.destination_world_from_clip * ndc;
+        let clip_position = mesh_view_bindings::view.clip_from_world * (world_position / world_position.w);
+        // Never in front of the portal surface
+        out.depth = clamp(clip_position.z / clip_position.w, 0.0, in.position.z);
+    }
+#endif
+    return out;
 }
\ No newline at end of file
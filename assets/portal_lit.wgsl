@@ -0,0 +1,36 @@
+#import bevy_pbr::{
+    pbr_fragment::pbr_input_from_standard_material,
+    pbr_functions::{alpha_discard, apply_pbr_lighting, main_pass_post_lighting_processing},
+    forward_io::{VertexOutput, FragmentOutput},
+}
+
+@group(2) @binding(100)
+var portal_texture: texture_2d<f32>;
+@group(2) @binding(101)
+var portal_sampler: sampler;
+// x, y, width, height of the viewport of the main camera in physical pixels, unused if width is 0
+@group(2) @binding(102)
+var<uniform> viewport: vec4<f32>;
+
+@fragment
+fn fragment(
+    in: VertexOutput,
+    @builtin(front_facing) is_front: bool,
+) -> FragmentOutput {
+    var pbr_input = pbr_input_from_standard_material(in, is_front);
+    pbr_input.material.base_color = alpha_discard(pbr_input.material, pbr_input.material.base_color);
+
+    // Same screen-space mask as the unlit portal material
+    var uv = in.position.xy / vec2<f32>(textureDimensions(portal_texture));
+    if viewport.z > 0.0 {
+        uv = (in.position.xy - viewport.xy) / viewport.zw;
+    }
+    let portal_color = textureSampleLevel(portal_texture, portal_sampler, uv, 0.0);
+
+    var out: FragmentOutput;
+    out.color = apply_pbr_lighting(pbr_input);
+    // The portal image is underneath the lit surface
+    out.color = vec4(out.color.rgb + portal_color.rgb, out.color.a);
+    out.color = main_pass_post_lighting_processing(pbr_input, out.color);
+    return out;
+}
@@ -0,0 +1,120 @@
+//! Micro-benchmark of [update_portal_cameras] with many portals, comparing portals sharing one
+//! main camera with portals each having their own main camera.
+//!
+//! The viewport size of a main camera is computed once per frame and reused by the portals
+//! sharing it, so the first case does 1 lookup per frame instead of one per portal.
+//!
+//! Run with `cargo bench --bench viewport_size`.
+
+use std::time::{Duration, Instant};
+
+use bevy::{
+    core::FrameCount,
+    log::LogPlugin,
+    prelude::*,
+    render::{
+        camera::RenderTarget,
+        render_resource::{Extent3d, TextureDimension, TextureFormat},
+        settings::WgpuSettings,
+        RenderPlugin,
+    },
+    window::ExitCondition,
+    winit::WinitPlugin,
+};
+use bevy_basic_portals::{portals::update_portal_cameras, prelude::*};
+
+const PORTALS: usize = 200;
+const FRAMES: u32 = 2000;
+
+fn main() {
+    let shared = bench(1);
+    let separate = bench(PORTALS);
+    println!(
+        "{PORTALS} portals, 1 main camera: {:?}/frame",
+        shared / FRAMES
+    );
+    println!(
+        "{PORTALS} portals, {PORTALS} main cameras: {:?}/frame",
+        separate / FRAMES
+    );
+}
+
+/// Time spent in [FRAMES] runs of [update_portal_cameras] with [PORTALS] portals spread over
+/// `main_cameras` cameras
+fn bench(main_cameras: usize) -> Duration {
+    let mut app = App::new();
+    // Without a window nor a GPU
+    app.add_plugins((
+        DefaultPlugins
+            .set(WindowPlugin {
+                primary_window: None,
+                exit_condition: ExitCondition::DontExit,
+                ..default()
+            })
+            .set(RenderPlugin {
+                render_creation: WgpuSettings {
+                    backends: None,
+                    ..default()
+                }
+                .into(),
+                ..default()
+            })
+            .disable::<LogPlugin>()
+            .disable::<WinitPlugin>(),
+        PortalsPlugin::MINIMAL,
+    ));
+    app.finish();
+    app.cleanup();
+
+    let world = app.world_mut();
+    let target = world.resource_mut::<Assets<Image>>().add(Image::new_fill(
+        Extent3d {
+            width: 1280,
+            height: 720,
+            ..default()
+        },
+        TextureDimension::D2,
+        &[0; 4],
+        TextureFormat::Bgra8UnormSrgb,
+        default(),
+    ));
+    let cameras: Vec<Entity> = (0..main_cameras)
+        .map(|_| {
+            world
+                .spawn((
+                    Camera3d::default(),
+                    Camera {
+                        target: RenderTarget::Image(target.clone()),
+                        ..default()
+                    },
+                    Transform::from_xyz(0., 0., 20.).looking_at(Vec3::ZERO, Vec3::Y),
+                ))
+                .id()
+        })
+        .collect();
+    let mesh = world
+        .resource_mut::<Assets<Mesh>>()
+        .add(Rectangle::new(1., 1.));
+    for index in 0..PORTALS {
+        world.spawn((
+            CreatePortal {
+                main_camera: Some(cameras[index % main_cameras]),
+                ..CreatePortal::to(Transform::from_xyz(index as f32, 0., -10.))
+            },
+            Mesh3d(mesh.clone()),
+            Transform::from_xyz(index as f32, 0., 0.),
+        ));
+    }
+
+    // Creates the portals
+    app.update();
+
+    let world = app.world_mut();
+    let system = world.register_system(update_portal_cameras);
+    let start = Instant::now();
+    for _ in 0..FRAMES {
+        world.resource_mut::<FrameCount>().0 += 1;
+        world.run_system(system).unwrap();
+    }
+    start.elapsed()
+}
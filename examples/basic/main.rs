@@ -2,7 +2,7 @@
 //! it uses a single sphere that will be displayed two times on screen thanks to the portal
 
 use bevy::prelude::*;
-use bevy_basic_portals::*;
+use bevy_basic_portals::prelude::*;
 
 fn main() {
     App::new()
@@ -20,15 +20,8 @@ fn setup(mut commands: Commands, mut meshes: ResMut<Assets<Mesh>>) {
     let portal_mesh = meshes.add(Mesh::from(Rectangle::new(10., 10.)));
     commands.spawn((
         // This component will be deleted and things that are needed to create the portal will be created
-        CreatePortal {
-            destination: AsPortalDestination::Create(Transform::from_xyz(20., 0., 0.).into()),
-            // Uncomment this to see the portal
-            /*debug: Some(DebugPortal {
-                show_window: false,
-                ..default()
-            }),*/
-            ..default()
-        },
+        // Use `CreatePortal { debug: Some(default()), ..CreatePortal::to(...) }` to see the portal
+        CreatePortal::to(Transform::from_xyz(20., 0., 0.)),
         Mesh3d(portal_mesh),
     ));
 
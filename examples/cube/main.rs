@@ -8,7 +8,7 @@ use bevy::{
     render::{render_resource::Face, view::RenderLayers},
 };
 
-use bevy_basic_portals::*;
+use bevy_basic_portals::prelude::*;
 
 #[path = "../../helpers/pivot_cameras.rs"]
 mod pivot_cameras;
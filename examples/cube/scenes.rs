@@ -1,5 +1,5 @@
 use bevy::{prelude::*, render::view::RenderLayers};
-use bevy_basic_portals::*;
+use bevy_basic_portals::prelude::*;
 use std::f32::consts::PI;
 
 pub const DESTINATION_DISTANCE: f32 = 50.;
@@ -20,8 +20,8 @@ pub fn setup_portal_cube_face(
 
     let destination_transform = get_destination_transform(spawn_portal_dir, spawn_portal_up);
 
-    let create_portal = portals::CreatePortal {
-        destination: AsPortalDestination::Create(destination_transform.into()),
+    let create_portal = CreatePortal {
+        destination: PortalDestinationSource::Create(destination_transform.into()),
         main_camera: Some(main_camera),
         render_layer,
         ..default()
@@ -0,0 +1,203 @@
+//! This example is a regression test for the colors of portals with non-default camera settings,
+//! it renders the same cube directly (left) and through a portal (right) with a non-default
+//! Exposure and several Tonemapping modes, compares the average color of both cubes on a
+//! screenshot, and exits with an error if they don't match.
+//!
+//! Run it with `cargo run --example exposure`, the exit code is non-zero on failure.
+
+use bevy::{
+    app::AppExit,
+    core_pipeline::tonemapping::Tonemapping,
+    prelude::*,
+    render::{
+        camera::Exposure,
+        view::screenshot::{Screenshot, ScreenshotCaptured},
+    },
+    window::WindowResolution,
+};
+use bevy_basic_portals::prelude::*;
+
+/// Maximum difference allowed between the average channels of both cubes (out of 255)
+const TOLERANCE: f32 = 4.;
+/// Frames to wait after changing the tonemapping mode before taking a screenshot
+const SETTLE_FRAMES: u32 = 30;
+/// Half size of the sampled square, in pixels
+const SAMPLE_HALF_SIZE: i32 = 6;
+
+const TONEMAPPING_MODES: [Tonemapping; 5] = [
+    Tonemapping::None,
+    Tonemapping::Reinhard,
+    Tonemapping::AcesFitted,
+    Tonemapping::AgX,
+    Tonemapping::TonyMcMapface,
+];
+
+fn main() {
+    App::new()
+        .add_plugins((
+            DefaultPlugins.set(WindowPlugin {
+                primary_window: Some(Window {
+                    // So that viewport coordinates and screenshot pixels match
+                    resolution: WindowResolution::new(800., 600.).with_scale_factor_override(1.),
+                    ..default()
+                }),
+                ..default()
+            }),
+            PortalsPlugin::MINIMAL,
+        ))
+        .init_resource::<ParityTest>()
+        .add_systems(Startup, setup)
+        .add_systems(Update, take_screenshot)
+        .run();
+}
+
+#[derive(Component)]
+struct MainCamera;
+
+#[derive(Resource, Default)]
+struct ParityTest {
+    mode: usize,
+    frames: u32,
+    waiting_for_screenshot: bool,
+    failures: u32,
+}
+
+fn setup(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.spawn((
+        Camera3d::default(),
+        Exposure { ev100: 12. },
+        TONEMAPPING_MODES[0],
+        Transform::default(),
+        MainCamera,
+    ));
+
+    commands.spawn((
+        DirectionalLight {
+            illuminance: 20_000.,
+            ..default()
+        },
+        Transform::from_xyz(1., 2., 3.).looking_at(Vec3::ZERO, Vec3::Y),
+    ));
+
+    // Portal on the right, the destination is far away so that the cubes don't see each other
+    commands.spawn((
+        CreatePortal {
+            destination: PortalDestinationSource::Create(Transform::from_xyz(103., 0., -10.).into()),
+            ..default()
+        },
+        Mesh3d(meshes.add(Rectangle::new(4., 4.))),
+        Transform::from_xyz(3., 0., -10.),
+    ));
+
+    let cube_mesh = meshes.add(Cuboid::new(2., 2., 2.));
+    let cube_material = materials.add(StandardMaterial {
+        base_color: Color::srgb(0.8, 0.3, 0.2),
+        perceptual_roughness: 1.,
+        reflectance: 0.,
+        ..default()
+    });
+
+    // Seen directly, its front face appears at (-3, 0, -11)
+    commands.spawn((
+        Mesh3d(cube_mesh.clone()),
+        MeshMaterial3d(cube_material.clone()),
+        Transform::from_xyz(-3., 0., -12.),
+    ));
+
+    // Seen through the portal, its front face appears at (3, 0, -11)
+    commands.spawn((
+        Mesh3d(cube_mesh),
+        MeshMaterial3d(cube_material),
+        Transform::from_xyz(103., 0., -12.),
+    ));
+}
+
+fn take_screenshot(mut commands: Commands, mut test: ResMut<ParityTest>) {
+    if test.waiting_for_screenshot {
+        return;
+    }
+    test.frames += 1;
+    if test.frames < SETTLE_FRAMES {
+        return;
+    }
+    test.waiting_for_screenshot = true;
+    commands
+        .spawn(Screenshot::primary_window())
+        .observe(compare_cubes);
+}
+
+fn compare_cubes(
+    trigger: Trigger<ScreenshotCaptured>,
+    mut test: ResMut<ParityTest>,
+    main_camera: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    mut tonemappings: Query<&mut Tonemapping, Or<(With<MainCamera>, With<PortalCamera>)>>,
+    mut exit: EventWriter<AppExit>,
+) {
+    let image = &trigger.event().0;
+    let (camera, camera_transform) = main_camera.single();
+    let mode = TONEMAPPING_MODES[test.mode];
+
+    let direct = camera
+        .world_to_viewport(camera_transform, Vec3::new(-3., 0., -11.))
+        .map(|position| average_color(image, position));
+    let through_portal = camera
+        .world_to_viewport(camera_transform, Vec3::new(3., 0., -11.))
+        .map(|position| average_color(image, position));
+
+    match (direct, through_portal) {
+        (Ok(direct), Ok(through_portal)) => {
+            let difference = (direct - through_portal).abs().max_element();
+            if difference > TOLERANCE {
+                error!("{mode:?}: direct {direct} and through portal {through_portal} differ by {difference}");
+                test.failures += 1;
+            } else {
+                info!("{mode:?}: direct {direct} and through portal {through_portal} match");
+            }
+        }
+        _ => {
+            error!("{mode:?}: couldn't find the cubes on screen");
+            test.failures += 1;
+        }
+    }
+
+    test.mode += 1;
+    test.frames = 0;
+    test.waiting_for_screenshot = false;
+
+    let Some(next_mode) = TONEMAPPING_MODES.get(test.mode) else {
+        exit.send(if test.failures == 0 {
+            AppExit::Success
+        } else {
+            AppExit::error()
+        });
+        return;
+    };
+    // Portal cameras copy the settings of the main camera only on creation
+    for mut tonemapping in tonemappings.iter_mut() {
+        *tonemapping = *next_mode;
+    }
+}
+
+/// Average of the first three channels of the pixels around `position`,
+/// assumes 4 bytes per pixel without row padding.
+fn average_color(image: &Image, position: Vec2) -> Vec3 {
+    let width = image.width() as i32;
+    let height = image.height() as i32;
+    let mut sum = Vec3::ZERO;
+    let mut count = 0.;
+    for y in -SAMPLE_HALF_SIZE..=SAMPLE_HALF_SIZE {
+        for x in -SAMPLE_HALF_SIZE..=SAMPLE_HALF_SIZE {
+            let x = (position.x as i32 + x).clamp(0, width - 1);
+            let y = (position.y as i32 + y).clamp(0, height - 1);
+            let index = ((y * width + x) * 4) as usize;
+            let pixel = &image.data[index..index + 3];
+            sum += Vec3::new(pixel[0] as f32, pixel[1] as f32, pixel[2] as f32);
+            count += 1.;
+        }
+    }
+    sum / count
+}
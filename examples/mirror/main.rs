@@ -1,7 +1,7 @@
 //! This example illustrates how to create a mirror
 
 use bevy::prelude::*;
-use bevy_basic_portals::*;
+use bevy_basic_portals::prelude::*;
 
 #[path = "../../helpers/pivot_cameras.rs"]
 mod pivot_cameras;
@@ -68,15 +68,14 @@ fn setup(
     let portal_mesh = meshes.add(Rectangle::new(10., 10.));
     let portal_transform = Transform::from_xyz(0., 0., -10.);
     let mut mirror = commands.spawn((
-        CreatePortal {
-            main_camera: Some(main_camera),
-            destination: AsPortalDestination::CreateMirror,
-            debug: Some(DebugPortal {
+        CreatePortal::builder()
+            .mirror()
+            .main_camera(main_camera)
+            .debug(DebugPortal {
                 show_window: false,
                 ..default()
-            }),
-            ..default()
-        },
+            })
+            .build(),
         Mesh3d(portal_mesh),
         portal_transform,
     ));
@@ -0,0 +1,100 @@
+//! This example illustrates picking entities through a mirror with the `picking` feature,
+//! click the cubes or their reflections to change their color.
+//!
+//! The portal image is mapped in screen space, so a pointer over a reflection is forwarded to
+//! the same position on the image of the mirror's portal camera, and picks the reflected cube.
+
+use bevy::prelude::*;
+use bevy_basic_portals::prelude::*;
+
+#[path = "../../helpers/pivot_cameras.rs"]
+mod pivot_cameras;
+
+fn main() {
+    App::new()
+        .add_plugins((
+            DefaultPlugins,
+            MeshPickingPlugin,
+            PortalsPlugin::MINIMAL,
+            pivot_cameras::PivotCamerasPlugin::default(),
+        ))
+        .add_systems(Startup, setup)
+        .run();
+}
+
+/// Colors a cube goes through when clicked
+const COLORS: [Color; 3] = [
+    Color::srgb(0.8, 0.3, 0.2),
+    Color::srgb(0.2, 0.8, 0.3),
+    Color::srgb(0.2, 0.3, 0.8),
+];
+
+#[derive(Component)]
+struct ColorIndex(usize);
+
+fn setup(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.insert_resource(AmbientLight {
+        color: Color::WHITE,
+        brightness: 400.,
+    });
+
+    let pivot = Vec3::ZERO;
+    let main_camera = commands
+        .spawn((
+            Camera3d::default(),
+            Transform::from_xyz(10., 0., 20.).looking_at(pivot, Vec3::Y),
+            pivot_cameras::PivotCamera {
+                pivot,
+                closest: 0.,
+                ..default()
+            },
+        ))
+        .id();
+
+    // Cubes, on the left so that their reflections are on the other side of the mirror
+    let cube_mesh = meshes.add(Cuboid::new(2., 2., 2.));
+    for (index, position) in [Vec3::new(-4., 2., -4.), Vec3::new(-2., -2., 0.)]
+        .into_iter()
+        .enumerate()
+    {
+        commands
+            .spawn((
+                Mesh3d(cube_mesh.clone()),
+                MeshMaterial3d(materials.add(COLORS[index])),
+                Transform::from_translation(position),
+                ColorIndex(index),
+            ))
+            .observe(change_color);
+    }
+
+    // Mirror
+    commands.spawn((
+        CreatePortal {
+            main_camera: Some(main_camera),
+            destination: PortalDestinationSource::CreateMirror,
+            ..default()
+        },
+        Mesh3d(meshes.add(Rectangle::new(10., 10.))),
+        Transform::from_xyz(0., 0., -10.),
+    ));
+}
+
+/// Picks the next color of a cube clicked directly or through the mirror
+fn change_color(
+    trigger: Trigger<Pointer<Click>>,
+    mut cubes: Query<(&mut ColorIndex, &MeshMaterial3d<StandardMaterial>)>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let Ok((mut color_index, material)) = cubes.get_mut(trigger.entity()) else {
+        return;
+    };
+    color_index.0 = (color_index.0 + 1) % COLORS.len();
+    if let Some(material) = materials.get_mut(&material.0) {
+        material.base_color = COLORS[color_index.0];
+    }
+    info!("Clicked {} with {:?}", trigger.entity(), trigger.pointer_id);
+}
@@ -1,7 +1,7 @@
 //! This example illustrates the portal and destination moving, and tests for hierarchy
 
 use bevy::prelude::*;
-use bevy_basic_portals::*;
+use bevy_basic_portals::prelude::*;
 
 const PORTAL_TRANSLATION_START: Vec3 = Vec3::ZERO;
 const PORTAL_TRANSLATION_END: Vec3 = Vec3::new(3., 3., 0.);
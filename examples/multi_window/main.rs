@@ -0,0 +1,96 @@
+//! This example illustrates a portal seen by a main camera rendering to a second window
+//!
+//! The primary window shows the scene from above, the second window shows it through the portal,
+//! resize the second window to check that the portal image follows it.
+
+use bevy::{
+    prelude::*,
+    render::camera::RenderTarget,
+    window::{WindowRef, WindowResolution},
+};
+use bevy_basic_portals::prelude::*;
+
+fn main() {
+    App::new()
+        .add_plugins((DefaultPlugins, PortalsPlugin::MINIMAL))
+        .add_systems(Startup, setup)
+        .add_systems(Update, rotate_spheres)
+        .run();
+}
+
+#[derive(Component)]
+struct Rotating;
+
+fn setup(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.spawn((
+        DirectionalLight::default(),
+        Transform::from_xyz(1., 2., 3.).looking_at(Vec3::ZERO, Vec3::Y),
+    ));
+
+    // Camera of the primary window, looking at the portal and the destination from above
+    commands.spawn((
+        Camera3d::default(),
+        Transform::from_xyz(10., 40., 20.).looking_at(Vec3::new(10., 0., 0.), Vec3::Y),
+    ));
+
+    // Main camera of the portal, in a second window
+    let second_window = commands
+        .spawn(Window {
+            title: "Through the portal".to_owned(),
+            resolution: WindowResolution::new(640., 480.),
+            ..default()
+        })
+        .id();
+    let main_camera = commands
+        .spawn((
+            Camera3d::default(),
+            Camera {
+                target: RenderTarget::Window(WindowRef::Entity(second_window)),
+                ..default()
+            },
+            Transform::from_xyz(0., 0., 15.).looking_at(Vec3::ZERO, Vec3::Y),
+        ))
+        .id();
+
+    // Portal at the origin, looking at the spheres around its destination
+    commands.spawn((
+        CreatePortal {
+            destination: PortalDestinationSource::Create(Transform::from_xyz(20., 0., 0.).into()),
+            main_camera: Some(main_camera),
+            debug: Some(DebugPortal {
+                show_window: false,
+                ..default()
+            }),
+            ..default()
+        },
+        Mesh3d(meshes.add(Rectangle::new(10., 10.))),
+    ));
+
+    let sphere_mesh = meshes.add(Sphere::new(1.).mesh().uv(32, 18));
+    let sphere_material = materials.add(Color::srgb(0.8, 0.3, 0.2));
+    commands
+        .spawn((
+            Transform::from_xyz(20., 0., -5.),
+            Visibility::default(),
+            Rotating,
+        ))
+        .with_children(|parent| {
+            for x in [-3., 0., 3.] {
+                parent.spawn((
+                    Mesh3d(sphere_mesh.clone()),
+                    MeshMaterial3d(sphere_material.clone()),
+                    Transform::from_xyz(x, 0., 0.),
+                ));
+            }
+        });
+}
+
+fn rotate_spheres(time: Res<Time>, mut query: Query<&mut Transform, With<Rotating>>) {
+    for mut transform in query.iter_mut() {
+        transform.rotate_y(time.delta_secs() * 0.5);
+    }
+}
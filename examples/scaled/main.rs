@@ -0,0 +1,116 @@
+//! This example illustrates portals and destinations with different scales
+//!
+//! On top are the portals, at the bottom are their destinations looking at the same spheres,
+//! the left portal is stretched horizontally, the middle one has a destination twice as big
+//! so it shows the spheres twice as small, and the right one has a destination squashed vertically
+
+use bevy::prelude::*;
+use bevy_basic_portals::prelude::*;
+
+#[path = "../../helpers/pivot_cameras.rs"]
+mod pivot_cameras;
+#[path = "../../helpers/textures.rs"]
+mod textures;
+
+fn main() {
+    App::new()
+        .add_plugins((
+            DefaultPlugins,
+            PortalsPlugin::MINIMAL,
+            pivot_cameras::PivotCamerasPlugin::default(),
+        ))
+        .add_systems(Startup, setup)
+        .run();
+}
+
+fn setup(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut images: ResMut<Assets<Image>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    // Camera
+    let pivot = Vec3::new(0., 5., 0.);
+    let main_camera = commands
+        .spawn((
+            Camera3d::default(),
+            Transform::from_xyz(0., 5., 30.).looking_at(pivot, Vec3::Y),
+            pivot_cameras::PivotCamera {
+                pivot,
+                closest: 0.,
+                ..default()
+            },
+        ))
+        .id();
+
+    // Lights
+    commands.insert_resource(AmbientLight {
+        color: Color::WHITE,
+        brightness: 500.,
+    });
+
+    commands.insert_resource(ClearColor(Color::srgb(0., 0., 0.)));
+
+    // Spheres seen through the portals
+    let debug_material = materials.add(textures::debug_material(&mut images, 2, None));
+    let sphere_mesh = meshes.add(Sphere::new(1.).mesh().uv(32, 18));
+    for x in [-3., 0., 3.] {
+        commands.spawn((
+            Mesh3d(sphere_mesh.clone()),
+            MeshMaterial3d(debug_material.clone()),
+            Transform::from_xyz(x, -10., -5.),
+        ));
+    }
+
+    let portal_mesh = meshes.add(Rectangle::new(5., 5.));
+    let destination_transform = Transform::from_xyz(0., -10., 0.);
+
+    // Portal stretched horizontally
+    setup_portal(
+        &mut commands,
+        main_camera,
+        portal_mesh.clone(),
+        Transform::from_xyz(-10., 10., 0.).with_scale(Vec3::new(2., 1., 1.)),
+        destination_transform,
+    );
+
+    // Destination twice as big as the portal
+    setup_portal(
+        &mut commands,
+        main_camera,
+        portal_mesh.clone(),
+        Transform::from_xyz(0., 10., 0.),
+        destination_transform.with_scale(Vec3::splat(2.)),
+    );
+
+    // Destination squashed vertically
+    setup_portal(
+        &mut commands,
+        main_camera,
+        portal_mesh,
+        Transform::from_xyz(10., 10., 0.),
+        destination_transform.with_scale(Vec3::new(1., 0.5, 1.)),
+    );
+}
+
+fn setup_portal(
+    commands: &mut Commands,
+    main_camera: Entity,
+    mesh: Handle<Mesh>,
+    portal_transform: Transform,
+    destination_transform: Transform,
+) {
+    commands.spawn((
+        CreatePortal {
+            main_camera: Some(main_camera),
+            destination: PortalDestinationSource::Create(destination_transform.into()),
+            debug: Some(DebugPortal {
+                show_window: false,
+                ..default()
+            }),
+            ..default()
+        },
+        Mesh3d(mesh),
+        portal_transform,
+    ));
+}
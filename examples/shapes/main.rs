@@ -4,7 +4,7 @@
 //! and around are shapes that you can also see through the portal with the correct orientation
 
 use bevy::{prelude::*, render::render_resource::Face};
-use bevy_basic_portals::*;
+use bevy_basic_portals::prelude::*;
 
 #[path = "../../helpers/pivot_cameras.rs"]
 mod pivot_cameras;
@@ -74,6 +74,19 @@ fn setup(
         Some(Face::Back),
     );
 
+    // Cube mirrored with a negative scale, like meshes mirrored in a modeling tool,
+    // the object is not culled as Bevy doesn't flip its faces
+    let debug_material = materials.add(textures::debug_material(&mut images, 2, None));
+    let mirrored_cube_mesh = meshes.add(Cuboid::new(5., 5., 5.));
+    setup_object_and_portal(
+        &mut commands,
+        main_camera,
+        mirrored_cube_mesh,
+        debug_material.clone(),
+        Transform::from_xyz(0., 0., 10.).with_scale(Vec3::new(-1., 1., 1.)),
+        Some(Face::Back),
+    );
+
     // double-sided Quad
     let debug_material = materials.add(textures::debug_material(&mut images, 1, None));
     let quad_mesh = meshes.add(Rectangle::new(5., 5.));
@@ -110,7 +123,7 @@ fn setup_object_and_portal(
     commands.spawn((
         CreatePortal {
             main_camera: Some(main_camera),
-            destination: AsPortalDestination::Create(destination_transform.into()),
+            destination: PortalDestinationSource::Create(destination_transform.into()),
             portal_mode: PortalMode::MaskedImageNoFrustum,
             cull_mode,
             debug: Some(DebugPortal {
@@ -0,0 +1,151 @@
+//! This example is a stress test for portals seen by several main cameras with changing viewports,
+//! press Space to toggle between fullscreen and split-screen, and resize the window.
+//!
+//! Each main camera has its own portal at the same place: portals are seen by a single main camera,
+//! the portal meshes are on different [RenderLayers] so that each camera only sees its portal.
+
+use bevy::{
+    input::common_conditions::input_just_pressed,
+    prelude::*,
+    render::{camera::Viewport, view::RenderLayers},
+    window::WindowResized,
+};
+use bevy_basic_portals::prelude::*;
+
+fn main() {
+    App::new()
+        .add_plugins((DefaultPlugins, PortalsPlugin::MINIMAL))
+        .init_resource::<SplitScreen>()
+        .add_systems(Startup, setup)
+        .add_systems(
+            Update,
+            (
+                toggle_split_screen.run_if(input_just_pressed(KeyCode::Space)),
+                update_viewports,
+                orbit_cameras,
+            )
+                .chain(),
+        )
+        .run();
+}
+
+#[derive(Resource, Default)]
+struct SplitScreen(bool);
+
+/// Index of the main camera, 0 is on the left or fullscreen, 1 is on the right
+#[derive(Component)]
+struct MainCamera(u32);
+
+fn setup(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.spawn((
+        DirectionalLight::default(),
+        Transform::from_xyz(1., 2., 3.).looking_at(Vec3::ZERO, Vec3::Y),
+    ));
+
+    let portal_mesh = meshes.add(Rectangle::new(10., 10.));
+    for index in 0..2 {
+        // Layer 0 is the scene, each camera also sees the layer of its portal
+        let portal_layer = RenderLayers::layer(1 + index as usize);
+        let main_camera = commands
+            .spawn((
+                Camera3d::default(),
+                Camera {
+                    order: index as isize,
+                    is_active: index == 0,
+                    ..default()
+                },
+                Transform::from_xyz(-20., 0., 20.).looking_at(Vec3::ZERO, Vec3::Y),
+                RenderLayers::layer(0).union(&portal_layer),
+                MainCamera(index),
+            ))
+            .id();
+
+        commands.spawn((
+            CreatePortal {
+                destination: PortalDestinationSource::Create(
+                    Transform::from_xyz(20., 0., 0.).into(),
+                ),
+                main_camera: Some(main_camera),
+                ..default()
+            },
+            Mesh3d(portal_mesh.clone()),
+            portal_layer,
+        ));
+    }
+
+    let sphere_mesh = meshes.add(Sphere::new(2.).mesh().uv(32, 18));
+    commands.spawn((
+        Mesh3d(sphere_mesh.clone()),
+        MeshMaterial3d(materials.add(Color::srgb(0.8, 0.3, 0.2))),
+        Transform::from_xyz(20., 0., -5.),
+    ));
+    commands.spawn((
+        Mesh3d(sphere_mesh),
+        MeshMaterial3d(materials.add(Color::srgb(0.2, 0.3, 0.8))),
+        Transform::from_xyz(0., 0., 5.),
+    ));
+
+    commands.spawn((
+        Text::new("Space: toggle split-screen"),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(12.),
+            left: Val::Px(12.),
+            ..default()
+        },
+    ));
+}
+
+fn toggle_split_screen(mut split_screen: ResMut<SplitScreen>) {
+    split_screen.0 = !split_screen.0;
+}
+
+/// Sets the viewports of the main cameras when the split-screen mode changes or the window is resized
+fn update_viewports(
+    split_screen: Res<SplitScreen>,
+    mut resize_events: EventReader<WindowResized>,
+    windows: Query<&Window>,
+    mut cameras: Query<(&MainCamera, &mut Camera)>,
+) {
+    let resized = resize_events.read().last().is_some();
+    if !resized && !split_screen.is_changed() {
+        return;
+    }
+    let Ok(window) = windows.get_single() else {
+        return;
+    };
+    let size = window.physical_size();
+    // Minimized window
+    if size.x < 2 || size.y == 0 {
+        return;
+    }
+    for (MainCamera(index), mut camera) in cameras.iter_mut() {
+        if split_screen.0 {
+            let half_size = UVec2::new(size.x / 2, size.y);
+            camera.is_active = true;
+            camera.viewport = Some(Viewport {
+                physical_position: UVec2::new(half_size.x * index, 0),
+                physical_size: half_size,
+                ..default()
+            });
+        } else {
+            camera.is_active = *index == 0;
+            camera.viewport = None;
+        }
+    }
+}
+
+/// Moves the main cameras in opposite directions so that both viewports show different views
+fn orbit_cameras(time: Res<Time>, mut cameras: Query<(&MainCamera, &mut Transform)>) {
+    for (MainCamera(index), mut transform) in cameras.iter_mut() {
+        let direction = if *index == 0 { 1. } else { -1. };
+        let angle =
+            direction * 0.4 * (time.elapsed_secs() * 0.5).sin() - std::f32::consts::FRAC_PI_4;
+        *transform = Transform::from_xyz(28. * angle.sin(), 0., 28. * angle.cos())
+            .looking_at(Vec3::ZERO, Vec3::Y);
+    }
+}
@@ -19,19 +19,42 @@
 //! - portals created by this crate are uni-directionnal, you can only look from one space to the other,
 //! if you want a bidirectional portal you can crate two portals manually
 //! - this crate doesn't handle "portal recursion", as in viewing a portal through another portal
-//! - portals created by this crate have no visible borders (not counting aliasing artifacts), you can "see" them with [DebugPortal]
+//! - portals created by this crate have no visible borders by default (not counting aliasing artifacts), you can add one with [PortalBorder](portals::PortalBorder)
+//! or "see" them with [DebugPortal](portals::DebugPortal)
 //! - this crate doesn't handle moving stuff through the portal, it is only visual, more like a crystal ball
-//! - this crate doesn't handle raycasting through the portal, it has to be done manually
-//! - this crate doesn't handle resizing window/viewport of the main camera
-//! - this crate doesn't handle changing the portal's or the destination's scale
-//! - this crate doesn't handle changing camera settings after creation
+//! - this crate doesn't handle raycasting through the portal, it has to be done manually with
+//! [portal_ray](portals::portal_ray), except for picking with the `picking` feature (see [picking])
+//! - the ratio of the scales of the portal and the destination magnifies, shrinks or stretches what is seen through the portal
+//! (see [portal_to_destination_scale](portals::portal_to_destination_scale)), the sign of the scales is ignored
+//! - this crate doesn't handle changing camera settings after creation, they are only copied again when switching
+//! to another main camera with [SetPortalMainCameraCommand](portals::SetPortalMainCameraCommand)
+//! - this crate doesn't handle stereo (XR) main cameras rendering both eyes in one view, if each eye is a separate camera,
+//! you can create one portal per eye, with a copy of the portal mesh on a [RenderLayers](bevy_render::view::RenderLayers) only seen by that eye's camera,
+//! which [CreatePortal::main_camera_views](portals::CreatePortal::main_camera_views) does for every main camera
+//! - there is no cubemap [PortalMode](portals::PortalMode) rendering the destination with six cameras for spherical portals with a correct
+//! parallax from any angle, a portal has a single [PortalCamera](portals::PortalCamera), spherical portals use
+//! [PortalMode::MaskedImageNoFrustum](portals::PortalMode::MaskedImageNoFrustum)
+//! - mirrors use the generic portal logic, objects crossing a mirror plane are not clipped by it (see
+//! [PortalDestinationSource::CreateMirror](portals::PortalDestinationSource::CreateMirror))
+//! - each portal renders to its own image, portals with the same image size can't share the layers of a texture array
+//! (the cameras of Bevy 0.15 render to a whole image)
 
+#[cfg(feature = "picking")]
+pub mod picking;
 pub mod portals;
-pub use portals::*;
+pub mod prelude;
 #[doc(inline)]
 pub use portals::{CreatePortal, PortalsPlugin};
 
 //TODO examples:
-// - should handle multiple main cameras
 // - portal or destination in a hierarchy should work
 // - spawning portals, despawning portal parts, removing necessary components
+
+//TODO features:
+// - share a texture array between portals with the same image size (one layer per portal):
+//   cameras of Bevy 0.15 render to a whole Image, each portal camera would need a
+//   ManualTextureView on its layer, and PortalMaterial a texture_2d_array binding with the layer index
+// - when updating to Bevy 0.16, model the portal parts with relationships (#[relationship] /
+//   #[relationship_target]) instead of copying PortalParts in Portal, PortalDestination and
+//   PortalCamera, so that despawning one part could clean up the others without the checks
+//   of despawn.rs (relationships don't exist in Bevy 0.15)
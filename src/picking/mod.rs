@@ -0,0 +1,295 @@
+//! Picking through portals with [bevy_picking]
+//!
+//! Pointers hovering a portal mesh are forwarded to a virtual pointer on the image of its
+//! [PortalCamera], one per pointer so that several touches can go through the same portal, so that the picking backends see the entities at the destination and
+//! send them [Over](bevy_picking::events::Over), [Out](bevy_picking::events::Out),
+//! [Click](bevy_picking::events::Click)...
+//!
+//! Portals seen through other portals (portals whose main camera is the [PortalCamera] of another
+//! portal) are picked too, see [PortalPickingSettings].
+//!
+//! Mirrors (see [PortalDestinationSource::CreateMirror](crate::portals::PortalDestinationSource::CreateMirror))
+//! need no special handling: the image is mapped in screen space, so the pointer has the same
+//! position on the image as on the screen, where the reflection is seen.
+//!
+//! The portal meshes must be pickable by a backend (for example `MeshPickingPlugin`).
+//! The virtual pointers are one frame late, and [PortalViewerMode::FixedCamera] portals are not supported.
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use bevy_math::Vec2;
+use bevy_picking::{
+    focus::HoverMap,
+    pointer::{Location, PointerAction, PointerId, PointerInput, PointerLocation},
+    PickSet,
+};
+use bevy_reflect::{std_traits::ReflectDefault, Reflect};
+use bevy_render::{camera::NormalizedRenderTarget, prelude::*};
+use bevy_window::{PrimaryWindow, Window};
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+
+use crate::portals::{Portal, PortalCamera, PortalViewerMode};
+
+/// Add the picking logic to [PortalsPlugin](crate::portals::PortalsPlugin)
+pub(crate) fn build_picking(app: &mut App) {
+    app.register_type::<PortalPointer>()
+        .register_type::<PortalPickingSettings>()
+        .init_resource::<PortalPickingSettings>()
+        .add_systems(
+            PreUpdate,
+            (despawn_portal_pointers, pick_through_portals)
+                .chain()
+                .before(PickSet::ProcessInput),
+        );
+}
+
+/// [Resource] to configure picking through portals.
+#[derive(Resource, Clone, Debug, Reflect)]
+#[reflect(Resource, Default)]
+pub struct PortalPickingSettings {
+    /// Maximum number of portals a pointer goes through, for portals seen through other portals
+    /// (portals whose main camera is the [PortalCamera] of another portal), defaults to 3.
+    pub max_depth: usize,
+}
+
+impl Default for PortalPickingSettings {
+    fn default() -> Self {
+        Self { max_depth: 3 }
+    }
+}
+
+/// High bits of the [Uuid] of the [PointerId::Custom] of the virtual pointers,
+/// offset by the pointer they replicate, the low bits are the bits of the portal camera entity.
+pub const PORTAL_POINTER_UUID_HIGH: u64 = 0xB0A7_A15E_9047_A15E;
+
+/// [Component] of a virtual pointer moving on the image of a [PortalCamera].
+///
+/// There is one virtual pointer per portal camera and per pointer going through it,
+/// so that simultaneous touches are replicated through portals.
+#[derive(Component, Clone, Copy, Debug, Reflect)]
+#[reflect(Component)]
+pub struct PortalPointer {
+    /// The entity with the [PortalCamera] component.
+    pub portal_camera: Entity,
+    /// The mouse, touch or custom pointer this virtual pointer replicates,
+    /// never a [PortalPointer] even for portals seen through other portals.
+    pub source: PointerId,
+}
+
+impl PortalPointer {
+    /// The [PointerId] of the virtual pointer of a portal camera replicating `source`,
+    /// deterministic so that creating portals stays deterministic.
+    pub fn pointer_id(portal_camera: Entity, source: PointerId) -> PointerId {
+        let source_bits = match source {
+            PointerId::Mouse => 0,
+            PointerId::Touch(id) => id.wrapping_add(1),
+            PointerId::Custom(uuid) => {
+                let (high, low) = uuid.as_u64_pair();
+                high ^ low
+            }
+        };
+        PointerId::Custom(Uuid::from_u64_pair(
+            PORTAL_POINTER_UUID_HIGH.wrapping_add(source_bits),
+            portal_camera.to_bits(),
+        ))
+    }
+}
+
+/// [System] that despawns the [PortalPointer]s of despawned [PortalCamera]s or pointers
+/// (for example touches that ended).
+pub fn despawn_portal_pointers(
+    mut commands: Commands,
+    portal_pointers: Query<(Entity, &PortalPointer)>,
+    portal_cameras: Query<(), With<PortalCamera>>,
+    pointers: Query<&PointerId, Without<PortalPointer>>,
+) {
+    for (pointer_entity, portal_pointer) in portal_pointers.iter() {
+        if !portal_cameras.contains(portal_pointer.portal_camera)
+            || !pointers.iter().any(|id| *id == portal_pointer.source)
+        {
+            commands.entity(pointer_entity).despawn();
+        }
+    }
+}
+
+/// [System] that forwards the [PointerInput]s of pointers hovering a [Portal] to a
+/// [PortalPointer] of its [PortalCamera], and removes the location of the virtual pointers
+/// of portals that are not hovered anymore.
+///
+/// The virtual pointers are spawned when a pointer first hovers a portal.
+/// Virtual pointers hovering a portal are forwarded too, up to [PortalPickingSettings::max_depth].
+#[allow(clippy::type_complexity, clippy::too_many_arguments)]
+pub fn pick_through_portals(
+    mut commands: Commands,
+    mut pointer_inputs: ParamSet<(EventReader<PointerInput>, EventWriter<PointerInput>)>,
+    hover_map: Res<HoverMap>,
+    settings: Res<PortalPickingSettings>,
+    portals: Query<&Portal>,
+    portal_cameras: Query<&PortalCamera>,
+    cameras: Query<&Camera>,
+    windows: Query<&Window>,
+    primary_window: Query<Entity, With<PrimaryWindow>>,
+    mut portal_pointers: Query<(&PortalPointer, &mut PointerLocation)>,
+) {
+    // Source pointer of the virtual pointers
+    let virtual_sources: HashMap<PointerId, PointerId> = portal_pointers
+        .iter()
+        .map(|(portal_pointer, _)| {
+            (
+                PortalPointer::pointer_id(portal_pointer.portal_camera, portal_pointer.source),
+                portal_pointer.source,
+            )
+        })
+        .collect();
+    let source_of = |pointer_id: &PointerId| {
+        virtual_sources
+            .get(pointer_id)
+            .copied()
+            .unwrap_or(*pointer_id)
+    };
+
+    // Portal cameras of the portals hovered by a pointer, from the last update of the hover map
+    let hovered_portal_cameras = |pointer_id: &PointerId| {
+        hover_map
+            .get(pointer_id)
+            .into_iter()
+            .flat_map(|hovered| hovered.keys())
+            .filter_map(|entity| portals.get(*entity).ok())
+            .map(|portal| portal.parts.portal_camera)
+            .collect::<Vec<_>>()
+    };
+
+    let forward_inputs = |inputs: &[PointerInput]| {
+        let mut forwarded_inputs = Vec::new();
+        for input in inputs {
+            let source = source_of(&input.pointer_id);
+            for portal_camera_entity in hovered_portal_cameras(&input.pointer_id) {
+                let Ok(portal_camera) = portal_cameras.get(portal_camera_entity) else {
+                    continue;
+                };
+                if let PortalViewerMode::FixedCamera(_) = portal_camera.viewer {
+                    continue;
+                }
+                // The main camera of a portal seen through another portal is a portal camera
+                let Ok(main_camera) = cameras.get(portal_camera.parts.main_camera) else {
+                    continue;
+                };
+                // The portal can be hovered through another camera than its main camera,
+                // for example in another window
+                if main_camera
+                    .target
+                    .normalize(primary_window.get_single().ok())
+                    .as_ref()
+                    != Some(&input.location.target)
+                {
+                    continue;
+                }
+                let Some(position) = portal_image_position(&input.location, main_camera, &windows)
+                else {
+                    continue;
+                };
+                forwarded_inputs.push((
+                    PortalPointer {
+                        portal_camera: portal_camera_entity,
+                        source,
+                    },
+                    PointerInput::new(
+                        PortalPointer::pointer_id(portal_camera_entity, source),
+                        Location {
+                            target: NormalizedRenderTarget::Image(portal_camera.image.clone()),
+                            position,
+                        },
+                        input.action,
+                    ),
+                ));
+            }
+        }
+        forwarded_inputs
+    };
+
+    let mut inputs: Vec<PointerInput> = pointer_inputs
+        .p0()
+        .read()
+        .filter(|input| !virtual_sources.contains_key(&input.pointer_id))
+        .cloned()
+        .collect();
+    let mut virtual_inputs = Vec::new();
+    for _ in 0..settings.max_depth {
+        let forwarded_inputs = forward_inputs(&inputs);
+        if forwarded_inputs.is_empty() {
+            break;
+        }
+        inputs = forwarded_inputs
+            .iter()
+            .map(|(_, input)| input.clone())
+            .collect();
+        virtual_inputs.extend(forwarded_inputs);
+    }
+
+    // Virtual pointers are spawned before the inputs are processed, see PickSet::ProcessInput
+    let mut spawned = HashSet::new();
+    for (portal_pointer, input) in &virtual_inputs {
+        if !virtual_sources.contains_key(&input.pointer_id) && spawned.insert(input.pointer_id) {
+            commands.spawn((input.pointer_id, *portal_pointer));
+        }
+    }
+
+    // Virtual pointers leave the destination when their portal is not hovered anymore
+    // by a pointer with the same source
+    let hovered: Vec<(Entity, PointerId)> = hover_map
+        .keys()
+        .flat_map(|pointer_id| {
+            let source = source_of(pointer_id);
+            hovered_portal_cameras(pointer_id)
+                .into_iter()
+                .map(move |portal_camera| (portal_camera, source))
+        })
+        .collect();
+    for (portal_pointer, mut location) in portal_pointers.iter_mut() {
+        if !hovered.contains(&(portal_pointer.portal_camera, portal_pointer.source))
+            && location.location.is_some()
+        {
+            location.location = None;
+        }
+    }
+
+    let mut writer = pointer_inputs.p1();
+    for (_, input) in virtual_inputs {
+        if let PointerAction::Moved { .. } = input.action {
+            writer.send(input);
+        } else {
+            // Presses happen where the virtual pointer is, move it there first
+            writer.send(PointerInput::new(
+                input.pointer_id,
+                input.location.clone(),
+                PointerAction::Moved { delta: Vec2::ZERO },
+            ));
+            writer.send(input);
+        }
+    }
+}
+
+/// Position on the image of a portal of a pointer at `location` on the render target of its
+/// main camera.
+///
+/// The image has the size of the viewport of the main camera in physical pixels,
+/// and is mapped in screen space on the portal mesh.
+pub fn portal_image_position(
+    location: &Location,
+    main_camera: &Camera,
+    windows: &Query<&Window>,
+) -> Option<Vec2> {
+    let scale_factor = match &location.target {
+        NormalizedRenderTarget::Window(window_ref) => {
+            windows.get(window_ref.entity()).ok()?.scale_factor()
+        }
+        _ => 1.,
+    };
+    let physical_position = location.position * scale_factor;
+    let viewport_position = main_camera
+        .viewport
+        .as_ref()
+        .map_or(Vec2::ZERO, |viewport| viewport.physical_position.as_vec2());
+    Some(physical_position - viewport_position)
+}
@@ -0,0 +1,209 @@
+//! Components and systems to open and close portals gracefully
+
+use bevy_app::prelude::*;
+use bevy_asset::prelude::*;
+use bevy_ecs::prelude::*;
+use bevy_pbr::prelude::*;
+use bevy_reflect::{std_traits::ReflectDefault, Reflect};
+use bevy_time::prelude::*;
+
+use super::*;
+
+/// Add the animation logic to [PortalsPlugin]
+pub(super) fn build_animation(app: &mut App) {
+    app.register_type::<PortalAnimation>()
+        .add_event::<PortalAnimationFinished>()
+        .add_systems(
+            Update,
+            (animate_portals, swap_closed_portal_materials).chain(),
+        );
+}
+
+/// [Component] to open and close a [Portal] with an animation.
+///
+/// The portal opens like an iris from the center of its mesh UVs (meshes without UVs just appear),
+/// or dissolves if it has a [PortalDissolve],
+/// and its [PortalCamera] is deactivated while it is closed.
+/// [CreatePortal] has no field for it: insert it on the portal entity, next to [CreatePortal]
+/// or later, it is animated once the portal is created.
+#[derive(Component, Clone, Reflect)]
+#[reflect(Component, Default)]
+pub struct PortalAnimation {
+    /// Current state of the animation, use [open](Self::open) and [close](Self::close) to change it.
+    pub state: PortalAnimationState,
+    /// Duration of the opening and closing animations, in seconds.
+    pub duration: f32,
+    /// How open the portal is, from 0 (closed) to 1 (open).
+    pub openness: f32,
+}
+
+impl Default for PortalAnimation {
+    fn default() -> Self {
+        Self {
+            state: PortalAnimationState::Open,
+            duration: 0.5,
+            openness: 1.,
+        }
+    }
+}
+
+impl PortalAnimation {
+    /// A closed portal starting to open.
+    pub fn opening(duration: f32) -> Self {
+        Self {
+            state: PortalAnimationState::Opening,
+            duration,
+            openness: 0.,
+        }
+    }
+
+    /// A closed portal, waiting for [open](Self::open).
+    pub fn closed(duration: f32) -> Self {
+        Self {
+            state: PortalAnimationState::Closed,
+            duration,
+            openness: 0.,
+        }
+    }
+
+    /// Starts opening the portal, if it is not open or opening.
+    pub fn open(&mut self) {
+        if matches!(
+            self.state,
+            PortalAnimationState::Closing | PortalAnimationState::Closed
+        ) {
+            self.state = PortalAnimationState::Opening;
+        }
+    }
+
+    /// Starts closing the portal, if it is not closed or closing.
+    pub fn close(&mut self) {
+        if matches!(
+            self.state,
+            PortalAnimationState::Opening | PortalAnimationState::Open
+        ) {
+            self.state = PortalAnimationState::Closing;
+        }
+    }
+}
+
+/// State of a [PortalAnimation].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Reflect)]
+pub enum PortalAnimationState {
+    Opening,
+    Open,
+    Closing,
+    Closed,
+}
+
+/// [Event] sent when a [PortalAnimation] finishes opening or closing.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct PortalAnimationFinished {
+    /// The entity with the [Portal] component.
+    pub portal: Entity,
+    /// [PortalAnimationState::Open] or [PortalAnimationState::Closed].
+    pub state: PortalAnimationState,
+}
+
+/// [System] that advances [PortalAnimation]s and updates the [PortalMaterial]s.
+///
+/// The [PortalCamera]s of closed portals don't render, see [PortalDeactivation::closed].
+#[allow(clippy::type_complexity)]
+pub fn animate_portals(
+    time: Res<Time>,
+    mut portals: Query<(
+        Entity,
+        &mut PortalAnimation,
+        &PortalBaseMaterial,
+        &mut PortalDeactivation,
+    )>,
+    mut materials: ResMut<Assets<PortalMaterial>>,
+    mut finished: EventWriter<PortalAnimationFinished>,
+) {
+    for (portal_entity, mut animation, portal_material, mut deactivation) in portals.iter_mut() {
+        let step = if animation.duration > 0. {
+            time.delta_secs() / animation.duration
+        } else {
+            1.
+        };
+        let openness = match animation.state {
+            PortalAnimationState::Opening => (animation.openness + step).min(1.),
+            PortalAnimationState::Open => 1.,
+            PortalAnimationState::Closing => (animation.openness - step).max(0.),
+            PortalAnimationState::Closed => 0.,
+        };
+        let finished_state = match animation.state {
+            PortalAnimationState::Opening if openness >= 1. => Some(PortalAnimationState::Open),
+            PortalAnimationState::Closing if openness <= 0. => Some(PortalAnimationState::Closed),
+            _ => None,
+        };
+        if let Some(state) = finished_state {
+            animation.state = state;
+            finished.send(PortalAnimationFinished {
+                portal: portal_entity,
+                state,
+            });
+        }
+        if animation.openness != openness {
+            animation.openness = openness;
+        }
+
+        // Only touch the material when needed, it rebuilds its bind group
+        if let Some(material) = materials.get(&portal_material.0) {
+            if material.openness != openness {
+                if let Some(material) = materials.get_mut(&portal_material.0) {
+                    material.openness = openness;
+                }
+            }
+        }
+
+        let closed = animation.state == PortalAnimationState::Closed;
+        if deactivation.closed != closed {
+            deactivation.closed = closed;
+        }
+    }
+}
+
+/// [System] that shows the [PortalOriginalMaterial] of closed portals instead of their
+/// [PortalMaterial], and the [PortalMaterial] again when they start opening.
+#[allow(clippy::type_complexity)]
+pub fn swap_closed_portal_materials(
+    mut commands: Commands,
+    portals: Query<
+        (
+            Entity,
+            &PortalAnimation,
+            &PortalBaseMaterial,
+            &PortalOriginalMaterial,
+            Has<MeshMaterial3d<PortalMaterial>>,
+            Option<&MeshMaterial3d<StandardMaterial>>,
+        ),
+        Changed<PortalAnimation>,
+    >,
+) {
+    for (
+        portal_entity,
+        animation,
+        portal_material,
+        original_material,
+        has_portal_material,
+        standard_material,
+    ) in portals.iter()
+    {
+        let closed = animation.state == PortalAnimationState::Closed;
+        if closed && has_portal_material {
+            commands
+                .entity(portal_entity)
+                .remove::<MeshMaterial3d<PortalMaterial>>()
+                .insert(MeshMaterial3d(original_material.0.clone()));
+        } else if !closed
+            && !has_portal_material
+            && standard_material.is_some_and(|material| material.0 == original_material.0)
+        {
+            commands
+                .entity(portal_entity)
+                .remove::<MeshMaterial3d<StandardMaterial>>()
+                .insert(MeshMaterial3d(portal_material.0.clone()));
+        }
+    }
+}
@@ -1,11 +1,29 @@
 //! Components and structs to create portals without caring about their implementation
+#![allow(deprecated)] // Derived impls of CreatePortalBundle use the deprecated struct
 
 use bevy_app::prelude::*;
-use bevy_color::{palettes::basic::GRAY, Color};
-use bevy_ecs::prelude::*;
-use bevy_reflect::Reflect;
-use bevy_render::{prelude::*, primitives::HalfSpace, render_resource::Face, view::RenderLayers};
-use bevy_transform::prelude::*;
+#[cfg(feature = "scene")]
+use bevy_asset::Handle;
+use bevy_color::{palettes::basic::GRAY, Color, LinearRgba};
+use bevy_ecs::{
+    entity::{EntityMapper, MapEntities},
+    prelude::*,
+    reflect::ReflectMapEntities,
+};
+use bevy_math::{Dir3, Quat, Vec3};
+use bevy_reflect::{std_traits::ReflectDefault, Reflect};
+use bevy_render::{
+    camera::ScalingMode,
+    mesh::MeshAabb,
+    prelude::*,
+    primitives::HalfSpace,
+    render_resource::Face,
+    view::{RenderLayers, VisibilitySystems},
+};
+#[cfg(feature = "scene")]
+use bevy_scene::prelude::*;
+use bevy_transform::{prelude::*, TransformSystem};
+use std::{f32::consts::PI, sync::Arc};
 
 use super::*;
 
@@ -16,6 +34,8 @@ pub struct PortalsPlugin {
     /// Defaults to [PortalsCheckMode::AlwaysCheck].
     pub check_create: PortalsCheckMode,
     /// If true, should check if a [PortalCamera] despawned or has the wrong components with [check_portal_camera_despawn]
+    ///
+    /// Ignored if [rendering](Self::rendering) is false, the portal cameras are then not [Camera]s.
     pub check_portal_camera_despawn: bool,
     /// What to do when there is a problem getting a [PortalParts]
     ///
@@ -27,6 +47,29 @@ pub struct PortalsPlugin {
     /// Defaults/`None` to despawn all entities and children with a warning, except for the main camera.
     /// Will be added as a [Resource], can be changed during execution.
     pub despawn_strategy: Option<PortalPartsDespawnStrategy>,
+    /// Number of frames to wait for the viewport of a main camera to keep the same size
+    /// before resizing the images of its portals, see [PortalImageResizeDelay].
+    ///
+    /// Defaults to 0.
+    pub resize_delay: u32,
+    /// Asset path of a shader replacing the portal shader, see [PortalShaderOverride].
+    ///
+    /// It must keep the same bind group layout as `assets/portal.wgsl` in this crate.
+    /// It is loaded through the [AssetServer](bevy_asset::AssetServer), so it hot-reloads
+    /// with the `file_watcher` feature of Bevy, and the shader of this crate is used if it
+    /// fails to load.
+    /// Defaults to `None` to use the shader of this crate.
+    pub shader_path: Option<String>,
+    /// Whether portals are rendered.
+    ///
+    /// If false, for example on a headless server without a GPU that still needs to know where
+    /// portals lead, no image, material nor [Camera] is created: the [PortalCamera]s are only
+    /// entities with a [Transform] and a [Frustum](bevy_render::primitives::Frustum),
+    /// see [PortalsHeadless].
+    /// Only the creation, update and despawn logic is added, without the render plugins.
+    ///
+    /// Defaults to true.
+    pub rendering: bool,
 }
 
 impl Default for PortalsPlugin {
@@ -35,6 +78,9 @@ impl Default for PortalsPlugin {
             check_create: PortalsCheckMode::AlwaysCheck,
             check_portal_camera_despawn: true,
             despawn_strategy: None,
+            resize_delay: 0,
+            shader_path: None,
+            rendering: true,
         }
     }
 }
@@ -44,23 +90,104 @@ impl PortalsPlugin {
         check_create: PortalsCheckMode::CheckAfterStartup,
         check_portal_camera_despawn: false,
         despawn_strategy: Some(PortalPartsDespawnStrategy::PANIC),
+        resize_delay: 0,
+        shader_path: None,
+        rendering: true,
     };
 }
 
 impl Plugin for PortalsPlugin {
     fn build(&self, app: &mut App) {
-        build_material(app);
+        configure_portal_system_sets(app);
+        if !self.rendering {
+            build_events(app);
+            build_placement(app);
+            build_headless(app, &self.check_create);
+            build_despawn(app, self.despawn_strategy.clone(), false);
+            return;
+        }
+        build_material(app, self.shader_path.clone());
+        build_lit(app);
         build_projection(app);
+        build_events(app);
+        build_placement(app);
+        build_layers(app);
+        build_views(app);
         build_create(app, &self.check_create);
-        build_update(app);
+        build_update(app, self.resize_delay);
+        build_skinned(app);
         build_despawn(
             app,
             self.despawn_strategy.clone(),
             self.check_portal_camera_despawn,
         );
+        build_animation(app);
+        build_time(app);
+        build_decal(app);
+        build_capture(app);
+        build_depth(app);
+        build_impostor(app);
+        build_view_info(app);
+        build_capabilities(app);
+        #[cfg(feature = "gizmos")]
+        build_gizmos(app);
+        #[cfg(feature = "ui")]
+        build_ui(app);
+        #[cfg(feature = "picking")]
+        crate::picking::build_picking(app);
     }
 }
 
+/// [SystemSet]s of the systems added by [PortalsPlugin], to order your own systems relative
+/// to the portal updates.
+///
+/// In [PostStartup] and [PostUpdate], [Create](Self::Create), [UpdateCameras](Self::UpdateCameras)
+/// and [UpdateVisibility](Self::UpdateVisibility) run in this order after
+/// [TransformPropagate](TransformSystem::TransformPropagate).
+/// For example, a camera-follow system moving a main camera in [PostUpdate] should run
+/// `.after(TransformSystem::TransformPropagate).before(PortalSystems::UpdateCameras)`
+/// (and update its [GlobalTransform] itself), or simply run in [Update].
+///
+/// With main cameras moved in [FixedUpdate] and transform interpolation, the interpolated
+/// [Transform]s must be written before [TransformPropagate](TransformSystem::TransformPropagate)
+/// so that the portal cameras follow the interpolated cameras instead of the fixed steps:
+/// interpolation systems running in [PostUpdate] should run
+/// `.before(TransformSystem::TransformPropagate)`, which also orders them before
+/// [UpdateCameras](Self::UpdateCameras).
+#[derive(SystemSet, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PortalSystems {
+    /// Creation of the portals from [CreatePortal] components, see [create_portals].
+    Create,
+    /// Update of the [PortalCamera]s, their transforms, frusta, viewports and images,
+    /// see [update_portal_cameras], and of what depends on them (impostors, destination depths,
+    /// UI portals).
+    UpdateCameras,
+    /// Systems reading the visibility computed by Bevy, after
+    /// [CheckVisibility](VisibilitySystems::CheckVisibility): deactivation
+    /// of the portal cameras which are not needed and [PortalViewInfo]s.
+    UpdateVisibility,
+    /// Checks of the [PortalCamera]s despawns, in [Update], see [check_portal_camera_despawn].
+    Despawn,
+}
+
+/// Order the [PortalSystems]
+fn configure_portal_system_sets(app: &mut App) {
+    app.configure_sets(
+        PostStartup,
+        PortalSystems::Create.after(TransformSystem::TransformPropagate),
+    )
+    .configure_sets(
+        PostUpdate,
+        (
+            PortalSystems::Create,
+            PortalSystems::UpdateCameras,
+            PortalSystems::UpdateVisibility.after(VisibilitySystems::CheckVisibility),
+        )
+            .chain()
+            .after(TransformSystem::TransformPropagate),
+    );
+}
+
 /// Whether and when [PortalsPlugin] should check for entities with [CreatePortal] components to create a portal using [create_portals].
 #[derive(PartialEq, Eq, Clone)]
 pub enum PortalsCheckMode {
@@ -76,7 +203,8 @@ pub enum PortalsCheckMode {
 ///
 /// Defaults to despawn all parts with a warning (without their children), except for the main camera.
 #[derive(Resource, Clone, Reflect)]
-#[reflect(Resource)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[reflect(Resource, Default)]
 pub struct PortalPartsDespawnStrategy {
     pub main_camera: PortalPartDespawnStrategy,
     pub portal: PortalPartDespawnStrategy,
@@ -122,6 +250,8 @@ impl PortalPartsDespawnStrategy {
 
 /// Strategy to despawn a portal part if it is not yet despawned
 #[derive(Default, PartialEq, Eq, Clone, Reflect)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[reflect(Default)]
 pub enum PortalPartDespawnStrategy {
     /// Despawn the entity and all of its children with a warning
     WarnThenDespawnWithChildren,
@@ -180,24 +310,139 @@ pub struct CreatePortalBundle {
 /// The portal will be created after the next check (see [PortalsCheckMode]), if it has the other components in [CreatePortalBundle].
 ///
 /// Requires [Mesh3d] to define the mesh of the portal. Indirectly requires [Transform] to locate the portal.
-#[derive(Component, Clone)]
+///
+/// Can be authored in scenes, the fields that can't be reflected
+/// ([cull_mode](Self::cull_mode), [portal_camera_insert](Self::portal_camera_insert), the
+/// [HalfSpace] of the [PortalMode] and [PortalDestinationSource::Resolve]) then use their default.
+/// With the `serde` feature it can also be serialized, without [projection](Self::projection),
+/// [portal_camera_insert](Self::portal_camera_insert), the images of the effects and
+/// [PortalDestinationSource::Resolve].
+#[derive(Component, Clone, Reflect)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+#[reflect(Component, Default, MapEntities)]
 #[require(Mesh3d)]
 pub struct CreatePortal {
     /// Where the portal should lead to.
-    pub destination: AsPortalDestination,
+    pub destination: PortalDestinationSource,
+    /// Where the back faces of the portal mesh should lead to, defaults to `None`.
+    ///
+    /// If set, a second portal is created on a hidden child of the portal, facing the other way,
+    /// with its own [PortalCamera] rendering to the back faces.
+    /// Both faces are then visible, [cull_mode](Self::cull_mode) is ignored.
+    pub back_destination: Option<PortalDestinationSource>,
+    /// If true and [back_destination](Self::back_destination) is `None`, the back faces of the
+    /// portal mesh lead to the back of the destination, like a hole in space seen from both
+    /// sides, defaults to false.
+    ///
+    /// Works like [back_destination](Self::back_destination), with a second [PortalCamera] and
+    /// image selected per fragment depending on the facing, so both sides can be visible at the
+    /// same time (for example in a mirror).
+    /// For mirrors, the back faces are a mirror too (see [MirrorConfig::back]).
+    pub double_sided: bool,
     /// What technique to use to render the portal effect, and how to define the
     /// frustum when applicable.
     pub portal_mode: PortalMode,
     /// The camera that will see this portal, defaults to the first camera found.
+    ///
+    /// If `None`, the portal will switch to another camera if its main camera despawns,
+    /// see [rebind_portal_main_cameras].
     pub main_camera: Option<Entity>,
+    /// If true, the portal is also seen by the other [Camera3d](bevy_core_pipeline::prelude::Camera3d)s,
+    /// through a [PortalView] for each of them, defaults to false.
+    ///
+    /// Views are created and removed as main cameras are spawned and despawned,
+    /// see [update_portal_views].
+    /// The portal mesh and the meshes of the views are each moved to their own render layer,
+    /// which is added to the [RenderLayers] of their main camera.
+    /// The views don't have back faces (see [back_destination](Self::back_destination)),
+    /// border nor debug elements.
+    pub main_camera_views: bool,
+    /// Entity to use for the [PortalCamera] instead of spawning a new one, defaults to `None`.
+    ///
+    /// Useful to keep the entities of the portal parts stable, for example for rollback netcode,
+    /// with an entity reserved beforehand (see [Commands::spawn_empty]).
+    pub portal_camera: Option<Entity>,
+    /// Called with the [PortalCamera] entity when the portal is created, to insert extra
+    /// components on it (fog, post-processing settings, markers...), defaults to `None`.
+    ///
+    /// Called after the components of the crate are inserted, so it can override them.
+    /// Also called for the back faces (see [back_destination](Self::back_destination)).
+    #[reflect(ignore)]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub portal_camera_insert: Option<PortalCameraInsert>,
+    /// Which entity the [PortalCamera] follows, defaults to the main camera.
+    pub viewer: PortalViewerMode,
+    /// Projection of the [PortalCamera], defaults to `None` to copy the main camera's projection.
+    ///
+    /// Main cameras using their own projection component instead of [Projection] need a
+    /// [PortalProjection::Custom] here.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub projection: Option<PortalProjection>,
     /// Whether to cull the “front”, “back” or neither side of a the portal mesh.
     ///
     /// If set to `None`, the two sides of the portal are visible and work as a portal.
     ///
     /// Defaults to `Some(Face::Back)`, see [StandardMaterial](bevy_pbr::StandardMaterial).
+    ///
+    /// The faces are swapped while the portal has a negative scale (an odd number of negative
+    /// axes, for example a mesh mirrored in a modeling tool), as it flips the winding of the
+    /// triangles, see [portal_cull_mode].
+    #[reflect(ignore)]
+    #[cfg_attr(feature = "serde", serde(with = "super::serialization::face"))]
     pub cull_mode: Option<Face>,
     /// Render layer used by the [PortalCamera], and debug elements.
+    #[cfg_attr(feature = "serde", serde(with = "super::serialization::render_layers"))]
     pub render_layer: RenderLayers,
+    /// If true, a free render layer is taken from the [PortalLayerAllocator] and used instead
+    /// of [render_layer](Self::render_layer), defaults to false.
+    ///
+    /// The layer is put on the destination and all its descendants (see [PortalAllocatedLayer]),
+    /// so the scene behind the portal should be spawned as children of the destination.
+    /// The back faces (see [back_destination](Self::back_destination)) get their own layer,
+    /// except with [double_sided](Self::double_sided) where they share it.
+    /// A destination shared by several portals is on the layers of all of them.
+    pub allocate_render_layer: bool,
+    /// Which settings of the main camera the [PortalCamera] copies, to trade quality for cost.
+    pub render_quality: PortalRenderQuality,
+    /// If true, the [PortalCamera] only renders the region of its image covered by the portal
+    /// on the main camera's screen, see [update_portal_camera_viewports].
+    ///
+    /// Only works with perspective projections, defaults to false.
+    pub scissor_to_portal: bool,
+    /// Animated distortion of the portal image, disabled by default.
+    pub ripple: PortalRipple,
+    /// Heat-haze distortion of the portal image, disabled by default.
+    pub heat_haze: Option<PortalHeatHaze>,
+    /// Refraction of the portal image with a normal map, disabled by default.
+    pub refraction: Option<PortalRefraction>,
+    /// Shadows of directional lights received by the portal surface, disabled by default.
+    pub shadows: Option<PortalShadows>,
+    /// If true, the portal outputs the depth of what the [PortalCamera] sees, reprojected for the
+    /// main camera, instead of the depth of the portal mesh, defaults to false.
+    ///
+    /// Effects intersecting the portal (particles, decals, fog...) are then occluded by the
+    /// content of the destination.
+    /// Disables MSAA on the [PortalCamera] (see [PortalDestinationDepth]), and updates the
+    /// material every frame where the cameras move.
+    /// The depth of the portal mesh is still used if the main camera has a depth prepass.
+    /// Not supported on the back faces, see [back_destination](Self::back_destination).
+    pub destination_depth: bool,
+    /// Dissolve of the portal's edges, disabled by default.
+    pub dissolve: Option<PortalDissolve>,
+    /// Opacity of the portal, to blend the destination with what's behind the portal mesh,
+    /// defaults to 1 (opaque).
+    pub alpha: f32,
+    /// Color the portal image is filled with until the [PortalCamera] renders to it,
+    /// to avoid a flash at spawn, defaults to `None` for transparent black.
+    ///
+    /// The image is filled again with transparent black when it is resized.
+    pub initial_color: Option<Color>,
+    /// Visible border around the portal, defaults to None.
+    pub border: Option<PortalBorder>,
+    /// Strategy to despawn the parts of this portal when one of them is missing,
+    /// overrides the global [PortalPartsDespawnStrategy] resource, defaults to None.
+    pub despawn_strategy: Option<PortalPartsDespawnStrategy>,
     /// Configures debug elements, defaults to None.
     pub debug: Option<DebugPortal>,
 }
@@ -205,36 +450,280 @@ pub struct CreatePortal {
 impl Default for CreatePortal {
     fn default() -> Self {
         Self {
-            destination: AsPortalDestination::Create(CreatePortalDestination::default()),
+            destination: PortalDestinationSource::default(),
+            back_destination: None,
+            double_sided: false,
             portal_mode: PortalMode::default(),
             main_camera: None,
+            main_camera_views: false,
+            portal_camera: None,
+            portal_camera_insert: None,
+            viewer: PortalViewerMode::default(),
+            projection: None,
             cull_mode: Some(Face::Back),
             render_layer: RenderLayers::default(),
+            allocate_render_layer: false,
+            render_quality: PortalRenderQuality::default(),
+            scissor_to_portal: false,
+            ripple: PortalRipple::default(),
+            heat_haze: None,
+            refraction: None,
+            shadows: None,
+            destination_depth: false,
+            dissolve: None,
+            alpha: 1.,
+            initial_color: None,
+            border: None,
+            despawn_strategy: None,
             debug: None,
         }
     }
 }
 
+impl MapEntities for CreatePortal {
+    fn map_entities<M: EntityMapper>(&mut self, entity_mapper: &mut M) {
+        self.destination.map_entities(entity_mapper);
+        if let Some(back_destination) = &mut self.back_destination {
+            back_destination.map_entities(entity_mapper);
+        }
+        for entity in self.main_camera.iter_mut().chain(&mut self.portal_camera) {
+            *entity = entity_mapper.map_entity(*entity);
+        }
+        if let PortalViewerMode::Entity(viewer) = &mut self.viewer {
+            *viewer = entity_mapper.map_entity(*viewer);
+        }
+    }
+}
+
+impl CreatePortal {
+    /// Portal to a destination created at `transform`, see [PortalDestinationSource::Create].
+    pub fn to(transform: Transform) -> Self {
+        Self {
+            destination: PortalDestinationSource::Create(transform.into()),
+            ..Default::default()
+        }
+    }
+
+    /// Portal to an existing `destination` entity, see [PortalDestinationSource::Use].
+    pub fn to_entity(destination: Entity) -> Self {
+        Self {
+            destination: PortalDestinationSource::Use(destination),
+            ..Default::default()
+        }
+    }
+
+    /// Mirror, see [PortalDestinationSource::CreateMirror].
+    pub fn mirror() -> Self {
+        Self {
+            destination: PortalDestinationSource::CreateMirror,
+            ..Default::default()
+        }
+    }
+
+    /// Mirror reflecting on another plane than the default one, see [MirrorConfig].
+    pub fn mirror_with(config: MirrorConfig) -> Self {
+        Self {
+            destination: PortalDestinationSource::CreateMirrorWith(config),
+            ..Default::default()
+        }
+    }
+
+    /// Preset for a reflective water surface: a mirror with [PortalRipple::WATER] ripples.
+    ///
+    /// The portal mesh should face up, for example a [Rectangle](bevy_math::primitives::Rectangle)
+    /// rotated by `-PI / 2` around the X axis.
+    pub fn water_mirror() -> Self {
+        Self {
+            destination: PortalDestinationSource::CreateMirror,
+            ripple: PortalRipple::WATER,
+            ..Default::default()
+        }
+    }
+
+    /// Preset for a security camera monitor: the portal mesh shows the feed of a camera
+    /// staying at `camera` relative to `destination`, see [PortalViewerMode::FixedCamera].
+    pub fn security_camera(destination: Entity, camera: Transform) -> Self {
+        Self {
+            destination: PortalDestinationSource::Use(destination),
+            viewer: PortalViewerMode::FixedCamera(camera),
+            ..Default::default()
+        }
+    }
+
+    /// Preset for a minimap: the portal mesh shows an orthographic top-down view of a
+    /// `extent` by `extent` square centered on `destination`, seen from `height` above it.
+    ///
+    /// To show the minimap in the UI instead, hide the portal mesh and use a `UiPortal`
+    /// (requires the `ui` feature).
+    pub fn minimap(destination: Entity, extent: f32, height: f32) -> Self {
+        Self {
+            destination: PortalDestinationSource::Use(destination),
+            viewer: PortalViewerMode::FixedCamera(
+                Transform::from_xyz(0., height, 0.).looking_at(Vec3::ZERO, Vec3::NEG_Z),
+            ),
+            projection: Some(PortalProjection::Orthographic(OrthographicProjection {
+                scaling_mode: ScalingMode::Fixed {
+                    width: extent,
+                    height: extent,
+                },
+                far: height * 2.,
+                ..OrthographicProjection::default_3d()
+            })),
+            ..Default::default()
+        }
+    }
+}
+
 /// How to create the [PortalDestination].
-#[derive(Clone)]
-pub enum AsPortalDestination {
+#[derive(Clone, Reflect)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[reflect(Default)]
+pub enum PortalDestinationSource {
     /// Use an already existing entity.
     Use(Entity),
     /// Create a [PortalDestination] with the given configuration.
     Create(CreatePortalDestination),
-    /// Create a [PortalDestination] to make a mirror.
+    /// Create a [PortalDestination] to make a mirror, reflecting on the plane `z = 0` of the
+    /// portal (the default [MirrorConfig]).
     ///
     /// Will set the [PortalDestination] as a child of the [Portal] entity
+    ///
+    /// This uses the generic portal logic (the destination is the portal rotated by PI around
+    /// an axis of the mirror plane, see [MirrorConfig::destination_transform]),
+    /// so the mirror mesh can be any shape, not only planar.
+    /// There is no specialized path for planar mirrors yet (a camera reflected on the mirror
+    /// plane with an oblique near clip plane): objects crossing the mirror plane are only culled
+    /// by the [HalfSpace] of the [PortalMode], not clipped, so they can be seen behind the mirror.
     CreateMirror,
+    /// Like [CreateMirror](Self::CreateMirror), reflecting on the plane described by the
+    /// [MirrorConfig].
+    CreateMirrorWith(MirrorConfig),
+    /// Find or create the destination with a [PortalDestinationResolver], for kinds of
+    /// destinations this crate doesn't know about.
+    ///
+    /// Resolved by [resolve_portal_destinations] before the portal is created,
+    /// the creation is retried at the next check if the resolver returns `None`.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    Resolve(
+        #[reflect(ignore)]
+        #[reflect(default = "unresolved_destination")]
+        Arc<dyn PortalDestinationResolver>,
+    ),
+    //TODO: specialized path for planar mirrors, with the portal camera reflected on the mirror
+    // plane and an oblique near clip plane (instead of the HalfSpace frustum, which only culls
+    // and doesn't clip objects crossing the mirror plane).
+    //TODO: CreateEnvironmentProbeMirror, for rough/decorative mirrors, rendering the destination
+    // in an EnvironmentMapLight at the mirror location instead of using a planar portal camera
+    // every frame. Needs the CubemapImage mode and prefiltering of the cubemap at runtime,
+    // which Bevy doesn't provide yet.
+}
+
+impl Default for PortalDestinationSource {
+    fn default() -> Self {
+        PortalDestinationSource::Create(CreatePortalDestination::default())
+    }
+}
+
+impl PortalDestinationSource {
+    /// The [MirrorConfig] of a [CreateMirror](Self::CreateMirror) or
+    /// [CreateMirrorWith](Self::CreateMirrorWith) destination, `None` for other destinations.
+    pub fn mirror_config(&self) -> Option<MirrorConfig> {
+        match self {
+            PortalDestinationSource::CreateMirror => Some(MirrorConfig::default()),
+            PortalDestinationSource::CreateMirrorWith(mirror_config) => Some(*mirror_config),
+            _ => None,
+        }
+    }
+}
+
+impl MapEntities for PortalDestinationSource {
+    fn map_entities<M: EntityMapper>(&mut self, entity_mapper: &mut M) {
+        match self {
+            PortalDestinationSource::Use(entity) => *entity = entity_mapper.map_entity(*entity),
+            PortalDestinationSource::Create(CreatePortalDestination {
+                parent: Some(parent),
+                ..
+            }) => *parent = entity_mapper.map_entity(*parent),
+            _ => (),
+        }
+    }
+}
+
+/// Function inserting extra components on a [PortalCamera], see [CreatePortal::portal_camera_insert].
+pub type PortalCameraInsert = Arc<dyn Fn(&mut EntityCommands) + Send + Sync>;
+
+/// Custom way to get the destination of a portal, see [PortalDestinationSource::Resolve].
+///
+/// For example to use the nearest spawn point, or a named anchor from level data.
+pub trait PortalDestinationResolver: Send + Sync + 'static {
+    /// Returns the destination entity for the portal entity `portal`, can spawn it.
+    ///
+    /// Returns `None` if the destination is not available yet.
+    fn resolve(&self, portal: Entity, world: &mut World) -> Option<Entity>;
+}
+
+/// Resolver used when a [PortalDestinationSource::Resolve] comes from reflected data,
+/// resolvers can't be reflected.
+fn unresolved_destination() -> Arc<dyn PortalDestinationResolver> {
+    struct UnresolvedDestination;
+    impl PortalDestinationResolver for UnresolvedDestination {
+        fn resolve(&self, _portal: Entity, _world: &mut World) -> Option<Entity> {
+            None
+        }
+    }
+    Arc::new(UnresolvedDestination)
+}
+
+/// Former name of [PortalDestinationSource].
+#[deprecated(since = "0.8.0", note = "Renamed to `PortalDestinationSource`.")]
+pub type AsPortalDestination = PortalDestinationSource;
+
+/// Which entity a [PortalCamera] follows, as if it was looking through the portal.
+#[derive(Clone, Copy, Default, PartialEq, Debug, Reflect)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[reflect(Default)]
+pub enum PortalViewerMode {
+    /// The portal camera follows the main camera, the portal mesh shows what the main camera
+    /// sees through it.
+    #[default]
+    MainCamera,
+    /// The portal camera follows a "virtual viewer" entity relative to the portal, its
+    /// image can be displayed elsewhere than on the portal mesh (for example in a UI node,
+    /// see `UiPortal` with the `ui` feature).
+    ///
+    /// The main camera is still used to define the projection and the size of the image.
+    Entity(Entity),
+    /// The portal camera doesn't follow anything and stays at this transform relative to the
+    /// destination, like a security camera.
+    ///
+    /// The image is mapped on the portal mesh using its UVs, like a monitor showing the feed,
+    /// and the frustum is not modified by the [PortalMode].
+    FixedCamera(Transform),
 }
 
 /// [PortalDestination] to be created
-#[derive(Clone, Default)]
+#[derive(Clone, Default, Reflect)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+#[reflect(Default)]
 pub struct CreatePortalDestination {
     /// Where to create the destination of the portal
     pub transform: Transform,
     ///Entity to use as a parent of the [PortalDestination]
     pub parent: Option<Entity>,
+    /// Scene spawned as children of the [PortalDestination], and despawned with it.
+    ///
+    /// The scene is isolated on a render layer taken from the [PortalLayerAllocator], unless
+    /// [CreatePortal::render_layer] is set to something else than the default layer,
+    /// see [CreatePortal::allocate_render_layer].
+    #[cfg(feature = "scene")]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub scene: Option<Handle<Scene>>,
+    /// If true, the destination is moved to a free slot of the [PortalDestinationPlacement],
+    /// far from the other destinations, and [transform](Self::transform) is relative to that slot.
+    ///
+    /// Defaults to false.
+    pub auto_place: bool,
     //TODO: pub spawn_as_children: something like an EntityCommand?
 }
 
@@ -247,9 +736,212 @@ impl From<Transform> for CreatePortalDestination {
     }
 }
 
+#[cfg(feature = "scene")]
+impl CreatePortalDestination {
+    /// Destination at `transform` with a `scene` behind it, see [CreatePortalDestination::scene].
+    pub fn with_scene(transform: Transform, scene: Handle<Scene>) -> Self {
+        Self {
+            transform,
+            scene: Some(scene),
+            ..Default::default()
+        }
+    }
+}
+
+/// Plane of a mirror created with [PortalDestinationSource::CreateMirrorWith], in the local space
+/// of the portal entity.
+///
+/// The default is the plane `z = 0` facing `+Z`, like a [Rectangle](bevy_math::primitives::Rectangle),
+/// use [MirrorConfig::facing] for floors, ceilings or meshes with another orientation
+/// instead of rotating the portal entity.
+/// [PortalMode::MaskedImageHalfSpaceFrustum] with `None` assumes the default plane,
+/// set the [HalfSpace] to the mirror plane otherwise.
+#[derive(Clone, Copy, PartialEq, Debug, Reflect)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+#[reflect(Default)]
+pub struct MirrorConfig {
+    /// Normal of the mirror plane, on the reflective side.
+    pub normal: Dir3,
+    /// Distance of the mirror plane from the origin of the portal entity, along [normal](Self::normal).
+    pub offset: f32,
+    /// Axis of the mirror plane the destination is rotated around, the reflected image is
+    /// flipped across it. Projected on the mirror plane,
+    /// any axis of the plane is used if it is parallel to the normal.
+    pub axis: Dir3,
+    /// Curvature of the mirror, defaults to [MirrorCurvature::Flat].
+    pub curvature: MirrorCurvature,
+}
+
+impl Default for MirrorConfig {
+    fn default() -> Self {
+        Self {
+            normal: Dir3::Z,
+            offset: 0.,
+            axis: Dir3::Y,
+            curvature: MirrorCurvature::Flat,
+        }
+    }
+}
+
+/// Curvature of a mirror, see [MirrorConfig::curvature].
+///
+/// Curved mirrors are approximated with a planar mirror whose destination is scaled by the
+/// inverse of the [magnification](Self::magnification) (see [portal_to_destination_scale]):
+/// a convex mirror shows a smaller and wider view, a concave mirror a bigger one.
+/// The distortion towards the edges of the mirror is not reproduced, the mirror mesh should
+/// still be the curved mesh so that it is shaded where it is seen.
+/// The scale is applied along the local axes of the portal, so [MirrorConfig::normal] and
+/// [MirrorConfig::axis] should be axes of the portal's local space.
+//TODO: exact reflections with a cubemap rendered at the mirror and a per-fragment lookup of
+// the reflected direction, see the CubemapImage TODO of PortalDestinationSource.
+#[derive(Clone, Copy, Default, PartialEq, Debug, Reflect)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[reflect(Default)]
+pub enum MirrorCurvature {
+    /// Planar mirror.
+    #[default]
+    Flat,
+    /// Section of a sphere, like a security dome mirror.
+    ///
+    /// Convex if `radius` is positive, concave if it is negative.
+    /// The magnification depends on the distance of what is reflected, `viewing_distance` is
+    /// the typical distance of the reflected objects from the mirror.
+    Sphere { radius: f32, viewing_distance: f32 },
+    /// Section of a cylinder curved around [MirrorConfig::axis], like a funhouse mirror
+    /// stretching or squashing what is reflected across that axis.
+    ///
+    /// Convex if `radius` is positive, concave if it is negative, see [MirrorCurvature::Sphere]
+    /// for `viewing_distance`.
+    Cylinder { radius: f32, viewing_distance: f32 },
+}
+
+impl MirrorCurvature {
+    /// Highest magnification of a concave mirror, reached when the reflected objects are near
+    /// its focal point.
+    pub const MAX_MAGNIFICATION: f32 = 10.;
+
+    /// Approximate magnification of what is reflected at the viewing distance, 1 for
+    /// [MirrorCurvature::Flat].
+    ///
+    /// Uses the mirror equation with a focal length of half the radius, the inverted image of
+    /// objects beyond the focal point of a concave mirror is not reproduced.
+    pub fn magnification(&self) -> f32 {
+        let (MirrorCurvature::Sphere {
+            radius,
+            viewing_distance,
+        }
+        | MirrorCurvature::Cylinder {
+            radius,
+            viewing_distance,
+        }) = *self
+        else {
+            return 1.;
+        };
+        // Negative for convex mirrors
+        let focal_length = -radius / 2.;
+        if focal_length.abs() <= f32::EPSILON {
+            return 1.;
+        }
+        (focal_length / (focal_length - viewing_distance))
+            .abs()
+            .clamp(1. / Self::MAX_MAGNIFICATION, Self::MAX_MAGNIFICATION)
+    }
+
+    /// The same curvature seen from the other side of the mirror.
+    pub fn back(&self) -> Self {
+        match *self {
+            MirrorCurvature::Flat => MirrorCurvature::Flat,
+            MirrorCurvature::Sphere {
+                radius,
+                viewing_distance,
+            } => MirrorCurvature::Sphere {
+                radius: -radius,
+                viewing_distance,
+            },
+            MirrorCurvature::Cylinder {
+                radius,
+                viewing_distance,
+            } => MirrorCurvature::Cylinder {
+                radius: -radius,
+                viewing_distance,
+            },
+        }
+    }
+}
+
+impl MirrorConfig {
+    /// Mirror plane going through the origin of the portal entity and facing `normal`,
+    /// for example [Dir3::Y] for a [Plane3d](bevy_math::primitives::Plane3d) on the floor.
+    pub fn facing(normal: Dir3) -> Self {
+        Self {
+            normal,
+            ..Default::default()
+        }
+    }
+
+    /// Sets [offset](Self::offset).
+    pub fn with_offset(mut self, offset: f32) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// Sets [axis](Self::axis).
+    pub fn with_axis(mut self, axis: Dir3) -> Self {
+        self.axis = axis;
+        self
+    }
+
+    /// Sets [curvature](Self::curvature).
+    pub fn with_curvature(mut self, curvature: MirrorCurvature) -> Self {
+        self.curvature = curvature;
+        self
+    }
+
+    /// Configuration of the other side of the mirror, relative to the portal created on the
+    /// back faces, which is rotated by PI around Y, see [CreatePortal::double_sided].
+    pub fn back(&self) -> Self {
+        let back_rotation = Quat::from_axis_angle(Vec3::Y, PI);
+        Self {
+            normal: back_rotation * -self.normal,
+            offset: -self.offset,
+            axis: back_rotation * self.axis,
+            curvature: self.curvature.back(),
+        }
+    }
+
+    /// Local transform of the [PortalDestination] created for this mirror, relative to the portal:
+    /// the portal rotated by PI around [axis](Self::axis) on the mirror plane, and scaled
+    /// according to the [curvature](Self::curvature).
+    pub fn destination_transform(&self) -> Transform {
+        let normal = *self.normal;
+        let axis = Dir3::new(self.axis.reject_from_normalized(normal))
+            .unwrap_or(Dir3::new_unchecked(normal.any_orthonormal_vector()));
+        let inverse_magnification = 1. / self.curvature.magnification();
+        let scale = match self.curvature {
+            MirrorCurvature::Flat => Vec3::ONE,
+            MirrorCurvature::Sphere { .. } => Vec3::splat(inverse_magnification),
+            // Not scaled along the axis of the cylinder
+            MirrorCurvature::Cylinder { .. } => {
+                Vec3::ONE + (inverse_magnification - 1.) * (Vec3::ONE - axis.abs())
+            }
+        };
+        Transform {
+            translation: 2. * self.offset * normal,
+            rotation: Quat::from_axis_angle(*axis, PI),
+            scale,
+        }
+    }
+}
+
 /// What technique to use to render the portal effect, and what entities are seen
 /// or not through it.
-#[derive(Clone)]
+///
+/// All the modes render the destination with a single [PortalCamera] to a 2D image, there is no
+/// cubemap mode for spherical portals yet (see the known limitations of the crate).
+#[derive(Clone, Reflect)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[reflect(Default)]
 pub enum PortalMode {
     /// The portal effect will be rendered on a texture with the same size as
     /// the main camera's viewport, and a shader will define the UV-mapping using
@@ -271,13 +963,46 @@ pub enum PortalMode {
     /// for rendering when they shouldn't be (for example, when the camera's forward
     /// is almost parallel to the plane, objects behind the camera but in front of
     /// the plane will be considered).
-    MaskedImageHalfSpaceFrustum(Option<HalfSpace>),
+    MaskedImageHalfSpaceFrustum(
+        #[reflect(ignore)]
+        #[cfg_attr(feature = "serde", serde(with = "super::serialization::half_space"))]
+        Option<HalfSpace>,
+    ),
+    /// Same as [PortalMode::MaskedImageHalfSpaceFrustum], but the lateral planes of the frustum
+    /// are also fitted to the [Aabb](bevy_render::primitives::Aabb) of the portal mesh seen from
+    /// the portal camera, see [fit_frustum_to_portal_aabb].
+    ///
+    /// Objects far to the sides of the destination, that can't be seen through a small portal,
+    /// are then culled.
+    MaskedImageAabbHalfSpaceFrustum(
+        #[reflect(ignore)]
+        #[cfg_attr(feature = "serde", serde(with = "super::serialization::half_space"))]
+        Option<HalfSpace>,
+    ),
+    /// Same as [PortalMode::MaskedImageHalfSpaceFrustum], but the lateral planes of the frustum
+    /// go through the portal camera and the edges of the portal, for planar rectangular portals,
+    /// see [fit_frustum_to_portal_rectangle].
+    ///
+    /// The rectangle is the [Aabb](bevy_render::primitives::Aabb) of the portal mesh flattened
+    /// along its thinnest axis, so the culling is exact for meshes like [Rectangle](bevy_math::primitives::Rectangle)
+    /// and [Plane3d](bevy_math::primitives::Plane3d).
+    MaskedImageRectangleHalfSpaceFrustum(
+        #[reflect(ignore)]
+        #[cfg_attr(feature = "serde", serde(with = "super::serialization::half_space"))]
+        Option<HalfSpace>,
+    ),
+    /// One of the modes above chosen from the portal mesh when the portal is created,
+    /// see [PortalMode::from_mesh].
+    Auto,
     //TODO
-    //MaskedImageRectangleFrustum(PortalRectangleView),
     //MaskedImageSphereHalfSpaceFrustum(_)
     //MaskedImageSphereRectangleFrustum(_)
     // A projection matrix will be defined to fit.
     //FittingProjectionRectangle(PortalRectangleView)
+    // The destination will be rendered in a cubemap by six portal cameras (needs PortalParts
+    // to support several portal cameras), and sampled using the view direction, for spherical
+    // portals with a correct parallax from any angle, also usable as an environment map.
+    //CubemapImage
 }
 
 impl Default for PortalMode {
@@ -286,6 +1011,98 @@ impl Default for PortalMode {
     }
 }
 
+impl PortalMode {
+    /// Chooses a mode for a portal mesh, used for [PortalMode::Auto].
+    ///
+    /// A planar mesh facing Z, like a [Rectangle](bevy_math::primitives::Rectangle) (its
+    /// [Aabb](bevy_render::primitives::Aabb) is flat along Z and its normals point to the same
+    /// side), gets a [PortalMode::MaskedImageHalfSpaceFrustum] with a [HalfSpace] in its plane.
+    /// The [HalfSpace] is rotated from `-Z` to the forward of the destination (see [get_frustum]),
+    /// so planar meshes facing another axis, like a [Plane3d](bevy_math::primitives::Plane3d),
+    /// get [PortalMode::MaskedImageNoFrustum], as well as other meshes like cubes, as a plane
+    /// would cull what is seen through parts of the mesh.
+    /// Spheres are not handled specifically (there is no mode for spherical portals yet), they
+    /// get [PortalMode::MaskedImageNoFrustum] too.
+    pub fn from_mesh(mesh: &Mesh) -> Self {
+        let (Some(aabb), Some(normals)) = (
+            mesh.compute_aabb(),
+            mesh.attribute(Mesh::ATTRIBUTE_NORMAL)
+                .and_then(|normals| normals.as_float3()),
+        ) else {
+            return PortalMode::MaskedImageNoFrustum;
+        };
+        let half_extents = Vec3::from(aabb.half_extents);
+        let thinnest_axis = Vec3::AXES
+            .into_iter()
+            .min_by(|a, b| a.dot(half_extents).total_cmp(&b.dot(half_extents)))
+            .unwrap_or(Vec3::Z);
+        if thinnest_axis != Vec3::Z
+            || thinnest_axis.dot(half_extents) > PLANAR_MESH_THICKNESS * half_extents.max_element()
+        {
+            return PortalMode::MaskedImageNoFrustum;
+        }
+        // All the normals point to the side the portal is seen from
+        let normals_side: Vec<f32> = normals
+            .iter()
+            .map(|normal| thinnest_axis.dot(Vec3::from(*normal)))
+            .collect();
+        let side = if normals_side.iter().all(|side| *side > 0.) {
+            1.
+        } else if normals_side.iter().all(|side| *side < 0.) {
+            -1.
+        } else {
+            return PortalMode::MaskedImageNoFrustum;
+        };
+        // What is behind the plane of the mesh is seen through it, the distance is opposite to
+        // the one of the plane in local space, see get_frustum
+        let normal = -side * thinnest_axis;
+        PortalMode::MaskedImageHalfSpaceFrustum(Some(HalfSpace::new(
+            normal.extend(normal.dot(Vec3::from(aabb.center))),
+        )))
+    }
+
+    /// Returns the mode chosen with [PortalMode::from_mesh] for [PortalMode::Auto], or a clone
+    /// of this mode. Without a mesh, [PortalMode::Auto] gives the default mode.
+    pub fn resolve(&self, mesh: Option<&Mesh>) -> Self {
+        match (self, mesh) {
+            (PortalMode::Auto, Some(mesh)) => PortalMode::from_mesh(mesh),
+            (PortalMode::Auto, None) => PortalMode::default(),
+            (portal_mode, _) => portal_mode.clone(),
+        }
+    }
+}
+
+/// Maximum thickness of a planar mesh for [PortalMode::from_mesh], relative to its size
+const PLANAR_MESH_THICKNESS: f32 = 0.01;
+
+/// Preset trading the quality of the rendering of a [PortalCamera] for its cost.
+///
+/// The color settings of the main camera ([Camera3d], [Tonemapping](bevy_core_pipeline::tonemapping::Tonemapping),
+/// [ColorGrading](bevy_render::view::ColorGrading), [Exposure](bevy_render::camera::Exposure))
+/// are always copied so that the portal looks the same as the rest of the scene.
+///
+/// Bevy can't skip the shadows for some cameras: the shadow maps of the point and spot lights
+/// are shared by all the cameras, and the cascades of the directional lights are rendered for
+/// each portal camera too. The presets below the full quality only make sampling them cheaper,
+/// reduce the cascades with [CascadeShadowConfig](bevy_pbr::CascadeShadowConfig) or disable the
+/// shadows of the lights behind the portal to save more.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug, Reflect)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[reflect(Default)]
+pub enum PortalRenderQuality {
+    /// Also copies the [Msaa](bevy_render::view::Msaa), [Bloom](bevy_core_pipeline::bloom::Bloom),
+    /// [ScreenSpaceAmbientOcclusion](bevy_pbr::ScreenSpaceAmbientOcclusion) and
+    /// [ShadowFilteringMethod](bevy_pbr::ShadowFilteringMethod) of the main camera.
+    Full,
+    /// Only copies the color settings, the [DebandDither](bevy_core_pipeline::tonemapping::DebandDither)
+    /// and the [Msaa](bevy_render::view::Msaa) of the main camera, no bloom or ambient occlusion,
+    /// and uses the cheapest [ShadowFilteringMethod::Hardware2x2](bevy_pbr::ShadowFilteringMethod::Hardware2x2).
+    #[default]
+    Simplified,
+    /// Same as [PortalRenderQuality::Simplified], but without MSAA or debanding.
+    Fastest,
+}
+
 /*#[derive(Clone)]
 pub struct PortalRectangleView {
     origin: Vec3,
@@ -293,8 +1110,39 @@ pub struct PortalRectangleView {
     rectangle: Vec2,
 }*/
 
+/// Configuration of the visible border of a portal.
+///
+/// The border is a copy of the portal mesh, scaled up in its local X and Y axes and put just behind it,
+/// so it works best with flat convex meshes facing Z, like [Rectangle](bevy_math::primitives::Rectangle)
+/// or [Circle](bevy_math::primitives::Circle).
+#[derive(Clone, Reflect)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+#[reflect(Default)]
+pub struct PortalBorder {
+    /// Color of the border, defaults to black.
+    pub color: Color,
+    /// Width of the border in the portal's local space, defaults to 0.1.
+    pub width: f32,
+    /// Emissive color of the border, defaults to black (not emissive).
+    pub emissive: LinearRgba,
+}
+
+impl Default for PortalBorder {
+    fn default() -> Self {
+        PortalBorder {
+            color: Color::BLACK,
+            width: 0.1,
+            emissive: LinearRgba::BLACK,
+        }
+    }
+}
+
 /// Configuration of debug elements.
-#[derive(Clone)]
+#[derive(Clone, Reflect)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+#[reflect(Default)]
 pub struct DebugPortal {
     /// Name of the portal, used in the debug window's title.
     pub name: Option<String>,
@@ -308,6 +1156,18 @@ pub struct DebugPortal {
     pub show_portal_copy: bool,
     /// If true, displays a small sphere at the [PortalCamera] position.
     pub show_portal_camera_point: bool,
+    /// If true, draws the [Frustum](bevy_render::primitives::Frustum) of the [PortalCamera] with
+    /// gizmos each frame, including the near plane replaced by the [PortalMode]'s [HalfSpace].
+    ///
+    /// Needs the `gizmos` feature (a warning is logged when the portal is created without it),
+    /// defaults to false.
+    pub show_frustum: bool,
+    /// If true, draws the near [HalfSpace] of the [PortalMode] (in global space) with gizmos each
+    /// frame, as a translucent grid centered on the destination with an arrow for its normal.
+    ///
+    /// Only drawn for the modes with a [HalfSpace]. Needs the `gizmos` feature (a warning is
+    /// logged when the portal is created without it), defaults to false.
+    pub show_half_space: bool,
 }
 
 impl Default for DebugPortal {
@@ -319,6 +1179,8 @@ impl Default for DebugPortal {
             show_destination_point: true,
             show_portal_copy: true,
             show_portal_camera_point: true,
+            show_frustum: false,
+            show_half_space: false,
         }
     }
 }
@@ -0,0 +1,180 @@
+//! Builder for [CreatePortal]
+
+use bevy_ecs::prelude::*;
+use bevy_render::{render_resource::Face, view::RenderLayers};
+use bevy_transform::prelude::*;
+use std::{marker::PhantomData, sync::Arc};
+
+use super::*;
+
+/// State of a [CreatePortalBuilder] whose destination is not set yet, it can't be built.
+pub struct NoDestination;
+
+/// State of a [CreatePortalBuilder] whose destination is set, it can be built.
+pub struct WithDestination;
+
+/// Builder for [CreatePortal], created with [CreatePortal::builder].
+///
+/// The destination must be set before [build](CreatePortalBuilder::build) is available,
+/// with [destination](CreatePortalBuilder::destination), [to](CreatePortalBuilder::to),
+/// [to_entity](CreatePortalBuilder::to_entity) or [mirror](CreatePortalBuilder::mirror).
+/// Other fields can be set with [with](CreatePortalBuilder::with), except the destination.
+pub struct CreatePortalBuilder<State = NoDestination> {
+    config: CreatePortal,
+    state: PhantomData<State>,
+}
+
+impl CreatePortal {
+    /// Starts a [CreatePortalBuilder].
+    pub fn builder() -> CreatePortalBuilder<NoDestination> {
+        CreatePortalBuilder {
+            config: CreatePortal::default(),
+            state: PhantomData,
+        }
+    }
+}
+
+impl CreatePortalBuilder<NoDestination> {
+    /// Sets [CreatePortal::destination].
+    pub fn destination(
+        self,
+        destination: PortalDestinationSource,
+    ) -> CreatePortalBuilder<WithDestination> {
+        CreatePortalBuilder {
+            config: CreatePortal {
+                destination,
+                ..self.config
+            },
+            state: PhantomData,
+        }
+    }
+
+    /// Creates the destination at `transform`, like [CreatePortal::to].
+    pub fn to(self, transform: Transform) -> CreatePortalBuilder<WithDestination> {
+        self.destination(CreatePortal::to(transform).destination)
+    }
+
+    /// Uses an existing `destination` entity, like [CreatePortal::to_entity].
+    pub fn to_entity(self, destination: Entity) -> CreatePortalBuilder<WithDestination> {
+        self.destination(CreatePortal::to_entity(destination).destination)
+    }
+
+    /// Makes a mirror, like [CreatePortal::mirror].
+    pub fn mirror(self) -> CreatePortalBuilder<WithDestination> {
+        self.destination(CreatePortal::mirror().destination)
+    }
+
+    /// Makes a mirror reflecting on another plane than the default one, like
+    /// [CreatePortal::mirror_with].
+    pub fn mirror_with(self, config: MirrorConfig) -> CreatePortalBuilder<WithDestination> {
+        self.destination(CreatePortal::mirror_with(config).destination)
+    }
+}
+
+impl<State> CreatePortalBuilder<State> {
+    /// Sets [CreatePortal::back_destination].
+    pub fn back_destination(mut self, back_destination: PortalDestinationSource) -> Self {
+        self.config.back_destination = Some(back_destination);
+        self
+    }
+
+    /// Sets [CreatePortal::double_sided].
+    pub fn double_sided(mut self, double_sided: bool) -> Self {
+        self.config.double_sided = double_sided;
+        self
+    }
+
+    /// Sets [CreatePortal::portal_mode].
+    pub fn mode(mut self, portal_mode: PortalMode) -> Self {
+        self.config.portal_mode = portal_mode;
+        self
+    }
+
+    /// Sets [CreatePortal::main_camera].
+    pub fn main_camera(mut self, main_camera: Entity) -> Self {
+        self.config.main_camera = Some(main_camera);
+        self
+    }
+
+    /// Sets [CreatePortal::main_camera_views].
+    pub fn main_camera_views(mut self, main_camera_views: bool) -> Self {
+        self.config.main_camera_views = main_camera_views;
+        self
+    }
+
+    /// Sets [CreatePortal::portal_camera_insert].
+    pub fn portal_camera_insert(
+        mut self,
+        portal_camera_insert: impl Fn(&mut EntityCommands) + Send + Sync + 'static,
+    ) -> Self {
+        self.config.portal_camera_insert = Some(Arc::new(portal_camera_insert));
+        self
+    }
+
+    /// Sets [CreatePortal::viewer].
+    pub fn viewer(mut self, viewer: PortalViewerMode) -> Self {
+        self.config.viewer = viewer;
+        self
+    }
+
+    /// Sets [CreatePortal::projection].
+    pub fn projection(mut self, projection: PortalProjection) -> Self {
+        self.config.projection = Some(projection);
+        self
+    }
+
+    /// Sets [CreatePortal::cull_mode].
+    pub fn cull_mode(mut self, cull_mode: Option<Face>) -> Self {
+        self.config.cull_mode = cull_mode;
+        self
+    }
+
+    /// Sets [CreatePortal::allocate_render_layer].
+    pub fn allocate_render_layer(mut self, allocate_render_layer: bool) -> Self {
+        self.config.allocate_render_layer = allocate_render_layer;
+        self
+    }
+
+    /// Sets [CreatePortal::render_layer].
+    pub fn render_layer(mut self, render_layer: RenderLayers) -> Self {
+        self.config.render_layer = render_layer;
+        self
+    }
+
+    /// Sets [CreatePortal::render_quality].
+    pub fn render_quality(mut self, render_quality: PortalRenderQuality) -> Self {
+        self.config.render_quality = render_quality;
+        self
+    }
+
+    /// Sets [CreatePortal::border].
+    pub fn border(mut self, border: PortalBorder) -> Self {
+        self.config.border = Some(border);
+        self
+    }
+
+    /// Sets [CreatePortal::debug].
+    pub fn debug(mut self, debug: DebugPortal) -> Self {
+        self.config.debug = Some(debug);
+        self
+    }
+
+    /// Changes any other field of the [CreatePortal].
+    ///
+    /// The destination is kept, it can only be set with the methods of the
+    /// [NoDestination] state, so that [build](CreatePortalBuilder::build) is only available
+    /// once it is set.
+    pub fn with(mut self, f: impl FnOnce(&mut CreatePortal)) -> Self {
+        let destination = std::mem::take(&mut self.config.destination);
+        f(&mut self.config);
+        self.config.destination = destination;
+        self
+    }
+}
+
+impl CreatePortalBuilder<WithDestination> {
+    /// Builds the [CreatePortal].
+    pub fn build(self) -> CreatePortal {
+        self.config
+    }
+}
@@ -0,0 +1,150 @@
+//! What the portals can use on the current device
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use bevy_reflect::Reflect;
+use bevy_render::{
+    render_resource::{DownlevelFlags, TextureFormat, TextureUsages},
+    renderer::{RenderAdapter, RenderDevice},
+    view::Msaa,
+};
+use tracing::{info, warn};
+
+/// Add the capability report to [PortalsPlugin](super::PortalsPlugin)
+pub(super) fn build_capabilities(app: &mut App) {
+    app.register_type::<PortalsCapabilities>()
+        .add_systems(PreStartup, detect_portals_capabilities);
+}
+
+/// Formats checked by [PortalsCapabilities::detect] for [PortalsCapabilities::texture_formats]
+const CANDIDATE_FORMATS: [TextureFormat; 5] = [
+    TextureFormat::Bgra8UnormSrgb,
+    TextureFormat::Rgba8UnormSrgb,
+    TextureFormat::Rgba16Float,
+    TextureFormat::Rgb10a2Unorm,
+    TextureFormat::R32Float,
+];
+
+/// Preferred format of the images portal cameras render to, see
+/// [PortalsCapabilities::portal_image_format]
+pub(super) const PORTAL_IMAGE_FORMAT: TextureFormat = TextureFormat::Bgra8UnormSrgb;
+
+/// Default of [PortalsCapabilities::portal_image_format] for reflection
+fn portal_image_format() -> TextureFormat {
+    PORTAL_IMAGE_FORMAT
+}
+
+/// Format of the portal images when [PORTAL_IMAGE_FORMAT] can't be used, like on some WebGL2 browsers
+const FALLBACK_PORTAL_IMAGE_FORMAT: TextureFormat = TextureFormat::Rgba8UnormSrgb;
+
+/// [Resource] describing what the portals can use on the current device, inserted by
+/// [detect_portals_capabilities] at startup.
+///
+/// Use it to adapt the [PortalRenderQuality](super::PortalRenderQuality), [Msaa] or
+/// [CreatePortal](super::CreatePortal) options per device,
+/// instead of guessing from the platform.
+#[derive(Resource, Clone, Debug, PartialEq, Reflect)]
+#[reflect(Resource)]
+pub struct PortalsCapabilities {
+    /// Name of the adapter, for logs and reports.
+    pub adapter_name: String,
+    /// The portal images can be rendered to with an HDR main camera ([TextureFormat::Rgba16Float]
+    /// can be rendered to and sampled).
+    pub hdr: bool,
+    /// MSAA sample counts supported for the portal images, always contains 1.
+    pub msaa_sample_counts: Vec<u32>,
+    /// Storage textures and buffers can be used in the portal shaders, false on WebGL2.
+    pub storage: bool,
+    /// The depth of the destination can be copied, see
+    /// [CreatePortal::destination_depth](super::CreatePortal::destination_depth).
+    pub destination_depth: bool,
+    /// Formats among a few common ones which can be both rendered to and sampled.
+    #[reflect(ignore)]
+    pub texture_formats: Vec<TextureFormat>,
+    /// Maximum width and height of the portal images, bigger viewports are clamped by the device.
+    pub max_image_size: u32,
+    /// Format of the images portal cameras render to, [TextureFormat::Bgra8UnormSrgb] if it can be
+    /// both rendered to and sampled, [TextureFormat::Rgba8UnormSrgb] otherwise (on WebGL2).
+    #[reflect(ignore, default = "portal_image_format")]
+    pub portal_image_format: TextureFormat,
+}
+
+impl PortalsCapabilities {
+    /// Checks the features of the device and adapter.
+    pub fn detect(render_device: &RenderDevice, render_adapter: &RenderAdapter) -> Self {
+        let limits = render_device.limits();
+        let downlevel_flags = render_adapter.get_downlevel_capabilities().flags;
+        let render_and_sample = TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING;
+
+        let portal_image_format = if render_adapter
+            .get_texture_format_features(PORTAL_IMAGE_FORMAT)
+            .allowed_usages
+            .contains(render_and_sample)
+        {
+            PORTAL_IMAGE_FORMAT
+        } else {
+            FALLBACK_PORTAL_IMAGE_FORMAT
+        };
+        let portal_image_features = render_adapter.get_texture_format_features(portal_image_format);
+        let mut msaa_sample_counts = portal_image_features.flags.supported_sample_counts();
+        if !msaa_sample_counts.contains(&1) {
+            msaa_sample_counts.insert(0, 1);
+        }
+
+        let hdr_features = render_adapter.get_texture_format_features(TextureFormat::Rgba16Float);
+        let depth_copy_features =
+            render_adapter.get_texture_format_features(TextureFormat::R32Float);
+
+        Self {
+            adapter_name: render_adapter.get_info().name,
+            hdr: hdr_features.allowed_usages.contains(render_and_sample),
+            msaa_sample_counts,
+            storage: limits.max_storage_textures_per_shader_stage > 0
+                && limits.max_storage_buffers_per_shader_stage > 0,
+            destination_depth: downlevel_flags
+                .contains(DownlevelFlags::DEPTH_TEXTURE_AND_BUFFER_COPIES)
+                && depth_copy_features
+                    .allowed_usages
+                    .contains(TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST),
+            texture_formats: CANDIDATE_FORMATS
+                .into_iter()
+                .filter(|format| {
+                    render_adapter
+                        .get_texture_format_features(*format)
+                        .allowed_usages
+                        .contains(render_and_sample)
+                })
+                .collect(),
+            max_image_size: limits.max_texture_dimension_2d,
+            portal_image_format,
+        }
+    }
+
+    /// Whether `msaa` can be used by the main cameras looking at portals.
+    pub fn supports_msaa(&self, msaa: Msaa) -> bool {
+        self.msaa_sample_counts.contains(&msaa.samples())
+    }
+
+    /// The highest [Msaa] supported for the portal images.
+    pub fn max_msaa(&self) -> Msaa {
+        [Msaa::Sample8, Msaa::Sample4, Msaa::Sample2]
+            .into_iter()
+            .find(|msaa| self.supports_msaa(*msaa))
+            .unwrap_or(Msaa::Off)
+    }
+}
+
+/// [System] inserting the [PortalsCapabilities] resource, added by [PortalsPlugin](super::PortalsPlugin) in [PreStartup].
+pub fn detect_portals_capabilities(
+    mut commands: Commands,
+    render_device: Option<Res<RenderDevice>>,
+    render_adapter: Option<Res<RenderAdapter>>,
+) {
+    let (Some(render_device), Some(render_adapter)) = (render_device, render_adapter) else {
+        warn!("No render device, portal capabilities not detected");
+        return;
+    };
+    let capabilities = PortalsCapabilities::detect(&render_device, &render_adapter);
+    info!("Portal capabilities: {capabilities:?}");
+    commands.insert_resource(capabilities);
+}
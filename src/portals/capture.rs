@@ -0,0 +1,91 @@
+//! Commands and events to capture the images of portals, for example for thumbnails
+
+use bevy_app::prelude::*;
+use bevy_asset::{prelude::*, RenderAssetUsages};
+use bevy_ecs::{prelude::*, system::EntityCommand};
+use bevy_image::{Image, TextureFormatPixelInfo};
+use bevy_render::{
+    gpu_readback::{Readback, ReadbackComplete},
+    render_resource::{Extent3d, TextureDimension},
+    renderer::RenderDevice,
+};
+use tracing::warn;
+
+use super::*;
+
+/// Add the capture logic to [PortalsPlugin]
+pub(super) fn build_capture(app: &mut App) {
+    app.add_event::<PortalImageCaptured>();
+}
+
+/// [EntityCommand] to capture the current image of a portal, apply it to the entity with the [Portal].
+///
+/// The image is copied from the GPU asynchronously (the buffer is mapped over the next frames),
+/// so capturing never blocks a frame. A [PortalImageCaptured] event is sent when it's done.
+pub struct CapturePortalImage;
+
+impl EntityCommand for CapturePortalImage {
+    fn apply(self, id: Entity, world: &mut World) {
+        let Some(image) = world
+            .get::<Portal>(id)
+            .and_then(|portal| world.get::<PortalCamera>(portal.parts.portal_camera))
+            .map(|portal_camera| portal_camera.image.clone())
+        else {
+            warn!("Entity {id} is not a portal, not capturing its image");
+            return;
+        };
+        let Some(descriptor) = world
+            .resource::<Assets<Image>>()
+            .get(&image)
+            .map(|image| image.texture_descriptor.clone())
+        else {
+            warn!("Image of portal {id} not found, not capturing it");
+            return;
+        };
+
+        world.spawn(Readback::texture(image)).observe(
+            move |trigger: Trigger<ReadbackComplete>,
+                  mut commands: Commands,
+                  mut captured: EventWriter<PortalImageCaptured>| {
+                // Only read back once
+                commands.entity(trigger.entity()).despawn();
+
+                let size = Extent3d {
+                    depth_or_array_layers: 1,
+                    ..descriptor.size
+                };
+                let row_size = (size.width * descriptor.format.pixel_size() as u32) as usize;
+                let padded_row_size = RenderDevice::align_copy_bytes_per_row(row_size);
+                // Rows are padded in the readback buffer
+                let data = trigger
+                    .event()
+                    .0
+                    .chunks(padded_row_size)
+                    .take(size.height as usize)
+                    .flat_map(|row| &row[..row_size.min(row.len())])
+                    .copied()
+                    .collect();
+
+                captured.send(PortalImageCaptured {
+                    portal: id,
+                    image: Image::new(
+                        size,
+                        TextureDimension::D2,
+                        data,
+                        descriptor.format,
+                        RenderAssetUsages::default(),
+                    ),
+                });
+            },
+        );
+    }
+}
+
+/// [Event] sent when a [CapturePortalImage] is done.
+#[derive(Event, Clone)]
+pub struct PortalImageCaptured {
+    /// The entity with the [Portal] component.
+    pub portal: Entity,
+    /// Copy of the image of the portal.
+    pub image: Image,
+}
@@ -3,8 +3,10 @@
 use bevy_image::Image;
 use bevy_app::prelude::*;
 use bevy_asset::prelude::*;
-use bevy_color::Alpha;
+use bevy_color::{Alpha, ColorToComponents, ColorToPacked};
+use bevy_core::Name;
 use bevy_core_pipeline::{
+    bloom::Bloom,
     prelude::*,
     tonemapping::{DebandDither, Tonemapping},
 };
@@ -14,20 +16,22 @@ use bevy_ecs::{
 };
 use bevy_hierarchy::prelude::*;
 use bevy_math::prelude::*;
-use bevy_pbr::prelude::*;
-use bevy_reflect::Reflect;
+use bevy_pbr::{prelude::*, ScreenSpaceAmbientOcclusion, ShadowFilteringMethod};
+use bevy_reflect::{std_traits::ReflectDefault, PartialReflect, Reflect};
 use bevy_render::{
     camera::{Exposure, RenderTarget},
+    mesh::MeshAabb,
     prelude::*,
-    render_resource::{
-        Extent3d, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages,
-    },
-    view::ColorGrading,
+    primitives::Frustum,
+    render_resource::{Extent3d, Face, TextureDescriptor, TextureDimension, TextureUsages},
+    view::{ColorGrading, Msaa, RenderLayers},
 };
+#[cfg(feature = "scene")]
+use bevy_scene::prelude::*;
 use bevy_transform::{prelude::*, TransformSystem};
 use bevy_window::{Window, WindowLevel, WindowRef, WindowResolution};
 use std::f32::consts::PI;
-use tracing::error;
+use tracing::{debug, error, info, warn};
 
 use super::*;
 
@@ -35,19 +39,86 @@ use super::*;
 pub(super) fn build_create(app: &mut App, check_create: &PortalsCheckMode) {
     app.register_type::<Portal>()
         .register_type::<PortalDestination>()
-        .register_type::<PortalCamera>();
+        .register_type::<PortalCamera>()
+        .register_type::<PortalOriginalMaterial>()
+        .register_type::<PortalSpawnedEntity>()
+        .register_type::<PortalDebugCamera>()
+        .register_type::<PortalParts>()
+        .register_type::<CreatePortal>()
+        .register_type::<PortalDestinationSource>()
+        .register_type::<CreatePortalDestination>()
+        .register_type::<MirrorConfig>()
+        .register_type::<MirrorCurvature>()
+        .register_type::<PortalMode>()
+        .register_type::<PortalViewerMode>()
+        .register_type::<PortalRenderQuality>()
+        .register_type::<PortalBorder>()
+        .register_type::<DebugPortal>()
+        .register_type::<PortalRipple>()
+        .register_type::<PortalHeatHaze>()
+        .register_type::<PortalRefraction>()
+        .register_type::<PortalShadows>()
+        .register_type::<PortalDissolve>()
+        .register_type::<PortalCreationFailurePolicy>()
+        .init_resource::<PortalCreationFailurePolicy>();
 
     if check_create != &PortalsCheckMode::Manual {
         app.add_systems(
             PostStartup,
-            create_portals.after(TransformSystem::TransformPropagate),
+            (
+                resolve_portal_destinations,
+                create_portals.after(TransformSystem::TransformPropagate),
+                log_portals_setup,
+            )
+                .chain()
+                .in_set(PortalSystems::Create),
         );
+    } else {
+        app.add_systems(PostStartup, log_portals_setup);
     }
 
     if check_create == &PortalsCheckMode::AlwaysCheck {
         app.add_systems(
             PostUpdate,
-            create_portals.after(TransformSystem::TransformPropagate),
+            (resolve_portal_destinations, create_portals)
+                .chain()
+                .in_set(PortalSystems::Create),
+        );
+    }
+}
+
+/// [System] that logs the detected main cameras and portals once after startup,
+/// and warns about common setup mistakes.
+#[allow(clippy::type_complexity)]
+fn log_portals_setup(
+    cameras: Query<
+        Entity,
+        (
+            With<Camera>,
+            Without<PortalCamera>,
+            Without<PortalDebugCamera>,
+        ),
+    >,
+    portals: Query<&Portal>,
+    portals_to_create: Query<Entity, With<CreatePortal>>,
+    headless: Option<Res<PortalsHeadless>>,
+) {
+    let cameras: Vec<Entity> = cameras.iter().collect();
+    let portals = portals.iter().count();
+    info!(
+        "Portals setup: {portals} portal(s) created, {} possible main camera(s) {cameras:?}",
+        cameras.len()
+    );
+    // Headless portals follow the viewer entity, see PortalsHeadless
+    if cameras.is_empty() && headless.is_none() && (portals > 0 || !portals_to_create.is_empty()) {
+        warn!("No camera found for the portals, spawn a camera (for example with Camera3d)");
+    }
+    if !portals_to_create.is_empty() {
+        warn!(
+            "Entities with CreatePortal were not turned into portals after startup: {:?}, \
+            they need a Mesh3d and a Transform, and PortalsPlugin::check_create should not be PortalsCheckMode::Manual \
+            (or use CreatePortalCommand)",
+            portals_to_create.iter().collect::<Vec<_>>()
         );
     }
 }
@@ -59,12 +130,20 @@ pub struct PortalParts {
     pub portal: Entity,
     pub destination: Entity,
     pub portal_camera: Entity,
+    /// The [PortalView]s of the portal, one per main camera, including the portal itself,
+    /// empty unless [CreatePortal::main_camera_views] is true.
+    pub views: Vec<Entity>,
+    /// Overrides the global [PortalPartsDespawnStrategy] for these parts,
+    /// see [CreatePortal::despawn_strategy].
+    pub despawn_strategy: Option<PortalPartsDespawnStrategy>,
 }
 
 /// Marker [Component] for the portal.
 ///
 /// Will replace [CreatePortal] after [create_portals].
 #[derive(Component, Reflect)]
+#[reflect(Component)]
+#[require(PortalViewInfo, PortalDeactivation)]
 pub struct Portal {
     pub parts: PortalParts,
 }
@@ -73,6 +152,7 @@ pub struct Portal {
 ///
 /// Will be added to the entity defined by [CreatePortal.destination](CreatePortal)
 #[derive(Component, Reflect)]
+#[reflect(Component)]
 pub struct PortalDestination {
     pub parts: PortalParts,
 }
@@ -81,15 +161,63 @@ pub struct PortalDestination {
 ///
 /// Note: The entity this component is attached to is not supposed to be a child of another entity.
 #[derive(Component, Reflect)]
+#[reflect(Component)]
 pub struct PortalCamera {
+    /// Image rendered by the portal camera, each portal has its own image.
     pub image: Handle<Image>,
-    #[reflect(ignore)]
     pub portal_mode: PortalMode,
     pub parts: PortalParts,
+    /// Whether the main camera was picked automatically ([CreatePortal::main_camera] was `None`).
+    ///
+    /// If true, the portal will follow another camera if its main camera despawns,
+    /// see [rebind_portal_main_cameras].
+    pub auto_main_camera: bool,
+    /// Which entity the portal camera follows, see [CreatePortal::viewer].
+    pub viewer: PortalViewerMode,
+    /// Whether to only render the region of the image covered by the portal,
+    /// see [CreatePortal::scissor_to_portal].
+    pub scissor_to_portal: bool,
+    /// Which settings of the main camera were copied, see [CreatePortal::render_quality].
+    pub render_quality: PortalRenderQuality,
+    /// Faces of the portal mesh culled by its [PortalMaterial] while the portal has a positive
+    /// scale, swapped by [update_portal_cameras] while it has a negative scale,
+    /// see [CreatePortal::cull_mode].
+    #[reflect(ignore)]
+    pub cull_mode: Option<Face>,
+}
+
+/// [Component] with the [StandardMaterial] the portal mesh had before the portal was created,
+/// which was replaced by the [PortalMaterial].
+///
+/// It is put back when the portal is [closed](PortalAnimationState::Closed), and when the portal
+/// parts are despawned but not the portal entity itself (see [PortalPartsDespawnStrategy]),
+/// so that a mesh can go from "wall" to "portal" and back.
+/// Portals using another material than [PortalMaterial] (for example with a [PortalLitSurface])
+/// only get it back when their parts are despawned.
+#[derive(Component, Clone, Reflect)]
+#[reflect(Component)]
+pub struct PortalOriginalMaterial(pub Handle<StandardMaterial>);
+
+/// Puts the [PortalOriginalMaterial] back on the portal mesh, instead of the [PortalMaterial]
+pub(super) fn restore_portal_original_material(mut portal: EntityWorldMut) {
+    let Some(original_material) = portal.take::<PortalOriginalMaterial>() else {
+        return;
+    };
+    portal
+        .remove::<MeshMaterial3d<PortalMaterial>>()
+        .insert(MeshMaterial3d(original_material.0));
 }
 
+/// Marker [Component] for the entities spawned by this crate along a portal (destinations
+/// created from [PortalDestinationSource::Create] or [PortalDestinationSource::CreateMirror],
+/// border, debug entities, back faces, [PortalView]s), despawned by [RemovePortalCommand].
+#[derive(Component, Clone, Copy, Default, Reflect)]
+#[reflect(Component, Default)]
+pub struct PortalSpawnedEntity;
+
 /// Marker [Component] for the debug camera when [DebugPortal::show_window] is true.
-#[derive(Component)]
+#[derive(Component, Clone, Copy, Default, Reflect)]
+#[reflect(Component, Default)]
 pub struct PortalDebugCamera;
 
 /// [EntityCommand] to create a portal manually.
@@ -106,16 +234,30 @@ pub struct CreatePortalCommand {
 
 impl EntityCommand for CreatePortalCommand {
     fn apply(self, id: Entity, world: &mut World) {
-        let (portal_transform, mesh) = world.query::<(&GlobalTransform, &Mesh3d)>().get(world, id)
-            .expect("You must provide a GlobalTransform and Handle<Mesh> components to the entity before using a CreatePortalCommand");
+        let Ok((portal_transform, mesh)) =
+            world.query::<(&GlobalTransform, &Mesh3d)>().get(world, id)
+        else {
+            portal_creation_failed_in_world(world, id, PortalCreationError::MissingComponents);
+            return;
+        };
         let portal_transform = *portal_transform;
         let mesh = mesh.clone();
 
-        let portal_create = match self.config {
+        let mut portal_create = match self.config {
             Some(config) => config,
-            None => world.query::<&CreatePortal>().get(world, id)
-                .expect("You must provide a CreatePortal component to the entity or to the CreatePortalCommand itself before using it").clone()
+            None => match world.query::<&CreatePortal>().get(world, id) {
+                Ok(config) => config.clone(),
+                Err(_) => {
+                    portal_creation_failed_in_world(
+                        world,
+                        id,
+                        PortalCreationError::MissingComponents,
+                    );
+                    return;
+                }
+            },
         };
+        resolve_destination_sources(&mut portal_create, id, world);
 
         let mut system_state = SystemState::<CreatePortalParams>::new(world);
         let mut create_params = system_state.get_mut(world);
@@ -126,12 +268,190 @@ impl EntityCommand for CreatePortalCommand {
             &portal_create,
             &portal_transform,
             &mesh,
+            None,
         );
 
         system_state.apply(world);
     }
 }
 
+/// Why a portal could not be created, see [PortalCreationFailed].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PortalCreationError {
+    /// [CreatePortal::main_camera] is not an entity with a [Camera] component.
+    MainCameraNotFound(Entity),
+    /// [CreatePortal::main_camera] is `None` and there is no camera,
+    /// or no viewer entity when [PortalsPlugin::rendering] is false.
+    NoCamera,
+    /// The entity is missing a [CreatePortal], [GlobalTransform] or [Mesh3d] component,
+    /// when using a [CreatePortalCommand].
+    MissingComponents,
+}
+
+impl std::fmt::Display for PortalCreationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MainCameraNotFound(camera_entity) => write!(
+                f,
+                "main camera {camera_entity} not found, CreatePortal::main_camera should be an entity with a Camera component"
+            ),
+            Self::NoCamera => write!(
+                f,
+                "no camera found, spawn a camera (for example with Camera3d) before the portal is created, see PortalsCheckMode \
+                (without rendering, set CreatePortal::main_camera or CreatePortal::viewer)"
+            ),
+            Self::MissingComponents => write!(
+                f,
+                "the entity needs CreatePortal (or a CreatePortalCommand config), GlobalTransform and Mesh3d components"
+            ),
+        }
+    }
+}
+
+/// [Resource] defining what to do when a portal can't be created, see [PortalCreationFailed].
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+#[reflect(Resource, Default)]
+pub enum PortalCreationFailurePolicy {
+    /// Log an error and keep the [CreatePortal] component to try again at the next check
+    /// (see [PortalsCheckMode]), default.
+    ///
+    /// The error is only logged and sent once per entity, the next failures are logged at the
+    /// debug level.
+    #[default]
+    Retry,
+    /// Log an error and remove the [CreatePortal] component.
+    Remove,
+    /// Panic, to catch setup mistakes early.
+    Panic,
+}
+
+/// Marker [Component] for the entities whose failed portal creation was already reported,
+/// see [PortalCreationFailurePolicy::Retry].
+#[derive(Component)]
+struct PortalCreationFailureReported;
+
+/// Logs a failed portal creation, sends a [PortalCreationFailed] and applies the
+/// [PortalCreationFailurePolicy].
+///
+/// `reported` is whether a previous failure of this entity was already reported.
+fn portal_creation_failed(
+    commands: &mut Commands,
+    portal_entity: Entity,
+    error: PortalCreationError,
+    policy: &PortalCreationFailurePolicy,
+    reported: bool,
+) {
+    if policy == &PortalCreationFailurePolicy::Panic {
+        panic!("Could not create portal {portal_entity}: {error}");
+    }
+    if reported {
+        debug!("Could not create portal {portal_entity} again: {error}");
+        return;
+    }
+    error!("Could not create portal {portal_entity}: {error}");
+    commands.send_event(PortalCreationFailed {
+        portal: portal_entity,
+        error,
+    });
+    let Some(mut entity_commands) = commands.get_entity(portal_entity) else {
+        return;
+    };
+    match policy {
+        PortalCreationFailurePolicy::Retry => {
+            entity_commands.insert(PortalCreationFailureReported);
+        }
+        PortalCreationFailurePolicy::Remove => {
+            entity_commands.remove::<CreatePortal>();
+        }
+        PortalCreationFailurePolicy::Panic => unreachable!("Panicked above"),
+    }
+}
+
+/// [portal_creation_failed] for [CreatePortalCommand]
+pub(super) fn portal_creation_failed_in_world(
+    world: &mut World,
+    portal_entity: Entity,
+    error: PortalCreationError,
+) {
+    let reported = world
+        .get_entity(portal_entity)
+        .is_ok_and(|entity| entity.contains::<PortalCreationFailureReported>());
+    let mut system_state =
+        SystemState::<(Commands, Option<Res<PortalCreationFailurePolicy>>)>::new(world);
+    let (mut commands, policy) = system_state.get_mut(world);
+    let policy = policy.as_deref().copied().unwrap_or_default();
+    portal_creation_failed(&mut commands, portal_entity, error, &policy, reported);
+    system_state.apply(world);
+}
+
+/// [EntityCommand] to create a portal from reflected data describing a [CreatePortal],
+/// for example a [DynamicStruct](bevy_reflect::DynamicStruct) from an editor or a scripting language.
+///
+/// The data is applied on top of [CreatePortal::default], so it can contain only some of the fields.
+/// The resulting [CreatePortal] is inserted on the entity, the portal is then created after the next check
+/// (see [PortalsCheckMode]).
+pub struct CreatePortalFromReflect(pub Box<dyn PartialReflect>);
+
+impl EntityCommand for CreatePortalFromReflect {
+    fn apply(self, id: Entity, world: &mut World) {
+        let mut config = CreatePortal::default();
+        if let Err(apply_error) = config.try_apply(self.0.as_ref()) {
+            error!("Could not create portal {id} from reflected data: {apply_error}");
+            return;
+        }
+        let Ok(mut entity) = world.get_entity_mut(id) else {
+            error!("Could not create portal {id} from reflected data: the entity doesn't exist");
+            return;
+        };
+        entity.insert(config);
+    }
+}
+
+/// [System] that resolves the [PortalDestinationSource::Resolve] destinations of [CreatePortal]s,
+/// replacing them with [PortalDestinationSource::Use].
+///
+/// Added with [create_portals] by [PortalsPlugin] depending on [PortalsCheckMode].
+pub fn resolve_portal_destinations(world: &mut World) {
+    let to_resolve: Vec<(Entity, CreatePortal)> = world
+        .query::<(Entity, &CreatePortal)>()
+        .iter(world)
+        .filter(|(_, create_portal)| {
+            matches!(
+                create_portal.destination,
+                PortalDestinationSource::Resolve(_)
+            ) || matches!(
+                create_portal.back_destination,
+                Some(PortalDestinationSource::Resolve(_))
+            )
+        })
+        .map(|(entity, create_portal)| (entity, create_portal.clone()))
+        .collect();
+
+    for (portal_entity, mut create_portal) in to_resolve {
+        resolve_destination_sources(&mut create_portal, portal_entity, world);
+        if let Some(mut create_portal_component) = world.get_mut::<CreatePortal>(portal_entity) {
+            *create_portal_component = create_portal;
+        }
+    }
+}
+
+/// Resolves the destination and back destination of a [CreatePortal] if needed.
+pub(super) fn resolve_destination_sources(
+    create_portal: &mut CreatePortal,
+    portal_entity: Entity,
+    world: &mut World,
+) {
+    let sources = std::iter::once(&mut create_portal.destination)
+        .chain(create_portal.back_destination.as_mut());
+    for source in sources {
+        if let PortalDestinationSource::Resolve(resolver) = source {
+            if let Some(destination) = resolver.clone().resolve(portal_entity, world) {
+                *source = PortalDestinationSource::Use(destination);
+            }
+        }
+    }
+}
+
 /// [System] that will find entities with the components of [CreatePortalBundle] and create a portal.
 ///
 /// It will create a [PortalCamera] at the destination, and put a portal material on the mesh of the entity with [CreatePortal].
@@ -145,13 +465,17 @@ pub fn create_portals(
     mut create_params: CreatePortalParams,
     portals_to_create: Query<(Entity, &CreatePortal, &GlobalTransform, &Mesh3d)>,
 ) {
-    for (portal_entity, portal_create, portal_transform, mesh) in portals_to_create.iter() {
+    // Sorted so that the portal parts are spawned in the same order for the same world
+    let mut portals_to_create: Vec<_> = portals_to_create.iter().collect();
+    portals_to_create.sort_unstable_by_key(|(portal_entity, ..)| *portal_entity);
+    for (portal_entity, portal_create, portal_transform, mesh) in portals_to_create {
         create_portal(
             &mut create_params,
             portal_entity,
             portal_create,
             portal_transform,
             mesh,
+            None,
         );
     }
 }
@@ -159,21 +483,115 @@ pub fn create_portals(
 /// Creates a portal.
 ///
 /// Called from [create_portals] or [CreatePortalCommand].
+///
+/// `front_material` is the material of the front portal when creating the portal on its back faces,
+/// see [CreatePortal::back_destination].
 #[allow(clippy::too_many_arguments)]
 fn create_portal(
-    CreatePortalParams {
+    create_params: &mut CreatePortalParams,
+    portal_entity: Entity,
+    create_portal: &CreatePortal,
+    portal_global_transform: &GlobalTransform,
+    portal_mesh: &Handle<Mesh>,
+    front_material: Option<&Handle<PortalMaterial>>,
+) {
+    if matches!(
+        create_portal.destination,
+        PortalDestinationSource::Resolve(_)
+    ) {
+        // Tried again at each check until the resolver returns a destination
+        debug!("Destination of portal {portal_entity} not resolved yet, skipping its creation");
+        return;
+    }
+
+    // Without rendering, the portal camera is an entity without a Camera nor an image,
+    // with a Transform and a Frustum kept up to date by update_portal_cameras
+    if create_params.headless.is_some() {
+        let CreatePortalParams {
+            commands,
+            failure_policy,
+            reported_failures,
+            ..
+        } = &mut *create_params;
+        // Headless apps usually don't have cameras, the viewer is used as the main camera
+        let main_camera_entity = match (create_portal.main_camera, create_portal.viewer) {
+            (Some(main_camera), _) => main_camera,
+            (None, PortalViewerMode::Entity(viewer)) => viewer,
+            (None, _) => {
+                portal_creation_failed(
+                    commands,
+                    portal_entity,
+                    PortalCreationError::NoCamera,
+                    failure_policy,
+                    reported_failures.contains(portal_entity),
+                );
+                return;
+            }
+        };
+        if create_portal.back_destination.is_some() || create_portal.double_sided {
+            warn!("Back destination of portal {portal_entity} is not supported without rendering, creating it without back faces");
+        }
+        if create_portal.main_camera_views {
+            warn!("Views of portal {portal_entity} are not supported without rendering, creating it without views");
+        }
+
+        let destination_entity =
+            spawn_portal_destination(commands, portal_entity, &create_portal.destination);
+
+        let mut portal_camera_commands = match create_portal.portal_camera {
+            Some(portal_camera_entity) => commands.entity(portal_camera_entity),
+            None => commands.spawn_empty(),
+        };
+        let portal_camera_entity = portal_camera_commands
+            .insert((
+                Transform::default(),
+                GlobalTransform::default(),
+                Frustum::default(),
+                create_portal.projection.clone().unwrap_or_default(),
+                Name::new(format!("Portal Camera {portal_entity}")),
+            ))
+            .id();
+
+        commands.entity(portal_entity).remove::<CreatePortal>();
+        insert_portal_parts(
+            commands,
+            create_portal,
+            PortalCamera {
+                image: Handle::default(),
+                portal_mode: create_portal.portal_mode.resolve(None),
+                parts: PortalParts {
+                    main_camera: main_camera_entity,
+                    portal: portal_entity,
+                    destination: destination_entity,
+                    portal_camera: portal_camera_entity,
+                    views: Vec::new(),
+                    despawn_strategy: create_portal.despawn_strategy.clone(),
+                },
+                auto_main_camera: false,
+                viewer: create_portal.viewer,
+                scissor_to_portal: false,
+                render_quality: create_portal.render_quality,
+                cull_mode: None,
+            },
+        );
+        return;
+    }
+
+    let CreatePortalParams {
         commands,
         portal_materials,
         meshes,
         materials,
         main_camera_query,
         size_params,
-    }: &mut CreatePortalParams,
-    portal_entity: Entity,
-    create_portal: &CreatePortal,
-    _portal_global_transform: &GlobalTransform,
-    portal_mesh: &Handle<Mesh>,
-) {
+        failure_policy,
+        reported_failures,
+        original_materials,
+        layer_allocator,
+        capabilities,
+        headless: _,
+    } = &mut *create_params;
+
     // Get main camera infos
     let (
         main_camera_entity,
@@ -184,35 +602,101 @@ fn create_portal(
         main_camera_deband_dither,
         main_camera_color_grading,
         main_camera_exposure,
-    ) = if let Some(camera_entity) = create_portal.main_camera {
-        main_camera_query.get(camera_entity).unwrap()
-    } else {
-        main_camera_query.iter().next().unwrap()
+        main_camera_msaa,
+        main_camera_bloom,
+        main_camera_ssao,
+        main_camera_shadow_filtering_method,
+    ) = match create_portal.main_camera {
+        Some(camera_entity) => match main_camera_query.get(camera_entity) {
+            Ok(main_camera) => main_camera,
+            Err(_) => {
+                portal_creation_failed(
+                    commands,
+                    portal_entity,
+                    PortalCreationError::MainCameraNotFound(camera_entity),
+                    failure_policy,
+                    reported_failures.contains(portal_entity),
+                );
+                return;
+            }
+        },
+        None => match main_camera_query.iter().next() {
+            Some(main_camera) => main_camera,
+            None => {
+                portal_creation_failed(
+                    commands,
+                    portal_entity,
+                    PortalCreationError::NoCamera,
+                    failure_policy,
+                    reported_failures.contains(portal_entity),
+                );
+                return;
+            }
+        },
     };
 
-    let main_camera_viewport_size =
-        get_viewport_size(main_camera, size_params).unwrap_or_else(|| {
+    let main_camera_viewport_size = size_params
+        .viewport_size(main_camera_entity, main_camera)
+        .unwrap_or_else(|| {
             error!("Viewport size not found, creating portal with default sized image");
             UVec2::new(100, 100)
         });
 
+    let (
+        Some(portal_materials),
+        Some(meshes),
+        Some(materials),
+        Some(layer_allocator),
+        Some(images),
+    ) = (
+        portal_materials.as_deref_mut(),
+        meshes.as_deref_mut(),
+        materials.as_deref_mut(),
+        layer_allocator.as_deref_mut(),
+        size_params.images.as_deref_mut(),
+    )
+    else {
+        error!("Portal {portal_entity} can't be rendered without the render plugins of Bevy, set PortalsPlugin::rendering to false on headless apps");
+        return;
+    };
+
     let size = Extent3d {
         width: main_camera_viewport_size.x,
         height: main_camera_viewport_size.y,
         ..Extent3d::default()
     };
 
+    // A negative scale flips the winding of the triangles of the portal mesh, which are always
+    // rendered with counter-clockwise front faces, see update_portal_cameras when it changes
+    let cull_mode = portal_cull_mode(create_portal.cull_mode, portal_global_transform);
+    if cull_mode != create_portal.cull_mode {
+        debug!("Portal {portal_entity} has a negative scale, flipping its cull mode");
+    }
+    // Both faces are rendered if the back faces are a portal too
+    let portal_material_cull_mode = create_portal
+        .cull_mode
+        .filter(|_| create_portal.back_destination.is_none() && !create_portal.double_sided);
+
     // Image that the PortalCamera will render to
+    let format = capabilities
+        .as_ref()
+        .map_or(PORTAL_IMAGE_FORMAT, |capabilities| {
+            capabilities.portal_image_format
+        });
     let mut portal_image = Image {
         texture_descriptor: TextureDescriptor {
-            label: None,
+            // Makes the portal images identifiable in GPU debuggers, kept when the image is resized.
+            // Each portal is identified by the Name of its portal camera.
+            label: Some("portal_image"),
             size,
             dimension: TextureDimension::D2,
-            format: TextureFormat::Bgra8UnormSrgb,
+            format,
             mip_level_count: 1,
             sample_count: 1,
+            // COPY_SRC for CapturePortalImage
             usage: TextureUsages::TEXTURE_BINDING
                 | TextureUsages::COPY_DST
+                | TextureUsages::COPY_SRC
                 | TextureUsages::RENDER_ATTACHMENT,
             view_formats: &[],
         },
@@ -221,47 +705,134 @@ fn create_portal(
 
     // Fill portal_image.data with zeroes
     portal_image.resize(size);
+    if let Some(initial_color) = create_portal.initial_color {
+        let [red, green, blue, alpha] = initial_color.to_srgba().to_u8_array();
+        let pixel_color = if format == PORTAL_IMAGE_FORMAT {
+            [blue, green, red, alpha]
+        } else {
+            [red, green, blue, alpha]
+        };
+        for pixel in portal_image.data.chunks_exact_mut(4) {
+            pixel.copy_from_slice(&pixel_color);
+        }
+    }
 
-    let portal_image = size_params.images.add(portal_image);
+    let portal_image = images.add(portal_image);
 
-    // Material that the portal camera will render to
-    let portal_material = portal_materials.add(PortalMaterial {
-        color_texture: Some(portal_image.clone()),
-        cull_mode: create_portal.cull_mode,
-    });
+    // The back portal shares the material of the front portal, which has a single depth image
+    let destination_depth_image = (create_portal.destination_depth && front_material.is_none())
+        .then(|| create_portal_depth_image(images, size));
 
-    // Create or get the destination entity
-    let destination_entity = match create_portal.destination {
-        AsPortalDestination::Use(entity) => entity,
-        AsPortalDestination::Create(CreatePortalDestination { transform, parent }) => {
-            let mut destination_commands = commands.spawn((
-                transform,
-                GlobalTransform::from(transform),
-            ));
-            if let Some(parent) = parent {
-                destination_commands.set_parent(parent);
+    // Material that the portal camera will render to
+    let portal_material = match front_material {
+        // The back portal shares the material of the front portal so that it is rebuilt when its image is resized
+        Some(front_material) => {
+            if let Some(material) = portal_materials.get_mut(front_material) {
+                material.back_texture = Some(portal_image.clone());
             }
-            destination_commands.id()
-        }
-        AsPortalDestination::CreateMirror => {
-            let mut destination_commands = commands.spawn(
-                Transform::from_rotation(Quat::from_axis_angle(Vec3::Y, PI))
-            );
-            destination_commands.set_parent(portal_entity);
-            destination_commands.id()
+            front_material.clone()
         }
+        None => portal_materials.add(PortalMaterial {
+            color_texture: Some(portal_image.clone()),
+            cull_mode: portal_cull_mode(portal_material_cull_mode, portal_global_transform),
+            mesh_uv: matches!(create_portal.viewer, PortalViewerMode::FixedCamera(_)),
+            ripple: create_portal.ripple.into(),
+            back_texture: None,
+            openness: 1.,
+            dissolve: create_portal
+                .dissolve
+                .as_ref()
+                .map_or(Vec4::ZERO, |dissolve| {
+                    Vec4::new(dissolve.threshold, dissolve.edge_width, 0., 0.)
+                }),
+            dissolve_edge_color: create_portal
+                .dissolve
+                .as_ref()
+                .map_or(Vec4::ZERO, |dissolve| dissolve.edge_color.to_vec4()),
+            dissolve_noise: create_portal
+                .dissolve
+                .as_ref()
+                .map(|dissolve| dissolve.noise.clone()),
+            alpha: create_portal.alpha,
+            heat_haze: create_portal
+                .heat_haze
+                .as_ref()
+                .map_or(Vec4::ZERO, |heat_haze| {
+                    Vec4::new(
+                        heat_haze.strength,
+                        0.,
+                        heat_haze.scroll_speed.x,
+                        heat_haze.scroll_speed.y,
+                    )
+                }),
+            heat_haze_noise: create_portal
+                .heat_haze
+                .as_ref()
+                .map(|heat_haze| heat_haze.noise.clone()),
+            refraction: create_portal
+                .refraction
+                .as_ref()
+                .map_or(0., |refraction| refraction.strength),
+            refraction_normal_map: create_portal
+                .refraction
+                .as_ref()
+                .map(|refraction| refraction.normal_map.clone()),
+            shadows: create_portal.shadows.map_or(0., |shadows| shadows.darkness),
+            destination_depth: Mat4::IDENTITY,
+            destination_depth_texture: destination_depth_image.clone(),
+            impostor: Vec4::ZERO,
+            viewport: main_camera
+                .physical_viewport_rect()
+                .map_or(Vec4::ZERO, |rect| {
+                    Vec4::new(
+                        rect.min.x as f32,
+                        rect.min.y as f32,
+                        rect.width() as f32,
+                        rect.height() as f32,
+                    )
+                }),
+        }),
+    };
+
+    let destination_entity =
+        spawn_portal_destination(commands, portal_entity, &create_portal.destination);
+
+    // Render layer of the portal camera and the debug elements,
+    // destination scenes are isolated on their own layer unless another one is chosen
+    #[cfg(feature = "scene")]
+    let allocate_render_layer = create_portal.allocate_render_layer
+        || (matches!(
+            &create_portal.destination,
+            PortalDestinationSource::Create(CreatePortalDestination { scene: Some(_), .. })
+        ) && create_portal.render_layer == RenderLayers::default());
+    #[cfg(not(feature = "scene"))]
+    let allocate_render_layer = create_portal.allocate_render_layer;
+    let allocated_layer = allocate_render_layer.then(|| layer_allocator.allocate());
+    let render_layer = match allocated_layer {
+        Some(allocated_layer) => RenderLayers::layer(allocated_layer),
+        None => create_portal.render_layer.clone(),
     };
 
     // Create the portal camera
-    let projection: PortalProjection = main_camera_projection
-        .cloned()
-        .unwrap_or_else(|| Projection::default())
-        .into();
-    let portal_camera_entity = commands
-        .spawn((
+    let projection: PortalProjection = match &create_portal.projection {
+        Some(projection) => projection.clone(),
+        None => match main_camera_projection {
+            Some(main_camera_projection) => main_camera_projection.clone().into(),
+            None => {
+                warn!("Main camera has no Projection, creating portal camera with a default perspective projection, set CreatePortal::projection to use a custom projection");
+                PortalProjection::default()
+            }
+        },
+    };
+    let mut portal_camera_commands = match create_portal.portal_camera {
+        Some(portal_camera_entity) => commands.entity(portal_camera_entity),
+        None => commands.spawn_empty(),
+    };
+    let portal_camera_entity = portal_camera_commands
+        .insert((
             main_camera_camera3d
                 .cloned()
-                .unwrap_or_else(|| Camera3d::default()),
+                .unwrap_or_default(),
             Camera {
                 order: -1,
                 target: RenderTarget::Image(portal_image.clone()),
@@ -270,21 +841,22 @@ fn create_portal(
             projection,
             main_camera_tonemapping
                 .cloned()
-                .unwrap_or_else(|| Tonemapping::default()),
+                .unwrap_or_default(),
             main_camera_deband_dither
                 .cloned()
-                .unwrap_or_else(|| DebandDither::default()),
+                .unwrap_or_default(),
             main_camera_color_grading
                 .cloned()
-                .unwrap_or_else(|| ColorGrading::default()),
+                .unwrap_or_default(),
             main_camera_exposure
                 .cloned()
-                .unwrap_or_else(|| Exposure::default()),
+                .unwrap_or_default(),
             Visibility::Hidden,
-            create_portal.render_layer.clone(),
+            render_layer.clone(),
+            Name::new(format!("Portal Camera {portal_entity}")),
             // TOFIX set the exact value of Transform and GlobalTransform to avoid black screen at spawn
             // let portal_camera_transform = get_portal_camera_transform(main_camera_transform, portal_transform, &destination_transform);
-            // This requires an extra Query to get destination_transform when AsPortalDestination::Entity/CreateMirror
+            // This requires an extra Query to get destination_transform when PortalDestinationSource::Entity/CreateMirror
             // Would still matter if the portal camera is a child of the destination
             //transform: portal_camera_transform,
             //global_transorm: GlobalTransform::from(portal_camera_transform),399c716bba4597815e3ee11be6cd999e
@@ -292,30 +864,146 @@ fn create_portal(
         .remove::<Projection>() // Required component of `Camera3d`, but in this specific case we don't want it
         .id();
 
+    match create_portal.render_quality {
+        PortalRenderQuality::Full => {
+            let mut portal_camera_commands = commands.entity(portal_camera_entity);
+            portal_camera_commands.insert(main_camera_msaa.copied().unwrap_or_default());
+            if let Some(bloom) = main_camera_bloom {
+                portal_camera_commands.insert(bloom.clone());
+            }
+            if let Some(ssao) = main_camera_ssao {
+                portal_camera_commands.insert(ssao.clone());
+            }
+            portal_camera_commands.insert(
+                main_camera_shadow_filtering_method
+                    .copied()
+                    .unwrap_or_default(),
+            );
+        }
+        PortalRenderQuality::Simplified => {
+            commands.entity(portal_camera_entity).insert((
+                main_camera_msaa.copied().unwrap_or_default(),
+                ShadowFilteringMethod::Hardware2x2,
+            ));
+        }
+        PortalRenderQuality::Fastest => {
+            commands.entity(portal_camera_entity).insert((
+                Msaa::Off,
+                DebandDither::Disabled,
+                ShadowFilteringMethod::Hardware2x2,
+            ));
+        }
+    }
+
+    if let Some(destination_depth_image) = destination_depth_image {
+        let mut camera3d = main_camera_camera3d.cloned().unwrap_or_default();
+        camera3d.depth_texture_usages =
+            (TextureUsages::from(camera3d.depth_texture_usages) | TextureUsages::COPY_SRC).into();
+        commands.entity(portal_camera_entity).insert((
+            camera3d,
+            Msaa::Off,
+            PortalDestinationDepth {
+                image: destination_depth_image,
+            },
+        ));
+    }
+
+    // The portal is the view of its main camera, the views of the other main cameras are
+    // created by update_portal_views, not for the back faces which follow the front portal
+    let view_layer = (create_portal.main_camera_views && front_material.is_none())
+        .then(|| layer_allocator.allocate());
+
     // Add portal components
     let parts = PortalParts {
         main_camera: main_camera_entity,
         portal: portal_entity,
         destination: destination_entity,
         portal_camera: portal_camera_entity,
+        views: view_layer.map(|_| portal_entity).into_iter().collect(),
+        despawn_strategy: create_portal.despawn_strategy.clone(),
     };
 
     let mut portal_entity_command = commands.entity(portal_entity);
-    portal_entity_command.insert(MeshMaterial3d(portal_material));
+    if let Ok(original_material) = original_materials.get(portal_entity) {
+        portal_entity_command
+            .remove::<MeshMaterial3d<StandardMaterial>>()
+            .insert(PortalOriginalMaterial(original_material.0.clone()));
+    }
+    portal_entity_command.insert((
+        MeshMaterial3d(portal_material.clone()),
+        PortalBaseMaterial(portal_material.clone()),
+    ));
     portal_entity_command.remove::<CreatePortal>();
-    portal_entity_command.insert(Portal {
-        parts: parts.clone(),
-    });
+    if let Some(allocated_layer) = allocated_layer {
+        portal_entity_command.insert(PortalAllocatedLayer(allocated_layer));
+    }
+    if let Some(view_layer) = view_layer {
+        portal_entity_command.insert((
+            PortalView {
+                portal: portal_entity,
+                main_camera: main_camera_entity,
+                layer: view_layer,
+            },
+            PortalViewConfig(CreatePortal {
+                main_camera_views: false,
+                back_destination: None,
+                double_sided: false,
+                portal_camera: None,
+                // The destination of a view is created under the destination of the portal,
+                // it shares its layer
+                render_layer: render_layer.clone(),
+                allocate_render_layer: false,
+                border: None,
+                debug: None,
+                ..create_portal.clone()
+            }),
+            RenderLayers::layer(view_layer),
+        ));
+    }
 
-    commands.entity(portal_camera_entity).insert(PortalCamera {
-        image: portal_image,
-        portal_mode: create_portal.portal_mode.clone(),
-        parts: parts.clone(),
-    });
+    insert_portal_parts(
+        commands,
+        create_portal,
+        PortalCamera {
+            image: portal_image,
+            portal_mode: create_portal.portal_mode.resolve(meshes.get(portal_mesh)),
+            parts,
+            auto_main_camera: create_portal.main_camera.is_none(),
+            viewer: create_portal.viewer,
+            scissor_to_portal: create_portal.scissor_to_portal,
+            render_quality: create_portal.render_quality,
+            cull_mode: portal_material_cull_mode,
+        },
+    );
 
-    commands
-        .entity(destination_entity)
-        .insert(PortalDestination { parts });
+    // Border
+    if let Some(border) = &create_portal.border {
+        if let Some(portal_aabb) = meshes.get(portal_mesh).and_then(Mesh::compute_aabb) {
+            let half_size = portal_aabb
+                .half_extents
+                .truncate()
+                .max(Vec2::splat(f32::EPSILON));
+            let center = Vec3::from(portal_aabb.center);
+            let scale = ((half_size + border.width) / half_size).extend(1.);
+            let mut border_material: StandardMaterial = border.color.into();
+            border_material.emissive = border.emissive;
+            border_material.cull_mode = cull_mode;
+            commands.entity(portal_entity).with_children(|parent| {
+                parent.spawn((
+                    Mesh3d(portal_mesh.clone()),
+                    MeshMaterial3d(materials.add(border_material)),
+                    // Scaled around the center of the mesh, and just behind it so that the portal is drawn on top
+                    Transform::from_translation(
+                        center - center * scale + Vec3::new(0., 0., -0.001),
+                    )
+                    .with_scale(scale),
+                    PortalSpawnedEntity,
+                ));
+            });
+        } else {
+            warn!("Portal mesh not found or without positions, creating portal without a border");
+        }
+    }
 
     // Debug
     if let Some(debug) = &create_portal.debug {
@@ -336,6 +1024,7 @@ fn create_portal(
                     window_level: WindowLevel::AlwaysOnBottom,
                     ..Window::default()
                 })
+                .insert(PortalSpawnedEntity)
                 .id();
             commands
                 .entity(portal_camera_entity)
@@ -348,7 +1037,8 @@ fn create_portal(
                             ..Camera::default()
                         },
                         PortalDebugCamera {},
-                        create_portal.render_layer.clone(),
+                        Name::new(format!("Portal Debug Camera {portal_entity}")),
+                        render_layer.clone(),
                     ));
                 });
         }
@@ -359,7 +1049,8 @@ fn create_portal(
                 parent.spawn((
                     Mesh3d(meshes.add(Sphere::new(0.1).mesh().ico(5).unwrap())),
                     MeshMaterial3d(materials.add(debug_color)),
-                    create_portal.render_layer.clone(),
+                    render_layer.clone(),
+                    PortalSpawnedEntity,
                 ));
             });
         }
@@ -376,7 +1067,8 @@ fn create_portal(
                     // So that it can still be seen through the portal,
                     // despite rounding frustum mismatch
                     Transform::from_xyz(0., 0., -0.001),
-                    create_portal.render_layer.clone(),
+                    render_layer.clone(),
+                    PortalSpawnedEntity,
                 ));
             });
         }
@@ -390,21 +1082,150 @@ fn create_portal(
                         Mesh3d(meshes.add(Sphere::new(0.1).mesh().ico(5).unwrap())),
                         MeshMaterial3d(materials.add(debug_color)),
                         Visibility::Visible,
-                        create_portal.render_layer.clone(),
+                        render_layer.clone(),
                     ));
                 });
         }
+
+        #[cfg(feature = "gizmos")]
+        if debug.show_frustum || debug.show_half_space {
+            commands
+                .entity(portal_camera_entity)
+                .insert(PortalDebugGizmos {
+                    color: debug_color,
+                    frustum: debug.show_frustum,
+                    half_space: debug.show_half_space,
+                });
+        }
+        #[cfg(not(feature = "gizmos"))]
+        if debug.show_frustum || debug.show_half_space {
+            warn!("DebugPortal::show_frustum and DebugPortal::show_half_space need the \"gizmos\" feature, not drawing them for portal {portal_entity}");
+        }
+    }
+
+    // Back faces
+    let back_destination = match (&create_portal.back_destination, &create_portal.destination) {
+        (Some(back_destination), _) => Some(back_destination.clone()),
+        (None, _) if !create_portal.double_sided => None,
+        (None, destination) => Some(match destination.mirror_config() {
+            Some(mirror_config) => PortalDestinationSource::CreateMirrorWith(mirror_config.back()),
+            // The back portal is rotated by PI around Y, so is the back of the destination
+            None => PortalDestinationSource::Create(CreatePortalDestination {
+                parent: Some(destination_entity),
+                ..Transform::from_rotation(Quat::from_axis_angle(Vec3::Y, PI)).into()
+            }),
+        }),
+    };
+    if let Some(PortalDestinationSource::Resolve(_)) = &back_destination {
+        warn!("Back destination of portal {portal_entity} not resolved, creating it without back faces");
+    } else if let Some(back_destination) = back_destination {
+        // Hidden so that only the front portal's mesh is rendered, used for the transform and Aabb
+        let back_portal_entity = commands
+            .spawn((
+                Mesh3d(portal_mesh.clone()),
+                Transform::from_rotation(Quat::from_axis_angle(Vec3::Y, PI)),
+                Visibility::Hidden,
+                PortalSpawnedEntity,
+            ))
+            .set_parent(portal_entity)
+            .id();
+        let back_create_portal = CreatePortal {
+            destination: back_destination,
+            back_destination: None,
+            double_sided: false,
+            // The back of the destination is one of its descendants, it shares its layer
+            render_layer: render_layer.clone(),
+            allocate_render_layer: allocate_render_layer && !create_portal.double_sided,
+            portal_camera: None,
+            // Already created by the front portal
+            border: None,
+            ..create_portal.clone()
+        };
+        let back_portal_global_transform = portal_global_transform
+            .mul_transform(Transform::from_rotation(Quat::from_axis_angle(Vec3::Y, PI)));
+        self::create_portal(
+            create_params,
+            back_portal_entity,
+            &back_create_portal,
+            &back_portal_global_transform,
+            portal_mesh,
+            Some(&portal_material),
+        );
     }
 }
 
+/// Creates or gets the destination entity of a portal
+pub(super) fn spawn_portal_destination(
+    commands: &mut Commands,
+    portal_entity: Entity,
+    destination: &PortalDestinationSource,
+) -> Entity {
+    match destination {
+        PortalDestinationSource::Use(entity) => *entity,
+        PortalDestinationSource::Create(create_destination) => {
+            let mut destination_commands = commands.spawn((
+                create_destination.transform,
+                GlobalTransform::from(create_destination.transform),
+                PortalSpawnedEntity,
+            ));
+            if let Some(parent) = create_destination.parent {
+                destination_commands.set_parent(parent);
+            }
+            if create_destination.auto_place {
+                destination_commands.queue(PlacePortalDestinationCommand);
+            }
+            #[cfg(feature = "scene")]
+            if let Some(scene) = &create_destination.scene {
+                destination_commands.insert(SceneRoot(scene.clone()));
+            }
+            destination_commands.id()
+        }
+        PortalDestinationSource::CreateMirror | PortalDestinationSource::CreateMirrorWith(_) => {
+            let mirror_config = destination.mirror_config().unwrap_or_default();
+            let mut destination_commands =
+                commands.spawn((mirror_config.destination_transform(), PortalSpawnedEntity));
+            destination_commands.set_parent(portal_entity);
+            destination_commands.id()
+        }
+        PortalDestinationSource::Resolve(_) => unreachable!("Checked by create_portal"),
+    }
+}
+
+/// Inserts the components referencing the [PortalParts] and sends [PortalCreated]
+fn insert_portal_parts(
+    commands: &mut Commands,
+    create_portal: &CreatePortal,
+    portal_camera: PortalCamera,
+) {
+    let parts = portal_camera.parts.clone();
+    commands
+        .entity(parts.portal)
+        .remove::<PortalCreationFailureReported>()
+        .insert(Portal {
+            parts: parts.clone(),
+        });
+    commands.entity(parts.portal_camera).insert(portal_camera);
+    if let Some(portal_camera_insert) = &create_portal.portal_camera_insert {
+        portal_camera_insert(&mut commands.entity(parts.portal_camera));
+    }
+    commands
+        .entity(parts.destination)
+        .insert(PortalDestination {
+            parts: parts.clone(),
+        });
+    commands.send_event(PortalCreated { parts });
+}
+
 /// [SystemParam] needed for [create_portals]
+///
+/// The render resources are missing when [PortalsPlugin::rendering] is false, see [PortalsHeadless].
 #[derive(SystemParam)]
 #[allow(clippy::type_complexity)]
 pub struct CreatePortalParams<'w, 's> {
     commands: Commands<'w, 's>,
-    portal_materials: ResMut<'w, Assets<PortalMaterial>>,
-    meshes: ResMut<'w, Assets<Mesh>>,
-    materials: ResMut<'w, Assets<StandardMaterial>>,
+    portal_materials: Option<ResMut<'w, Assets<PortalMaterial>>>,
+    meshes: Option<ResMut<'w, Assets<Mesh>>>,
+    materials: Option<ResMut<'w, Assets<StandardMaterial>>>,
     main_camera_query: Query<
         'w,
         's,
@@ -417,7 +1238,17 @@ pub struct CreatePortalParams<'w, 's> {
             Option<&'static DebandDither>,
             Option<&'static ColorGrading>,
             Option<&'static Exposure>,
+            Option<&'static Msaa>,
+            Option<&'static Bloom>,
+            Option<&'static ScreenSpaceAmbientOcclusion>,
+            Option<&'static ShadowFilteringMethod>,
         ),
     >,
     size_params: PortalImageSizeParams<'w, 's>,
+    failure_policy: Res<'w, PortalCreationFailurePolicy>,
+    reported_failures: Query<'w, 's, (), With<PortalCreationFailureReported>>,
+    original_materials: Query<'w, 's, &'static MeshMaterial3d<StandardMaterial>>,
+    layer_allocator: Option<ResMut<'w, PortalLayerAllocator>>,
+    capabilities: Option<Res<'w, PortalsCapabilities>>,
+    headless: Option<Res<'w, PortalsHeadless>>,
 }
@@ -0,0 +1,105 @@
+//! Components and commands to place things coherently on both sides of a portal
+
+use bevy_app::prelude::*;
+use bevy_ecs::{prelude::*, system::EntityCommand};
+use bevy_hierarchy::prelude::*;
+use bevy_math::Vec3;
+use bevy_reflect::Reflect;
+use bevy_render::prelude::*;
+use bevy_transform::prelude::*;
+use tracing::warn;
+
+use super::*;
+
+/// Add the decal logic to [PortalsPlugin]
+pub(super) fn build_decal(app: &mut App) {
+    app.register_type::<PortalDecalAnchor>();
+}
+
+/// [Component] of an anchor spawned by [spawn_portal_decal_anchors].
+///
+/// Add decals (cracks, bullet holes...) as children of both anchors of a pair, they will stay
+/// in sync as the portal and the destination move.
+#[derive(Component, Clone, Copy, Debug, Reflect)]
+#[reflect(Component)]
+pub struct PortalDecalAnchor {
+    /// The entity with the [Portal] component.
+    pub portal: Entity,
+    /// The other anchor of the pair.
+    pub pair: Entity,
+    /// True for the anchor on the portal, false for the anchor at the destination.
+    pub on_portal: bool,
+}
+
+/// [EntityCommand] to spawn paired decal anchors on a portal, see [spawn_portal_decal_anchors].
+pub struct SpawnPortalDecalAnchors {
+    /// Position of the hit on the portal surface, in world space.
+    pub position: Vec3,
+    /// Normal of the portal surface at the hit, in world space.
+    pub normal: Vec3,
+}
+
+impl EntityCommand for SpawnPortalDecalAnchors {
+    fn apply(self, id: Entity, world: &mut World) {
+        spawn_portal_decal_anchors(world, id, self.position, self.normal);
+    }
+}
+
+/// Spawns two anchors for a hit on a portal surface (for example from picking):
+/// one as a child of the portal at the hit, and one as a child of the destination,
+/// at the point seen through the portal at the hit.
+///
+/// The anchors look towards `-normal`, their forward is "into" the surface.
+/// They have a [Visibility] so that the visibility of their decal children is propagated.
+///
+/// Returns the anchor on the portal and the anchor at the destination,
+/// or `None` if `portal` is not a created [Portal].
+pub fn spawn_portal_decal_anchors(
+    world: &mut World,
+    portal: Entity,
+    position: Vec3,
+    normal: Vec3,
+) -> Option<(Entity, Entity)> {
+    let Some((portal_global_transform, destination)) = world
+        .get::<Portal>(portal)
+        .map(|portal_component| portal_component.parts.destination)
+        .and_then(|destination| {
+            world
+                .get::<GlobalTransform>(portal)
+                .map(|portal_global_transform| (*portal_global_transform, destination))
+        })
+    else {
+        warn!("Entity {portal} is not a portal, not spawning decal anchors");
+        return None;
+    };
+
+    let hit_transform = Transform::from_translation(position).looking_to(-normal, Vec3::Y);
+    // The same local transform relative to the portal and to the destination,
+    // like the portal camera relative to the main camera
+    let local_transform =
+        GlobalTransform::from(hit_transform).reparented_to(&portal_global_transform);
+
+    let portal_anchor = world
+        .spawn((local_transform, Visibility::default()))
+        .set_parent(portal)
+        .id();
+    let destination_anchor = world
+        .spawn((
+            local_transform,
+            Visibility::default(),
+            PortalDecalAnchor {
+                portal,
+                pair: portal_anchor,
+                on_portal: false,
+            },
+        ))
+        .set_parent(destination)
+        .id();
+    world.entity_mut(portal_anchor).insert(PortalDecalAnchor {
+        portal,
+        pair: destination_anchor,
+        on_portal: true,
+    });
+
+    Some((portal_anchor, destination_anchor))
+}
@@ -0,0 +1,263 @@
+//! Output of the depth of the destination by the portal surface
+
+use bevy_app::prelude::*;
+use bevy_asset::prelude::*;
+use bevy_core_pipeline::core_3d::graph::{Core3d, Node3d};
+use bevy_ecs::{prelude::*, query::QueryItem};
+use bevy_image::Image;
+use bevy_math::UVec2;
+use bevy_reflect::Reflect;
+use bevy_render::{
+    camera::CameraProjection,
+    extract_component::{ExtractComponent, ExtractComponentPlugin},
+    prelude::*,
+    render_asset::RenderAssets,
+    render_graph::{
+        NodeRunError, RenderGraphApp, RenderGraphContext, RenderLabel, ViewNode, ViewNodeRunner,
+    },
+    render_resource::{
+        Buffer, BufferDescriptor, BufferUsages, Extent3d, ImageCopyBuffer, ImageCopyTexture,
+        ImageDataLayout, Origin3d, TextureAspect, TextureDescriptor, TextureDimension,
+        TextureFormat, TextureUsages,
+    },
+    renderer::{RenderContext, RenderDevice},
+    texture::GpuImage,
+    view::ViewDepthTexture,
+    Render, RenderApp, RenderSet,
+};
+use bevy_transform::prelude::*;
+
+use super::*;
+
+/// Add the destination depth logic to [PortalsPlugin]
+pub(super) fn build_depth(app: &mut App) {
+    app.register_type::<PortalDestinationDepth>()
+        .add_plugins(ExtractComponentPlugin::<PortalDestinationDepth>::default())
+        .add_systems(
+            PostUpdate,
+            update_portal_destination_depths
+                .in_set(PortalSystems::UpdateCameras)
+                .after(update_portal_cameras),
+        );
+
+    let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+        return;
+    };
+    render_app
+        .add_systems(
+            Render,
+            prepare_portal_depth_copy_buffers.in_set(RenderSet::PrepareResources),
+        )
+        .add_render_graph_node::<ViewNodeRunner<PortalDepthCopyNode>>(Core3d, PortalDepthCopy)
+        .add_render_graph_edges(Core3d, (Node3d::EndMainPass, PortalDepthCopy));
+}
+
+/// [Component] of a [PortalCamera] copying its depth to an image after its main pass,
+/// see [CreatePortal::destination_depth].
+///
+/// The depth is copied to an [TextureFormat::R32Float] image because depth images can't be
+/// uploaded from the CPU like the other [Image]s.
+/// The portal camera must not use MSAA, and its [Camera3d::depth_texture_usages] must contain
+/// [TextureUsages::COPY_SRC].
+#[derive(Component, ExtractComponent, Clone, Reflect)]
+#[reflect(Component)]
+pub struct PortalDestinationDepth {
+    pub image: Handle<Image>,
+}
+
+/// Helper function to create the image of a [PortalDestinationDepth]
+pub(super) fn create_portal_depth_image(
+    images: &mut Assets<Image>,
+    size: Extent3d,
+) -> Handle<Image> {
+    let mut depth_image = Image {
+        texture_descriptor: TextureDescriptor {
+            label: Some("portal_depth"),
+            size,
+            dimension: TextureDimension::D2,
+            format: TextureFormat::R32Float,
+            mip_level_count: 1,
+            sample_count: 1,
+            usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
+            view_formats: &[],
+        },
+        ..Image::default()
+    };
+    depth_image.resize(size);
+    images.add(depth_image)
+}
+
+/// [System] that updates [PortalMaterial::destination_depth] from the cameras of the portals
+/// with a [PortalDestinationDepth], and resizes its image with the portal image.
+#[allow(clippy::type_complexity)]
+pub fn update_portal_destination_depths(
+    portal_query: Query<(&Portal, &GlobalTransform, &PortalBaseMaterial)>,
+    portal_camera_query: Query<(
+        &PortalCamera,
+        &PortalDestinationDepth,
+        &GlobalTransform,
+        &PortalProjection,
+        &Camera,
+    )>,
+    destination_query: Query<&GlobalTransform, With<PortalDestination>>,
+    mut materials: ResMut<Assets<PortalMaterial>>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    for (portal, portal_global_transform, portal_material) in portal_query.iter() {
+        let Ok((portal_camera, depth, portal_camera_global_transform, projection, camera)) =
+            portal_camera_query.get(portal.parts.portal_camera)
+        else {
+            continue;
+        };
+        let Ok(destination_global_transform) = destination_query.get(portal.parts.destination)
+        else {
+            continue;
+        };
+
+        let portal_image_size = images.get(&portal_camera.image).map(Image::size);
+        let depth_image_size = images.get(&depth.image).map(Image::size);
+        if let (Some(portal_image_size), Some(depth_image_size)) =
+            (portal_image_size, depth_image_size)
+        {
+            if portal_image_size != depth_image_size && portal_image_size != UVec2::ZERO {
+                if let Some(depth_image) = images.get_mut(&depth.image) {
+                    let size = Extent3d {
+                        width: portal_image_size.x,
+                        height: portal_image_size.y,
+                        ..Extent3d::default()
+                    };
+                    depth_image.texture_descriptor.size = size;
+                    depth_image.resize(size);
+                }
+            }
+        }
+
+        // Camera on the side of the portal seeing what the portal camera sees,
+        // the depth of the destination is unprojected with it to be reprojected by the main camera
+        let world_from_virtual_camera = portal_global_transform.compute_matrix()
+            * destination_global_transform.compute_matrix().inverse()
+            * portal_camera_global_transform.compute_matrix();
+        let clip_from_view = match camera.sub_camera_view.as_ref() {
+            Some(sub_camera_view) => projection.get_clip_from_view_for_sub(sub_camera_view),
+            None => projection.get_clip_from_view(),
+        };
+        let world_from_clip = world_from_virtual_camera * clip_from_view.inverse();
+
+        // Only touch the material when needed, it rebuilds its bind group
+        if materials
+            .get(&portal_material.0)
+            .is_some_and(|material| material.destination_depth != world_from_clip)
+        {
+            if let Some(material) = materials.get_mut(&portal_material.0) {
+                material.destination_depth = world_from_clip;
+            }
+        }
+    }
+}
+
+/// [RenderLabel] of [PortalDepthCopyNode]
+#[derive(Debug, Hash, PartialEq, Eq, Clone, RenderLabel)]
+pub struct PortalDepthCopy;
+
+/// Render world [Component] with the buffer used to copy the depth of a [PortalDestinationDepth]
+#[derive(Component)]
+pub struct PortalDepthCopyBuffer {
+    buffer: Buffer,
+    size: UVec2,
+}
+
+/// [System] of the render world that creates the [PortalDepthCopyBuffer]s
+fn prepare_portal_depth_copy_buffers(
+    mut commands: Commands,
+    views: Query<(
+        Entity,
+        &PortalDestinationDepth,
+        Option<&PortalDepthCopyBuffer>,
+    )>,
+    images: Res<RenderAssets<GpuImage>>,
+    render_device: Res<RenderDevice>,
+) {
+    for (entity, depth, copy_buffer) in views.iter() {
+        let Some(gpu_image) = images.get(&depth.image) else {
+            continue;
+        };
+        if copy_buffer.is_some_and(|copy_buffer| copy_buffer.size == gpu_image.size) {
+            continue;
+        }
+        let padded_bytes_per_row =
+            RenderDevice::align_copy_bytes_per_row(gpu_image.size.x as usize * 4);
+        let buffer = render_device.create_buffer(&BufferDescriptor {
+            label: Some("portal_depth_copy_buffer"),
+            size: (padded_bytes_per_row * gpu_image.size.y as usize) as u64,
+            usage: BufferUsages::COPY_SRC | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        commands.entity(entity).insert(PortalDepthCopyBuffer {
+            buffer,
+            size: gpu_image.size,
+        });
+    }
+}
+
+/// [ViewNode] copying the depth of a [PortalCamera] to the image of its [PortalDestinationDepth]
+#[derive(Default)]
+pub struct PortalDepthCopyNode;
+
+impl ViewNode for PortalDepthCopyNode {
+    type ViewQuery = (
+        &'static ViewDepthTexture,
+        &'static PortalDestinationDepth,
+        &'static PortalDepthCopyBuffer,
+    );
+
+    fn run<'w>(
+        &self,
+        _graph: &mut RenderGraphContext,
+        render_context: &mut RenderContext<'w>,
+        (depth_texture, depth, copy_buffer): QueryItem<'w, Self::ViewQuery>,
+        world: &'w World,
+    ) -> Result<(), NodeRunError> {
+        let Some(gpu_image) = world.resource::<RenderAssets<GpuImage>>().get(&depth.image) else {
+            return Ok(());
+        };
+        let size = depth_texture.texture.size();
+        // The images are resized one after the other, depth textures can only be copied whole
+        if gpu_image.size != copy_buffer.size
+            || gpu_image.size != UVec2::new(size.width, size.height)
+            || depth_texture.texture.sample_count() != 1
+        {
+            return Ok(());
+        }
+
+        let layout = ImageDataLayout {
+            offset: 0,
+            bytes_per_row: Some(
+                RenderDevice::align_copy_bytes_per_row(size.width as usize * 4) as u32,
+            ),
+            rows_per_image: None,
+        };
+        let encoder = render_context.command_encoder();
+        encoder.copy_texture_to_buffer(
+            ImageCopyTexture {
+                texture: &depth_texture.texture,
+                mip_level: 0,
+                origin: Origin3d::ZERO,
+                aspect: TextureAspect::DepthOnly,
+            },
+            ImageCopyBuffer {
+                buffer: &copy_buffer.buffer,
+                layout,
+            },
+            size,
+        );
+        encoder.copy_buffer_to_texture(
+            ImageCopyBuffer {
+                buffer: &copy_buffer.buffer,
+                layout,
+            },
+            gpu_image.texture.as_image_copy(),
+            size,
+        );
+        Ok(())
+    }
+}
@@ -1,15 +1,22 @@
 //! System and helpers for the update of portal cameras
 
 use bevy_app::prelude::*;
+use bevy_asset::{Assets, Handle};
 use bevy_ecs::{
     prelude::*,
     query::QueryEntityError,
     system::{EntityCommand, SystemState},
     world::Command,
 };
-use bevy_hierarchy::DespawnRecursiveExt;
-use bevy_render::camera::Camera;
+use bevy_hierarchy::{prelude::*, DespawnRecursiveExt};
+use bevy_image::Image;
+use bevy_pbr::MeshMaterial3d;
+use bevy_render::{
+    camera::{Camera, RenderTarget},
+    view::RenderLayers,
+};
 use bevy_transform::prelude::*;
+use bevy_window::WindowRef;
 use tracing::warn;
 
 use super::*;
@@ -20,7 +27,8 @@ pub(super) fn build_despawn(
     despawn_strategy: Option<PortalPartsDespawnStrategy>,
     should_check_portal_camera_despawn: bool,
 ) {
-    app.register_type::<PortalPartsDespawnStrategy>();
+    app.register_type::<PortalPartsDespawnStrategy>()
+        .register_type::<PortalPartDespawnStrategy>();
 
     if let Some(despawn_strategy) = despawn_strategy {
         app.insert_resource(despawn_strategy);
@@ -29,7 +37,10 @@ pub(super) fn build_despawn(
     }
 
     if should_check_portal_camera_despawn {
-        app.add_systems(Update, check_portal_camera_despawn);
+        app.add_systems(
+            Update,
+            check_portal_camera_despawn.in_set(PortalSystems::Despawn),
+        );
     }
 }
 
@@ -92,6 +103,134 @@ impl EntityCommand for DespawnPortalPartsEntityCommand {
     }
 }
 
+/// [EntityCommand] undoing the creation of a portal, the inverse of [CreatePortalCommand].
+///
+/// Queued on the portal entity, it despawns the [PortalCamera] with its image and debug window,
+/// the destination if it was created by this crate (see [PortalSpawnedEntity]), the border,
+/// debug entities, back faces and [PortalView]s, and frees the [PortalMaterial].
+/// The portal entity itself is kept with its mesh, the [Portal] and material components are
+/// removed and the [PortalOriginalMaterial] is put back, so the mesh can be reused.
+/// Destinations not created by this crate are kept too, without their [PortalDestination].
+///
+/// Materials added by a [PortalMaterialExtensionPlugin] are not removed.
+#[derive(Default)]
+pub struct RemovePortalCommand;
+
+impl EntityCommand for RemovePortalCommand {
+    fn apply(self, id: Entity, world: &mut World) {
+        let Some(portal) = world.get::<Portal>(id) else {
+            warn!("RemovePortalCommand called on entity {id} which is not a portal");
+            return;
+        };
+        let parts = portal.parts.clone();
+        remove_portal(world, &parts);
+        world.send_event(PortalPartsDespawned { parts });
+    }
+}
+
+/// Logic of [RemovePortalCommand], also used for the back faces
+fn remove_portal(world: &mut World, parts: &PortalParts) {
+    let children = |world: &World, entity: Entity| {
+        world
+            .get::<Children>(entity)
+            .map(|children| children.to_vec())
+            .unwrap_or_default()
+    };
+
+    // Border and back faces
+    for child in children(world, parts.portal) {
+        if world.get::<PortalSpawnedEntity>(child).is_none() {
+            continue;
+        }
+        if let Some(back_portal) = world.get::<Portal>(child) {
+            let back_parts = back_portal.parts.clone();
+            remove_portal(world, &back_parts);
+        }
+        world.entity_mut(child).despawn_recursive();
+    }
+
+    // Portal camera, its images and debug window
+    if let Ok(portal_camera_entity) = world.get_entity(parts.portal_camera) {
+        let images: Vec<Handle<Image>> = [
+            portal_camera_entity
+                .get::<PortalCamera>()
+                .map(|portal_camera| portal_camera.image.clone()),
+            portal_camera_entity
+                .get::<PortalDestinationDepth>()
+                .map(|destination_depth| destination_depth.image.clone()),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        let debug_windows: Vec<Entity> = children(world, parts.portal_camera)
+            .into_iter()
+            .filter_map(|child| {
+                let child = world.get_entity(child).ok()?;
+                if !child.contains::<PortalDebugCamera>() {
+                    return None;
+                }
+                match child.get::<Camera>()?.target {
+                    RenderTarget::Window(WindowRef::Entity(window)) => Some(window),
+                    _ => None,
+                }
+            })
+            .collect();
+
+        world.entity_mut(parts.portal_camera).despawn_recursive();
+        for window in debug_windows {
+            if let Ok(window) = world.get_entity_mut(window) {
+                window.despawn();
+            }
+        }
+        if let Some(mut image_assets) = world.get_resource_mut::<Assets<Image>>() {
+            for image in images {
+                image_assets.remove(&image);
+            }
+        }
+    }
+
+    // Destination
+    if world
+        .get::<PortalSpawnedEntity>(parts.destination)
+        .is_some()
+    {
+        world.entity_mut(parts.destination).despawn_recursive();
+    } else if world.get_entity(parts.destination).is_ok() {
+        for child in children(world, parts.destination) {
+            if world.get::<PortalSpawnedEntity>(child).is_some() {
+                world.entity_mut(child).despawn_recursive();
+            }
+        }
+        world
+            .entity_mut(parts.destination)
+            .remove::<PortalDestination>();
+    }
+
+    // Portal, with its required components
+    if let Ok(mut portal) = world.get_entity_mut(parts.portal) {
+        let portal_material = portal.take::<PortalBaseMaterial>();
+        // Put on the portal mesh with its views, see CreatePortal::main_camera_views
+        if portal.take::<PortalView>().is_some() {
+            portal.remove::<(PortalViewConfig, RenderLayers)>();
+        }
+        portal.remove::<(
+            Portal,
+            PortalAllocatedLayer,
+            PortalViewInfo,
+            PortalDeactivation,
+            MeshMaterial3d<PortalMaterial>,
+            MeshMaterial3d<LitPortalMaterial>,
+        )>();
+        restore_portal_original_material(portal);
+        if let (Some(portal_material), Some(mut portal_material_assets)) = (
+            portal_material,
+            world.get_resource_mut::<Assets<PortalMaterial>>(),
+        ) {
+            portal_material_assets.remove(&portal_material.0);
+        }
+    }
+}
+
 /// Despawns portal parts according to a strategy
 pub fn despawn_portal_parts(
     commands: &mut Commands,
@@ -112,6 +251,9 @@ fn despawn_portal_parts_with_message(
     strategy: &PortalPartsDespawnStrategy,
     error_message: &str,
 ) {
+    commands.send_event(PortalPartsDespawned {
+        parts: parts.clone(),
+    });
     despawn_portal_part(
         commands,
         parts.portal_camera,
@@ -133,6 +275,11 @@ fn despawn_portal_parts_with_message(
         error_message,
         "Portal",
     );
+    if !strategy.portal.should_despawn() {
+        if let Some(mut portal_commands) = commands.get_entity(parts.portal) {
+            portal_commands.queue(restore_portal_original_material);
+        }
+    }
     despawn_portal_part(
         commands,
         parts.main_camera,
@@ -197,7 +344,9 @@ pub fn check_portal_camera_despawn(
 }
 
 /// Helper function to deal with "missing" portal parts,
-/// see [PortalsPlugin](struct.PortalsPlugin.html#structfield.despawn_strategy)
+/// see [PortalsPlugin](struct.PortalsPlugin.html#structfield.despawn_strategy).
+///
+/// The [PortalParts::despawn_strategy] of the portal overrides `strategy` if any.
 pub(super) fn deal_with_part_query_error(
     commands: &mut Commands,
     parts: &PortalParts,
@@ -227,5 +376,6 @@ pub(super) fn deal_with_part_query_error(
             )
         }
     };
+    let strategy = parts.despawn_strategy.as_ref().unwrap_or(strategy);
     despawn_portal_parts_with_message(commands, parts, strategy, &error_message);
 }
@@ -0,0 +1,79 @@
+//! Diagnostics about the portals, to budget how many portals a scene can afford
+
+use bevy_app::prelude::*;
+use bevy_asset::prelude::*;
+use bevy_diagnostic::{Diagnostic, DiagnosticPath, Diagnostics, RegisterDiagnostic};
+use bevy_ecs::prelude::*;
+use bevy_image::{Image, TextureFormatPixelInfo};
+use bevy_render::prelude::*;
+
+use super::*;
+
+/// [Plugin] adding diagnostics about the portals, add it with [PortalsPlugin].
+///
+/// Use Bevy's `LogDiagnosticsPlugin` to log them, or read them from the [DiagnosticsStore](bevy_diagnostic::DiagnosticsStore).
+/// The GPU time of each portal camera isn't measured, Bevy's `RenderDiagnosticsPlugin` only
+/// measures the time of each render pass for all the cameras together.
+#[derive(Default)]
+pub struct PortalDiagnosticsPlugin;
+
+impl Plugin for PortalDiagnosticsPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_diagnostic(Diagnostic::new(Self::PORTALS))
+            .register_diagnostic(Diagnostic::new(Self::ACTIVE_PORTAL_CAMERAS))
+            .register_diagnostic(Diagnostic::new(Self::PORTAL_TEXTURE_MEMORY).with_suffix(" MiB"))
+            .register_diagnostic(Diagnostic::new(Self::PORTAL_IMAGE_RESIZES))
+            .add_systems(Last, Self::diagnostic_system);
+    }
+}
+
+impl PortalDiagnosticsPlugin {
+    /// Number of [Portal]s.
+    pub const PORTALS: DiagnosticPath = DiagnosticPath::const_new("portals/portals");
+    /// Number of [PortalCamera]s whose [Camera] is active.
+    pub const ACTIVE_PORTAL_CAMERAS: DiagnosticPath =
+        DiagnosticPath::const_new("portals/active_portal_cameras");
+    /// Memory of the images the [PortalCamera]s render to, and of their [PortalDestinationDepth]s,
+    /// in mebibytes.
+    pub const PORTAL_TEXTURE_MEMORY: DiagnosticPath =
+        DiagnosticPath::const_new("portals/portal_texture_memory");
+    /// Number of [PortalImageResized] events sent this frame.
+    pub const PORTAL_IMAGE_RESIZES: DiagnosticPath =
+        DiagnosticPath::const_new("portals/portal_image_resizes");
+
+    /// [System] measuring the diagnostics, added by [PortalDiagnosticsPlugin] in [Last].
+    pub fn diagnostic_system(
+        mut diagnostics: Diagnostics,
+        portals: Query<(), With<Portal>>,
+        portal_cameras: Query<(&Camera, &PortalCamera, Option<&PortalDestinationDepth>)>,
+        images: Res<Assets<Image>>,
+        mut resized: EventReader<PortalImageResized>,
+    ) {
+        diagnostics.add_measurement(&Self::PORTALS, || portals.iter().count() as f64);
+        diagnostics.add_measurement(&Self::ACTIVE_PORTAL_CAMERAS, || {
+            portal_cameras
+                .iter()
+                .filter(|(camera, ..)| camera.is_active)
+                .count() as f64
+        });
+        diagnostics.add_measurement(&Self::PORTAL_TEXTURE_MEMORY, || {
+            let bytes: usize = portal_cameras
+                .iter()
+                .flat_map(|(_, portal_camera, depth)| {
+                    std::iter::once(&portal_camera.image).chain(depth.map(|depth| &depth.image))
+                })
+                .filter_map(|image| images.get(image))
+                .map(|image| {
+                    let size = image.texture_descriptor.size;
+                    size.width as usize
+                        * size.height as usize
+                        * image.texture_descriptor.format.pixel_size()
+                        * image.texture_descriptor.sample_count as usize
+                })
+                .sum();
+            bytes as f64 / (1024. * 1024.)
+        });
+        let resizes = resized.read().count();
+        diagnostics.add_measurement(&Self::PORTAL_IMAGE_RESIZES, || resizes as f64);
+    }
+}
@@ -0,0 +1,53 @@
+//! Events sent during the lifecycle of portals
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use bevy_math::UVec2;
+
+use super::*;
+
+/// Add the lifecycle events to [PortalsPlugin]
+pub(super) fn build_events(app: &mut App) {
+    app.add_event::<PortalCreated>()
+        .add_event::<PortalImageResized>()
+        .add_event::<PortalPartsDespawned>()
+        .add_event::<PortalCreationFailed>();
+}
+
+/// [Event] sent when a portal is created, by [create_portals] or [CreatePortalCommand],
+/// for example to add components to the [PortalCamera].
+///
+/// Sent with [Commands], the parts exist when the event is read.
+#[derive(Event, Clone)]
+pub struct PortalCreated {
+    pub parts: PortalParts,
+}
+
+/// [Event] sent when the image of a [PortalCamera] is resized to follow the viewport of its
+/// main camera, see [update_portal_cameras].
+#[derive(Event, Clone, Copy, Debug)]
+pub struct PortalImageResized {
+    /// The entity with the [Portal] component.
+    pub portal: Entity,
+    /// New size of the image in physical pixels.
+    pub new_size: UVec2,
+}
+
+/// [Event] sent when the parts of a portal are despawned, with [DespawnPortalPartsCommand],
+/// [DespawnPortalPartsEntityCommand] or because a part is missing
+/// (see [PortalPartsDespawnStrategy]).
+///
+/// Some parts may be left depending on the strategy.
+#[derive(Event, Clone)]
+pub struct PortalPartsDespawned {
+    pub parts: PortalParts,
+}
+
+/// [Event] sent when a portal could not be created, the [CreatePortal] component is then kept
+/// or removed according to the [PortalCreationFailurePolicy].
+#[derive(Event, Clone, Copy, Debug)]
+pub struct PortalCreationFailed {
+    /// The entity with the [CreatePortal] component.
+    pub portal: Entity,
+    pub error: PortalCreationError,
+}
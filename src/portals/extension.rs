@@ -0,0 +1,107 @@
+//! Extension of the portal material with custom shaders and bindings
+
+use bevy_app::prelude::*;
+use bevy_asset::{prelude::*, AssetEvents};
+use bevy_ecs::prelude::*;
+use bevy_pbr::{prelude::*, ExtendedMaterial, MaterialExtension};
+use bevy_render::render_resource::AsBindGroup;
+use std::{hash::Hash, marker::PhantomData};
+
+use super::*;
+
+/// [PortalMaterial] extended with a custom [MaterialExtension], for bespoke portal looks.
+pub type ExtendedPortalMaterial<E> = ExtendedMaterial<PortalMaterial, E>;
+
+/// [Component] to use an [ExtendedPortalMaterial] for a portal, put it next to [CreatePortal].
+///
+/// Requires [PortalMaterialExtensionPlugin] with the same extension.
+/// The [PortalBaseMaterial] of the portal is still used by this crate (for example to resize
+/// its image), and copied in the base of the extended material when it changes.
+#[derive(Component, Clone)]
+pub struct PortalMaterialExtension<E: MaterialExtension>(pub E);
+
+/// [Plugin] to use [PortalMaterialExtension]s with the extension `E`.
+///
+/// Registers the [MaterialPlugin] of [ExtendedPortalMaterial], add it after [PortalsPlugin].
+pub struct PortalMaterialExtensionPlugin<E: MaterialExtension>(PhantomData<E>);
+
+impl<E: MaterialExtension> Default for PortalMaterialExtensionPlugin<E> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<E: MaterialExtension> Plugin for PortalMaterialExtensionPlugin<E>
+where
+    <ExtendedPortalMaterial<E> as AsBindGroup>::Data: PartialEq + Eq + Hash + Clone,
+{
+    fn build(&self, app: &mut App) {
+        app.add_plugins(MaterialPlugin::<ExtendedPortalMaterial<E>>::default())
+            .add_systems(
+                Last,
+                (
+                    extend_portal_materials::<E>,
+                    sync_extended_portal_materials::<E>,
+                )
+                    .chain()
+                    .after(AssetEvents),
+            );
+        build_rebind::<ExtendedPortalMaterial<E>>(app);
+    }
+}
+
+/// [System] that replaces the [PortalMaterial] of created portals with a [PortalMaterialExtension]
+/// by an [ExtendedPortalMaterial].
+#[allow(clippy::type_complexity)]
+pub fn extend_portal_materials<E: MaterialExtension>(
+    mut commands: Commands,
+    portals: Query<
+        (Entity, &PortalBaseMaterial, &PortalMaterialExtension<E>),
+        (With<Portal>, With<MeshMaterial3d<PortalMaterial>>),
+    >,
+    base_materials: Res<Assets<PortalMaterial>>,
+    mut extended_materials: ResMut<Assets<ExtendedPortalMaterial<E>>>,
+) {
+    for (portal_entity, base_material, extension) in portals.iter() {
+        let Some(base) = base_materials.get(&base_material.0) else {
+            continue;
+        };
+        let extended_material = extended_materials.add(ExtendedMaterial {
+            base: base.clone(),
+            extension: extension.0.clone(),
+        });
+        commands
+            .entity(portal_entity)
+            .remove::<MeshMaterial3d<PortalMaterial>>()
+            .insert(MeshMaterial3d(extended_material));
+    }
+}
+
+/// [System] that copies the [PortalBaseMaterial]s of portals in the base of their
+/// [ExtendedPortalMaterial] when they change.
+pub fn sync_extended_portal_materials<E: MaterialExtension>(
+    mut base_material_events: EventReader<AssetEvent<PortalMaterial>>,
+    portals: Query<(
+        &PortalBaseMaterial,
+        &MeshMaterial3d<ExtendedPortalMaterial<E>>,
+    )>,
+    base_materials: Res<Assets<PortalMaterial>>,
+    mut extended_materials: ResMut<Assets<ExtendedPortalMaterial<E>>>,
+) {
+    for event in base_material_events.read() {
+        let AssetEvent::Modified { id } = event else {
+            continue;
+        };
+        let Some(base) = base_materials.get(*id) else {
+            continue;
+        };
+        for (base_material, extended_material) in portals.iter() {
+            if base_material.0.id() != *id {
+                continue;
+            }
+            if let Some(extended_material) = extended_materials.get_mut(extended_material) {
+                extended_material.base = base.clone();
+            }
+        }
+    }
+}
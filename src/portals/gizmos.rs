@@ -0,0 +1,139 @@
+//! Debug gizmos of the portal cameras, see [DebugPortal]
+
+use bevy_app::prelude::*;
+use bevy_color::Alpha;
+use bevy_color::Color;
+use bevy_ecs::prelude::*;
+use bevy_gizmos::prelude::*;
+use bevy_math::{Isometry3d, Quat, UVec2, Vec2, Vec3, Vec3A};
+use bevy_reflect::Reflect;
+use bevy_render::primitives::{Frustum, HalfSpace};
+use bevy_transform::prelude::*;
+
+use super::*;
+
+/// Add the debug gizmos to [PortalsPlugin]
+pub(super) fn build_gizmos(app: &mut App) {
+    app.register_type::<PortalDebugGizmos>().add_systems(
+        PostUpdate,
+        draw_portal_debug_gizmos
+            .in_set(PortalSystems::UpdateCameras)
+            .after(update_portal_cameras),
+    );
+}
+
+/// [Component] on a [PortalCamera] whose debug elements are drawn with gizmos each frame,
+/// added from its [DebugPortal].
+///
+/// Needs the [GizmoPlugin](bevy_gizmos::GizmoPlugin), which is in Bevy's `DefaultPlugins`.
+#[derive(Component, Clone, Debug, Reflect)]
+#[reflect(Component)]
+pub struct PortalDebugGizmos {
+    /// Color of the gizmos, see [DebugPortal::color].
+    pub color: Color,
+    /// Whether to draw the [Frustum] of the portal camera, see [DebugPortal::show_frustum].
+    pub frustum: bool,
+    /// Whether to draw the near [HalfSpace] of the [PortalMode], see [DebugPortal::show_half_space].
+    pub half_space: bool,
+}
+
+/// [System] drawing the [PortalDebugGizmos] of the portal cameras, added by [PortalsPlugin]
+/// after [update_portal_cameras].
+pub fn draw_portal_debug_gizmos(
+    mut gizmos: Gizmos,
+    portal_cameras: Query<(&PortalCamera, &Frustum, &PortalDebugGizmos)>,
+    destinations: Query<&GlobalTransform>,
+) {
+    for (portal_camera, frustum, debug_gizmos) in &portal_cameras {
+        if debug_gizmos.frustum {
+            draw_frustum(&mut gizmos, frustum, debug_gizmos.color);
+        }
+        let has_half_space = matches!(
+            portal_camera.portal_mode,
+            PortalMode::MaskedImageHalfSpaceFrustum(_)
+                | PortalMode::MaskedImageAabbHalfSpaceFrustum(_)
+                | PortalMode::MaskedImageRectangleHalfSpaceFrustum(_)
+        ) && !matches!(portal_camera.viewer, PortalViewerMode::FixedCamera(_));
+        if debug_gizmos.half_space && has_half_space {
+            if let Ok(destination_transform) = destinations.get(portal_camera.parts.destination) {
+                draw_half_space(
+                    &mut gizmos,
+                    &frustum.half_spaces[4],
+                    destination_transform,
+                    debug_gizmos.color,
+                );
+            }
+        }
+    }
+}
+
+/// Draws the edges of a [Frustum], skipped if some of its planes don't intersect
+fn draw_frustum(gizmos: &mut Gizmos, frustum: &Frustum, color: Color) {
+    // Half-spaces are left, right, bottom, top, near, far
+    let corners = |plane: usize| {
+        [(0, 2), (1, 2), (1, 3), (0, 3)].map(|(x, y)| {
+            planes_intersection(
+                &frustum.half_spaces[x],
+                &frustum.half_spaces[y],
+                &frustum.half_spaces[plane],
+            )
+        })
+    };
+    let (Some(near), Some(far)) = (
+        corners(4).into_iter().collect::<Option<Vec<_>>>(),
+        corners(5).into_iter().collect::<Option<Vec<_>>>(),
+    ) else {
+        return;
+    };
+    gizmos.linestrip(near.iter().chain(near.first()).copied(), color);
+    gizmos.linestrip(far.iter().chain(far.first()).copied(), color);
+    for (near_corner, far_corner) in near.into_iter().zip(far) {
+        gizmos.line(near_corner, far_corner, color);
+    }
+}
+
+/// Draws the plane of a [HalfSpace] as a translucent grid centered on the destination,
+/// with an arrow for its normal (pointing to what is seen through the portal)
+fn draw_half_space(
+    gizmos: &mut Gizmos,
+    half_space: &HalfSpace,
+    destination_transform: &GlobalTransform,
+    color: Color,
+) {
+    let normal = Vec3::from(half_space.normal());
+    let destination = destination_transform.translation();
+    let center = destination - (normal.dot(destination) + half_space.d()) * normal;
+    let rotation = Quat::from_rotation_arc(Vec3::Z, normal);
+    let size = destination_transform
+        .scale()
+        .max_element()
+        .max(f32::EPSILON);
+    gizmos
+        .grid(
+            Isometry3d::new(center, rotation),
+            UVec2::splat(HALF_SPACE_GRID_CELLS),
+            Vec2::splat(size * HALF_SPACE_GRID_SIZE / HALF_SPACE_GRID_CELLS as f32),
+            color.with_alpha(0.3),
+        )
+        .outer_edges();
+    gizmos.arrow(center, center + normal * size, color);
+}
+
+/// Width of the grid drawn for a [HalfSpace], at a destination with a scale of 1
+const HALF_SPACE_GRID_SIZE: f32 = 2.;
+
+/// Number of cells of the grid drawn for a [HalfSpace] along each axis
+const HALF_SPACE_GRID_CELLS: u32 = 8;
+
+/// Point on the planes of three [HalfSpace]s, `None` if two of them are parallel
+fn planes_intersection(a: &HalfSpace, b: &HalfSpace, c: &HalfSpace) -> Option<Vec3> {
+    let (na, nb, nc) = (a.normal(), b.normal(), c.normal());
+    let determinant = na.dot(nb.cross(nc));
+    if determinant.abs() < f32::EPSILON {
+        return None;
+    }
+    // The planes are normal.dot(point) + d = 0
+    let point: Vec3A =
+        -(a.d() * nb.cross(nc) + b.d() * nc.cross(na) + c.d() * na.cross(nb)) / determinant;
+    Some(point.into())
+}
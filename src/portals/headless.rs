@@ -0,0 +1,31 @@
+//! Portals without rendering, for headless apps like servers, see [PortalsPlugin::rendering]
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+
+use super::*;
+
+/// Add the headless logic to [PortalsPlugin], the create and update logic without the render logic
+pub(super) fn build_headless(app: &mut App, check_create: &PortalsCheckMode) {
+    app.register_type::<PortalProjection>()
+        .insert_resource(PortalsHeadless);
+
+    build_create(app, check_create);
+
+    app.add_systems(
+        PostUpdate,
+        update_portal_cameras.in_set(PortalSystems::UpdateCameras),
+    );
+}
+
+/// [Resource] present when [PortalsPlugin::rendering] is false.
+///
+/// [create_portals] and [CreatePortalCommand] then create the [PortalCamera] as an entity
+/// without a [Camera](bevy_render::prelude::Camera) nor an image, with a
+/// [Transform](bevy_transform::prelude::Transform) and a
+/// [Frustum](bevy_render::primitives::Frustum) kept up to date by [update_portal_cameras].
+/// The visual options of [CreatePortal] are ignored, and without a [CreatePortal::main_camera]
+/// the viewer entity is used as the main camera (see [PortalViewerMode::Entity]),
+/// as headless apps usually don't have cameras.
+#[derive(Resource, Clone, Copy, Debug, Default)]
+pub struct PortalsHeadless;
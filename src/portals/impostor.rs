@@ -0,0 +1,112 @@
+//! Components and systems to replace far away portals by a snapshot
+
+use bevy_app::prelude::*;
+use bevy_asset::prelude::*;
+use bevy_ecs::prelude::*;
+use bevy_math::{Vec2, Vec4};
+use bevy_reflect::{std_traits::ReflectDefault, Reflect};
+
+use super::*;
+
+/// Add the impostor logic to [PortalsPlugin]
+pub(super) fn build_impostor(app: &mut App) {
+    app.register_type::<PortalImpostor>().add_systems(
+        PostUpdate,
+        update_portal_impostors
+            .in_set(PortalSystems::UpdateCameras)
+            .after(update_portal_cameras),
+    );
+}
+
+/// [Component] to stop rendering a [Portal] beyond a distance from its main camera,
+/// it then shows a snapshot of its last image instead.
+///
+/// The snapshot is the last image rendered by the [PortalCamera], which is deactivated,
+/// and it follows the portal on the screen like a billboard.
+/// It is only accurate for far away portals, and is lost if the image is resized.
+#[derive(Component, Clone, Reflect)]
+#[reflect(Component, Default)]
+pub struct PortalImpostor {
+    /// Distance between the main camera and the portal beyond which the snapshot is used.
+    pub distance: f32,
+    /// Position of the portal on the screen (in UV units) and its depth when the snapshot was taken,
+    /// `None` while the portal is rendered.
+    #[reflect(ignore)]
+    snapshot: Option<(Vec2, f32)>,
+}
+
+impl Default for PortalImpostor {
+    fn default() -> Self {
+        Self::new(100.)
+    }
+}
+
+impl PortalImpostor {
+    /// A portal rendered up to `distance` from its main camera.
+    pub fn new(distance: f32) -> Self {
+        Self {
+            distance,
+            snapshot: None,
+        }
+    }
+
+    /// Whether the snapshot is currently shown.
+    pub fn is_active(&self) -> bool {
+        self.snapshot.is_some()
+    }
+}
+
+/// [System] that switches [PortalImpostor]s between the snapshot and the rendered image,
+/// and updates the [PortalMaterial] to move the snapshot with the portal.
+///
+/// The [PortalCamera] doesn't render while the snapshot is shown, see [PortalDeactivation::impostor].
+pub fn update_portal_impostors(
+    mut portals: Query<(
+        &mut PortalImpostor,
+        &PortalBaseMaterial,
+        &PortalViewInfo,
+        &mut PortalDeactivation,
+    )>,
+    mut materials: ResMut<Assets<PortalMaterial>>,
+) {
+    for (mut impostor, portal_material, view_info, mut deactivation) in portals.iter_mut() {
+        let screen_position = view_info.screen_position;
+        let depth = view_info.depth;
+        let far = view_info.distance > impostor.distance;
+
+        // The snapshot needs the portal to be in front of the camera
+        let new_snapshot = match (impostor.snapshot, screen_position) {
+            (_, None) => None,
+            (None, Some(position)) if far => Some((position, depth)),
+            (Some(snapshot), Some(_)) if far => Some(snapshot),
+            _ => None,
+        };
+        if impostor.snapshot != new_snapshot {
+            impostor.snapshot = new_snapshot;
+        }
+        // The portal camera is deactivated by update_portal_camera_activation
+        if deactivation.impostor != new_snapshot.is_some() {
+            deactivation.impostor = new_snapshot.is_some();
+        }
+
+        // Maps the current screen UVs around the portal to the ones of the snapshot
+        let material_impostor = match (new_snapshot, screen_position) {
+            (Some((snapshot_position, snapshot_depth)), Some(position)) => {
+                let scale = depth / snapshot_depth;
+                let offset = snapshot_position - position * scale;
+                Vec4::new(scale, offset.x, offset.y, 1.)
+            }
+            _ => Vec4::ZERO,
+        };
+
+        // Only touch the material when needed, it rebuilds its bind group
+        if materials
+            .get(&portal_material.0)
+            .is_some_and(|material| material.impostor != material_impostor)
+        {
+            if let Some(material) = materials.get_mut(&portal_material.0) {
+                material.impostor = material_impostor;
+            }
+        }
+    }
+}
@@ -0,0 +1,148 @@
+//! Automatic allocation of render layers for the scenes behind portals
+
+use bevy_app::prelude::*;
+use bevy_ecs::{
+    entity::{EntityHashMap, EntityHashSet},
+    prelude::*,
+};
+use bevy_hierarchy::prelude::*;
+use bevy_reflect::{std_traits::ReflectDefault, Reflect};
+use bevy_render::view::{RenderLayers, VisibilitySystems};
+
+use super::*;
+
+/// Add the render layer allocation to [PortalsPlugin]
+pub(super) fn build_layers(app: &mut App) {
+    app.init_resource::<PortalLayerAllocator>()
+        .register_type::<PortalLayerAllocator>()
+        .register_type::<PortalAllocatedLayer>()
+        .add_observer(free_portal_allocated_layer)
+        .add_systems(
+            PostUpdate,
+            propagate_portal_allocated_layers.before(VisibilitySystems::CheckVisibility),
+        );
+}
+
+/// [Resource] handing out render layers not used by other portals,
+/// see [CreatePortal::allocate_render_layer].
+///
+/// Layers are allocated from [first_layer](Self::first_layer), layer 0 being the default layer
+/// of the main scene.
+/// The layers of despawned or removed portals are freed and reused.
+#[derive(Resource, Clone, Debug, Reflect)]
+#[reflect(Resource, Default)]
+pub struct PortalLayerAllocator {
+    /// First layer that can be allocated, set it above the layers used by your own scenes,
+    /// defaults to 1.
+    pub first_layer: usize,
+    allocated: Vec<usize>,
+}
+
+impl Default for PortalLayerAllocator {
+    fn default() -> Self {
+        Self {
+            first_layer: 1,
+            allocated: Vec::new(),
+        }
+    }
+}
+
+impl PortalLayerAllocator {
+    /// Returns the lowest layer not allocated yet, and marks it as allocated.
+    pub fn allocate(&mut self) -> usize {
+        let layer = (self.first_layer..)
+            .find(|layer| !self.allocated.contains(layer))
+            .expect("There is always a free layer");
+        self.allocated.push(layer);
+        layer
+    }
+
+    /// Marks `layer` as free, it can then be allocated again.
+    pub fn free(&mut self, layer: usize) {
+        self.allocated.retain(|allocated| *allocated != layer);
+    }
+
+    /// Layers currently allocated.
+    pub fn allocated(&self) -> &[usize] {
+        &self.allocated
+    }
+}
+
+/// [Component] on a [Portal] whose render layer was allocated by the [PortalLayerAllocator].
+///
+/// The layer is given to the destination and all its descendants by
+/// [propagate_portal_allocated_layers], and freed when this component is removed
+/// (when the portal is despawned, or removed with [RemovePortalCommand]).
+#[derive(Component, Clone, Copy, Debug, Reflect)]
+#[reflect(Component)]
+pub struct PortalAllocatedLayer(pub usize);
+
+/// [System] putting the [PortalAllocatedLayer] of the portals on their destination and all
+/// its descendants, added by [PortalsPlugin] in [PostUpdate].
+///
+/// Overrides the [RenderLayers] of those entities, including the ones spawned later as
+/// children of the destination.
+/// A destination shared by several portals gets the layers of all of them.
+///
+/// The hierarchy of a destination is only walked when a portal using it changed, or when
+/// an entity was added to the hierarchy.
+#[allow(clippy::too_many_arguments)]
+pub fn propagate_portal_allocated_layers(
+    mut commands: Commands,
+    portals: Query<(Ref<Portal>, Ref<PortalAllocatedLayer>)>,
+    mut removed_layers: RemovedComponents<PortalAllocatedLayer>,
+    all_portals: Query<&Portal>,
+    reparented: Query<Entity, Changed<Parent>>,
+    parents: Query<&Parent>,
+    children: Query<&Children>,
+    render_layers: Query<&RenderLayers>,
+    mut dirty_destinations: Local<EntityHashSet>,
+) {
+    dirty_destinations.clear();
+    let mut destination_layers = EntityHashMap::<RenderLayers>::default();
+    for (portal, allocated_layer) in &portals {
+        let destination = portal.parts.destination;
+        let layers = destination_layers.entry(destination).or_default();
+        *layers = layers.union(&RenderLayers::layer(allocated_layer.0));
+        if portal.is_changed() || allocated_layer.is_changed() {
+            dirty_destinations.insert(destination);
+        }
+    }
+    // The other portals sharing the destination of a removed layer
+    for portal_entity in removed_layers.read() {
+        if let Ok(portal) = all_portals.get(portal_entity) {
+            dirty_destinations.insert(portal.parts.destination);
+        }
+    }
+    // New entities in the hierarchy of a destination
+    for entity in &reparented {
+        if let Some(destination) = std::iter::once(entity)
+            .chain(parents.iter_ancestors(entity))
+            .find(|ancestor| destination_layers.contains_key(ancestor))
+        {
+            dirty_destinations.insert(destination);
+        }
+    }
+
+    for destination in dirty_destinations.iter() {
+        let Some(layers) = destination_layers.get(destination) else {
+            continue;
+        };
+        for entity in std::iter::once(*destination).chain(children.iter_descendants(*destination)) {
+            if render_layers.get(entity).ok() != Some(layers) {
+                commands.entity(entity).try_insert(layers.clone());
+            }
+        }
+    }
+}
+
+/// Observer freeing the layer of a [PortalAllocatedLayer] when it is removed
+fn free_portal_allocated_layer(
+    trigger: Trigger<OnRemove, PortalAllocatedLayer>,
+    allocated_layers: Query<&PortalAllocatedLayer>,
+    mut allocator: ResMut<PortalLayerAllocator>,
+) {
+    if let Ok(allocated_layer) = allocated_layers.get(trigger.entity()) {
+        allocator.free(allocated_layer.0);
+    }
+}
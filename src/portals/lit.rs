@@ -0,0 +1,138 @@
+//! Lit variant of the portal material, based on [StandardMaterial]
+
+use bevy_app::prelude::*;
+use bevy_asset::{prelude::*, AssetEvents};
+use bevy_color::Color;
+use bevy_ecs::prelude::*;
+use bevy_image::Image;
+use bevy_math::Vec4;
+use bevy_pbr::{prelude::*, ExtendedMaterial, MaterialExtension};
+use bevy_reflect::{std_traits::ReflectDefault, Reflect, TypePath};
+use bevy_render::{
+    prelude::*,
+    render_resource::{AsBindGroup, ShaderRef},
+};
+
+use super::*;
+
+/// Add the lit material logic to [PortalsPlugin]
+pub(super) fn build_lit(app: &mut App) {
+    bevy_asset::load_internal_asset!(
+        app,
+        PORTAL_LIT_SHADER_HANDLE,
+        concat!(env!("CARGO_MANIFEST_DIR"), "/assets/portal_lit.wgsl"),
+        Shader::from_wgsl
+    );
+
+    app.register_type::<PortalLitSurface>()
+        .add_plugins(MaterialPlugin::<LitPortalMaterial>::default())
+        .add_systems(
+            Last,
+            (light_portal_materials, sync_lit_portal_materials)
+                .chain()
+                .after(AssetEvents),
+        );
+    build_rebind::<LitPortalMaterial>(app);
+}
+
+pub const PORTAL_LIT_SHADER_HANDLE: Handle<Shader> =
+    Handle::weak_from_u128(0x5B0C2E8F1D7A4C3E9A6B2F4D8E1C7A35);
+
+/// [StandardMaterial] showing the portal image underneath its lit surface.
+pub type LitPortalMaterial = ExtendedMaterial<StandardMaterial, LitPortalExtension>;
+
+/// [MaterialExtension] of [LitPortalMaterial], adds the portal image to the lit color.
+#[derive(Asset, AsBindGroup, Clone, TypePath)]
+pub struct LitPortalExtension {
+    /// Image of the portal, the same as [PortalMaterial::color_texture].
+    #[texture(100)]
+    #[sampler(101)]
+    pub portal_texture: Option<Handle<Image>>,
+    /// Viewport of the main camera, the same as [PortalMaterial::viewport].
+    #[uniform(102)]
+    pub viewport: Vec4,
+}
+
+impl MaterialExtension for LitPortalExtension {
+    fn fragment_shader() -> ShaderRef {
+        PORTAL_LIT_SHADER_HANDLE.into()
+    }
+}
+
+/// [Component] to render a portal with a [LitPortalMaterial], put it next to [CreatePortal].
+///
+/// The portal image is added to the lit color of the [StandardMaterial], so it participates
+/// in lighting: with a black `base_color` only the specular highlights show over the image
+/// (a mirror), with a dark grey one and a `base_color_texture` it looks dusty (a glass pane).
+///
+/// The effects of [PortalMaterial] (ripple, dissolve, back faces, animation...) are not applied.
+#[derive(Component, Clone, Reflect)]
+#[reflect(Component, Default)]
+pub struct PortalLitSurface(pub StandardMaterial);
+
+impl Default for PortalLitSurface {
+    fn default() -> Self {
+        Self(StandardMaterial {
+            base_color: Color::BLACK,
+            perceptual_roughness: 0.1,
+            ..Default::default()
+        })
+    }
+}
+
+/// [System] that replaces the [PortalMaterial] of created portals with a [PortalLitSurface]
+/// by a [LitPortalMaterial].
+#[allow(clippy::type_complexity)]
+pub fn light_portal_materials(
+    mut commands: Commands,
+    portals: Query<
+        (Entity, &PortalBaseMaterial, &PortalLitSurface),
+        (With<Portal>, With<MeshMaterial3d<PortalMaterial>>),
+    >,
+    base_materials: Res<Assets<PortalMaterial>>,
+    mut lit_materials: ResMut<Assets<LitPortalMaterial>>,
+) {
+    for (portal_entity, base_material, surface) in portals.iter() {
+        let Some(base) = base_materials.get(&base_material.0) else {
+            continue;
+        };
+        let lit_material = lit_materials.add(ExtendedMaterial {
+            base: surface.0.clone(),
+            extension: LitPortalExtension {
+                portal_texture: base.color_texture.clone(),
+                viewport: base.viewport,
+            },
+        });
+        commands
+            .entity(portal_entity)
+            .remove::<MeshMaterial3d<PortalMaterial>>()
+            .insert(MeshMaterial3d(lit_material));
+    }
+}
+
+/// [System] that copies the image and viewport of the [PortalBaseMaterial]s of portals in their
+/// [LitPortalMaterial] when they change.
+pub fn sync_lit_portal_materials(
+    mut base_material_events: EventReader<AssetEvent<PortalMaterial>>,
+    portals: Query<(&PortalBaseMaterial, &MeshMaterial3d<LitPortalMaterial>)>,
+    base_materials: Res<Assets<PortalMaterial>>,
+    mut lit_materials: ResMut<Assets<LitPortalMaterial>>,
+) {
+    for event in base_material_events.read() {
+        let AssetEvent::Modified { id } = event else {
+            continue;
+        };
+        let Some(base) = base_materials.get(*id) else {
+            continue;
+        };
+        for (base_material, lit_material) in portals.iter() {
+            if base_material.0.id() != *id {
+                continue;
+            }
+            if let Some(lit_material) = lit_materials.get_mut(lit_material) {
+                lit_material.extension.portal_texture = base.color_texture.clone();
+                lit_material.extension.viewport = base.viewport;
+            }
+        }
+    }
+}
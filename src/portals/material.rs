@@ -1,11 +1,14 @@
 //! Material for portal rendering
 
-use bevy_app::App;
-use bevy_asset::prelude::*;
+use bevy_app::prelude::*;
+use bevy_asset::{prelude::*, LoadState};
+use bevy_color::LinearRgba;
+use bevy_ecs::prelude::*;
 use bevy_image::Image;
+use bevy_math::{Mat4, Vec2, Vec4};
 use bevy_pbr::prelude::*;
 use bevy_pbr::{MaterialPipeline, MaterialPipelineKey};
-use bevy_reflect::TypePath;
+use bevy_reflect::Reflect;
 use bevy_render::{
     mesh::MeshVertexBufferLayoutRef,
     prelude::*,
@@ -13,9 +16,10 @@ use bevy_render::{
         AsBindGroup, Face, RenderPipelineDescriptor, ShaderRef, SpecializedMeshPipelineError,
     },
 };
+use tracing::warn;
 
 /// Add the material logic to [PortalsPlugin](super::PortalsPlugin)
-pub(super) fn build_material(app: &mut App) {
+pub(super) fn build_material(app: &mut App, shader_path: Option<String>) {
     bevy_asset::load_internal_asset!(
         app,
         PORTAL_SHADER_HANDLE,
@@ -23,17 +27,284 @@ pub(super) fn build_material(app: &mut App) {
         Shader::from_wgsl
     );
 
-    app.add_plugins(MaterialPlugin::<PortalMaterial>::default());
+    app.register_type::<PortalBaseMaterial>()
+        .add_plugins(MaterialPlugin::<PortalMaterial>::default())
+        .register_asset_reflect::<PortalMaterial>();
+    super::build_rebind::<PortalMaterial>(app);
+
+    if let Some(shader_path) = shader_path {
+        app.insert_resource(PortalShaderOverride {
+            path: shader_path,
+            handle: None,
+        })
+        .add_systems(Startup, load_portal_shader_override)
+        .add_systems(Update, apply_portal_shader_override);
+    }
+}
+
+/// [Resource] with the shader replacing the portal shader, see [`PortalsPlugin::shader_path`](super::PortalsPlugin).
+#[derive(Resource)]
+pub struct PortalShaderOverride {
+    /// Asset path of the shader.
+    pub path: String,
+    /// Handle of the shader, once loaded.
+    pub handle: Option<Handle<Shader>>,
+}
+
+/// [System] that starts loading the [PortalShaderOverride].
+fn load_portal_shader_override(
+    mut shader_override: ResMut<PortalShaderOverride>,
+    asset_server: Res<AssetServer>,
+) {
+    shader_override.handle = Some(asset_server.load(&shader_override.path));
+}
+
+/// [System] that copies the [PortalShaderOverride] to [PORTAL_SHADER_HANDLE] when it is loaded,
+/// [Material::fragment_shader] can't depend on the plugin's configuration.
+///
+/// The shader is copied again when it is modified, to hot-reload it with the `file_watcher`
+/// feature of Bevy. If it fails to load, the embedded shader is kept.
+fn apply_portal_shader_override(
+    mut shader_override: ResMut<PortalShaderOverride>,
+    mut shader_events: EventReader<AssetEvent<Shader>>,
+    mut shaders: ResMut<Assets<Shader>>,
+    asset_server: Res<AssetServer>,
+) {
+    let Some(handle) = &shader_override.handle else {
+        return;
+    };
+    if let Some(LoadState::Failed(error)) = asset_server.get_load_state(handle) {
+        warn!(
+            "Portal shader {} failed to load, using the embedded shader: {error}",
+            shader_override.path
+        );
+        shader_override.handle = None;
+        return;
+    }
+    for event in shader_events.read() {
+        if !event.is_loaded_with_dependencies(handle) && !event.is_modified(handle) {
+            continue;
+        }
+        if let Some(shader) = shaders.get(handle).cloned() {
+            shaders.insert(&PORTAL_SHADER_HANDLE, shader);
+        }
+    }
 }
 
 /// Material with the portal shader (renders the image without deformation using the mesh as a mask).
-#[derive(Asset, AsBindGroup, Clone, TypePath)]
+#[derive(Asset, AsBindGroup, Clone, Reflect)]
 #[bind_group_data(PortalMaterialKey)]
+#[uniform(2, PortalMaterialUniform)]
 pub struct PortalMaterial {
     #[texture(0)]
     #[sampler(1)]
     pub color_texture: Option<Handle<Image>>,
+    #[reflect(ignore)]
     pub cull_mode: Option<Face>,
+    /// If true, the image is mapped on the mesh using its UVs instead of the screen-space mask,
+    /// see [PortalViewerMode::FixedCamera](super::PortalViewerMode::FixedCamera).
+    pub mesh_uv: bool,
+    /// Animated distortion of the image, a [PortalRipple] packed as
+    /// `(strength, frequency, speed, 0)`, disabled by default.
+    pub ripple: Vec4,
+    /// Image shown on the back faces of the mesh, see [CreatePortal::back_destination](super::CreatePortal::back_destination).
+    #[texture(3)]
+    #[sampler(4)]
+    pub back_texture: Option<Handle<Image>>,
+    /// How open the portal is, from 0 (closed) to 1 (open), see [PortalAnimation](super::PortalAnimation).
+    pub openness: f32,
+    /// Dissolve of the portal's edges, a [PortalDissolve] packed as `(threshold, edge_width, 0, 0)`.
+    pub dissolve: Vec4,
+    /// Color of the edges of the dissolve, see [PortalDissolve::edge_color].
+    pub dissolve_edge_color: Vec4,
+    /// Noise texture of the dissolve, disabled if `None`, see [PortalDissolve::noise].
+    #[texture(8)]
+    #[sampler(9)]
+    pub dissolve_noise: Option<Handle<Image>>,
+    /// Opacity of the image, the material is alpha blended if it is below 1.
+    pub alpha: f32,
+    /// Heat-haze distortion, a [PortalHeatHaze] packed as `(strength, 0, scroll_speed.x, scroll_speed.y)`.
+    pub heat_haze: Vec4,
+    /// Noise texture of the heat-haze distortion, disabled if `None`, see [PortalHeatHaze::noise].
+    #[texture(12)]
+    #[sampler(13)]
+    pub heat_haze_noise: Option<Handle<Image>>,
+    /// Strength of the refraction, see [PortalRefraction].
+    pub refraction: f32,
+    /// Normal map of the refraction, disabled if `None`, see [PortalRefraction::normal_map].
+    #[texture(15)]
+    #[sampler(16)]
+    pub refraction_normal_map: Option<Handle<Image>>,
+    /// Darkness of the shadows received, disabled if 0, see [PortalShadows].
+    pub shadows: f32,
+    /// `world_from_clip` matrix of the [PortalCamera](super::PortalCamera) moved to the portal's side,
+    /// to reproject [destination_depth_texture](Self::destination_depth_texture) for the main camera.
+    pub destination_depth: Mat4,
+    /// Depth rendered by the [PortalCamera](super::PortalCamera), output by the portal if not `None`,
+    /// see [CreatePortal::destination_depth](super::CreatePortal::destination_depth).
+    #[texture(19, sample_type = "float", filterable = false)]
+    pub destination_depth_texture: Option<Handle<Image>>,
+    /// Mapping of the screen UVs to the ones of the snapshot of a [PortalImpostor](super::PortalImpostor),
+    /// packed as `(scale, offset.x, offset.y, enabled)`.
+    pub impostor: Vec4,
+    /// Viewport of the main camera in physical pixels, packed as `(x, y, width, height)`, to map
+    /// the position of the fragments on the screen to the image (for example in split-screen),
+    /// the whole image is mapped to the render target if the width is 0.
+    ///
+    /// Set when the portal is created, then the viewport of the main camera is kept up to date
+    /// in the bind group of the material by the render world, without modifying the asset.
+    pub viewport: Vec4,
+}
+
+pub use uniform::PortalMaterialUniform;
+
+// Works around the ShaderType derive, which looks for `bevy` in the dev-dependencies before
+// `bevy_render` in the dependencies, and generates functions only used to check the types
+#[allow(dead_code)]
+mod uniform {
+    use bevy_math::{Mat4, Vec4};
+    use bevy_render::render_resource::ShaderType;
+
+    mod bevy {
+        pub(super) use bevy_render as render;
+    }
+
+    /// Parameters of a [PortalMaterial](super::PortalMaterial) in its shader, in a single uniform
+    /// buffer because WebGL2 only allows a few of them per shader stage.
+    #[derive(Clone, Default, ShaderType)]
+    pub struct PortalMaterialUniform {
+        pub destination_world_from_clip: Mat4,
+        pub ripple: Vec4,
+        pub dissolve: Vec4,
+        pub dissolve_edge_color: Vec4,
+        pub heat_haze: Vec4,
+        pub impostor: Vec4,
+        pub viewport: Vec4,
+        pub openness: f32,
+        pub alpha: f32,
+        pub refraction: f32,
+        pub shadows_darkness: f32,
+    }
+}
+
+impl From<&PortalMaterial> for PortalMaterialUniform {
+    fn from(material: &PortalMaterial) -> Self {
+        PortalMaterialUniform {
+            destination_world_from_clip: material.destination_depth,
+            ripple: material.ripple,
+            dissolve: material.dissolve,
+            dissolve_edge_color: material.dissolve_edge_color,
+            heat_haze: material.heat_haze,
+            impostor: material.impostor,
+            viewport: material.viewport,
+            openness: material.openness,
+            alpha: material.alpha,
+            refraction: material.refraction,
+            shadows_darkness: material.shadows,
+        }
+    }
+}
+
+/// [Component] with the [PortalMaterial] of a portal, used by this crate even when the
+/// portal mesh uses another material, see [PortalMaterialExtension](super::PortalMaterialExtension).
+#[derive(Component, Clone, Reflect)]
+#[reflect(Component)]
+pub struct PortalBaseMaterial(pub Handle<PortalMaterial>);
+
+/// Animated ripples distorting the image of a portal, for example to make water.
+///
+/// Animated with the time from Bevy's globals, disabled if `strength` is 0.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Reflect)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PortalRipple {
+    /// Maximum offset of the image, in screen UV units (0.005 is subtle).
+    pub strength: f32,
+    /// Number of ripples per world unit (roughly).
+    pub frequency: f32,
+    /// Speed of the animation.
+    pub speed: f32,
+}
+
+impl PortalRipple {
+    /// Subtle ripples for a water surface.
+    pub const WATER: Self = Self {
+        strength: 0.004,
+        frequency: 2.,
+        speed: 1.5,
+    };
+}
+
+/// Dissolve of a portal using a noise texture, for example to make it appear or disappear.
+///
+/// Fragments where the noise is below `threshold` are discarded, and the ones slightly above
+/// are drawn with `edge_color`. When enabled, [PortalAnimation](super::PortalAnimation)
+/// dissolves the portal instead of opening it like an iris.
+#[derive(Clone, Debug, Default, PartialEq, Reflect)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PortalDissolve {
+    /// Grayscale noise texture (only the red channel is used), sampled with the mesh UVs.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub noise: Handle<Image>,
+    /// From 0 (not dissolved) to 1 (fully dissolved).
+    pub threshold: f32,
+    /// Width of the edges, in noise units.
+    pub edge_width: f32,
+    /// Color of the edges.
+    pub edge_color: LinearRgba,
+}
+
+/// Heat-haze distortion of the image of a portal, using a scrolling noise texture.
+#[derive(Clone, Debug, Default, PartialEq, Reflect)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PortalHeatHaze {
+    /// Noise texture, its red and green channels offset the image.
+    ///
+    /// Should use a repeating sampler (see [ImageAddressMode::Repeat](bevy_image::ImageAddressMode::Repeat)).
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub noise: Handle<Image>,
+    /// Maximum offset of the image, in screen UV units (0.01 is visible).
+    pub strength: f32,
+    /// Scrolling speed of the noise, in noise UV units per second.
+    pub scroll_speed: Vec2,
+}
+
+/// Glass-like refraction of the image of a portal, using a normal map.
+///
+/// The X and Y of the tangent-space normals offset the image, for example for rippled glass or lenses.
+#[derive(Clone, Debug, Default, PartialEq, Reflect)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PortalRefraction {
+    /// Tangent-space normal map, sampled with the mesh UVs.
+    ///
+    /// Should be loaded as linear (not sRGB), like the normal maps of [StandardMaterial].
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub normal_map: Handle<Image>,
+    /// Maximum offset of the image, in screen UV units (0.02 is visible).
+    pub strength: f32,
+}
+
+/// Shadows of the foreground received by a portal surface, for mirrors and windows in lit rooms.
+///
+/// Only the shadows of [DirectionalLight]s are sampled, the image is darkened where they are
+/// in shadow, weighted by the luminance of the lights.
+/// The portal mesh must be in the view of the shadow cascades of the lights.
+#[derive(Clone, Copy, Debug, PartialEq, Reflect)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PortalShadows {
+    /// How much the image is darkened in the shadows, from 0 (not at all) to 1 (black).
+    pub darkness: f32,
+}
+
+impl Default for PortalShadows {
+    fn default() -> Self {
+        Self { darkness: 0.6 }
+    }
+}
+
+impl From<PortalRipple> for Vec4 {
+    fn from(ripple: PortalRipple) -> Self {
+        Vec4::new(ripple.strength, ripple.frequency, ripple.speed, 0.)
+    }
 }
 
 pub const PORTAL_SHADER_HANDLE: Handle<Shader> =
@@ -44,6 +315,14 @@ impl Material for PortalMaterial {
         PORTAL_SHADER_HANDLE.into()
     }
 
+    fn alpha_mode(&self) -> AlphaMode {
+        if self.alpha < 1. {
+            AlphaMode::Blend
+        } else {
+            AlphaMode::Opaque
+        }
+    }
+
     fn specialize(
         _: &MaterialPipeline<Self>,
         descriptor: &mut RenderPipelineDescriptor,
@@ -51,6 +330,29 @@ impl Material for PortalMaterial {
         key: MaterialPipelineKey<Self>,
     ) -> Result<(), SpecializedMeshPipelineError> {
         descriptor.primitive.cull_mode = key.bind_group_data.cull_mode;
+        if let Some(fragment) = descriptor.fragment.as_mut() {
+            if key.bind_group_data.mesh_uv {
+                fragment.shader_defs.push("PORTAL_MESH_UV".into());
+            }
+            if key.bind_group_data.back_texture {
+                fragment.shader_defs.push("PORTAL_BACK_TEXTURE".into());
+            }
+            if key.bind_group_data.dissolve {
+                fragment.shader_defs.push("PORTAL_DISSOLVE".into());
+            }
+            if key.bind_group_data.heat_haze {
+                fragment.shader_defs.push("PORTAL_HEAT_HAZE".into());
+            }
+            if key.bind_group_data.refraction {
+                fragment.shader_defs.push("PORTAL_REFRACTION".into());
+            }
+            if key.bind_group_data.shadows {
+                fragment.shader_defs.push("PORTAL_SHADOWS".into());
+            }
+            if key.bind_group_data.destination_depth {
+                fragment.shader_defs.push("PORTAL_DESTINATION_DEPTH".into());
+            }
+        }
         Ok(())
     }
 }
@@ -58,12 +360,26 @@ impl Material for PortalMaterial {
 #[derive(Clone, PartialEq, Eq, Hash)]
 pub struct PortalMaterialKey {
     cull_mode: Option<Face>,
+    mesh_uv: bool,
+    back_texture: bool,
+    dissolve: bool,
+    heat_haze: bool,
+    refraction: bool,
+    shadows: bool,
+    destination_depth: bool,
 }
 
 impl From<&PortalMaterial> for PortalMaterialKey {
     fn from(material: &PortalMaterial) -> Self {
         PortalMaterialKey {
             cull_mode: material.cull_mode,
+            mesh_uv: material.mesh_uv,
+            back_texture: material.back_texture.is_some(),
+            dissolve: material.dissolve_noise.is_some(),
+            heat_haze: material.heat_haze_noise.is_some(),
+            refraction: material.refraction_normal_map.is_some(),
+            shadows: material.shadows > 0.,
+            destination_depth: material.destination_depth_texture.is_some(),
         }
     }
 }
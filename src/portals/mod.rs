@@ -4,6 +4,10 @@ mod api;
 pub use api::*;
 mod material;
 pub use material::*;
+mod extension;
+pub use extension::*;
+mod lit;
+pub use lit::*;
 mod projection;
 pub use projection::*;
 mod create;
@@ -12,3 +16,53 @@ mod update;
 pub use update::*;
 mod despawn;
 pub use despawn::*;
+mod animation;
+pub use animation::*;
+mod time;
+pub use time::*;
+mod decal;
+pub use decal::*;
+mod capture;
+pub use capture::*;
+mod depth;
+pub use depth::*;
+mod rebind;
+use rebind::*;
+mod impostor;
+pub use impostor::*;
+mod view_info;
+pub use view_info::*;
+mod headless;
+pub use headless::*;
+mod portal_math;
+pub use portal_math::*;
+mod query;
+pub use query::*;
+mod builder;
+pub use builder::*;
+mod events;
+pub use events::*;
+mod capabilities;
+pub use capabilities::*;
+mod retarget;
+pub use retarget::*;
+mod layers;
+pub use layers::*;
+mod views;
+pub use views::*;
+mod placement;
+pub use placement::*;
+mod diagnostics;
+pub use diagnostics::*;
+mod skinned;
+pub use skinned::*;
+#[cfg(feature = "gizmos")]
+mod gizmos;
+#[cfg(feature = "gizmos")]
+pub use gizmos::*;
+#[cfg(feature = "serde")]
+mod serialization;
+#[cfg(feature = "ui")]
+mod ui;
+#[cfg(feature = "ui")]
+pub use ui::*;
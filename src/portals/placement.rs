@@ -0,0 +1,120 @@
+//! Automatic placement of the destinations created by the crate, like pocket dimensions
+
+use bevy_app::prelude::*;
+use bevy_ecs::{prelude::*, system::EntityCommand};
+use bevy_hierarchy::prelude::*;
+use bevy_math::Vec3;
+use bevy_reflect::{std_traits::ReflectDefault, Reflect};
+use bevy_transform::prelude::*;
+
+/// Add the placement of destinations to [PortalsPlugin](super::PortalsPlugin)
+pub(super) fn build_placement(app: &mut App) {
+    app.init_resource::<PortalDestinationPlacement>()
+        .register_type::<PortalDestinationPlacement>()
+        .register_type::<PortalDestinationSlot>()
+        .add_observer(free_portal_destination_slot);
+}
+
+/// [Resource] placing the destinations created with
+/// [CreatePortalDestination::auto_place](super::CreatePortalDestination::auto_place) far apart
+/// in a grid, so that the scenes behind different portals never overlap.
+///
+/// Slot `i` is at `origin + spacing * (i % columns, 0, i / columns)`, the slots of despawned
+/// destinations are reused.
+/// Point and spot lights don't reach other slots if their range is smaller than the spacing,
+/// directional lights still light every slot.
+#[derive(Resource, Clone, Debug, Reflect)]
+#[reflect(Resource, Default)]
+pub struct PortalDestinationPlacement {
+    /// Position of the first slot, defaults to 1000 units away from the origin on X and Z.
+    pub origin: Vec3,
+    /// Distance between two slots, defaults to 1000.
+    pub spacing: f32,
+    /// Number of slots on a row of the grid, along X, defaults to 16.
+    pub columns: u32,
+    occupied: Vec<usize>,
+}
+
+impl Default for PortalDestinationPlacement {
+    fn default() -> Self {
+        Self {
+            origin: Vec3::new(1000., 0., 1000.),
+            spacing: 1000.,
+            columns: 16,
+            occupied: Vec::new(),
+        }
+    }
+}
+
+impl PortalDestinationPlacement {
+    /// Returns the lowest free slot, and marks it as occupied.
+    pub fn allocate(&mut self) -> usize {
+        let slot = (0..)
+            .find(|slot| !self.occupied.contains(slot))
+            .expect("There is always a free slot");
+        self.occupied.push(slot);
+        slot
+    }
+
+    /// Marks `slot` as free, it can then be allocated again.
+    pub fn free(&mut self, slot: usize) {
+        self.occupied.retain(|occupied| *occupied != slot);
+    }
+
+    /// Position of `slot` in the grid.
+    pub fn slot_translation(&self, slot: usize) -> Vec3 {
+        let columns = self.columns.max(1) as usize;
+        self.origin + self.spacing * Vec3::new((slot % columns) as f32, 0., (slot / columns) as f32)
+    }
+}
+
+/// [Component] on a destination placed by the [PortalDestinationPlacement], its slot is freed
+/// when this component is removed (when the destination is despawned).
+#[derive(Component, Clone, Copy, Debug, Reflect)]
+#[reflect(Component)]
+pub struct PortalDestinationSlot(pub usize);
+
+/// [EntityCommand] moving a destination to a free slot of the [PortalDestinationPlacement]
+///
+/// The translation of the slot is added to the [Transform] of the destination.
+pub(super) struct PlacePortalDestinationCommand;
+
+impl EntityCommand for PlacePortalDestinationCommand {
+    fn apply(self, id: Entity, world: &mut World) {
+        let Some(mut placement) = world.get_resource_mut::<PortalDestinationPlacement>() else {
+            return;
+        };
+        let slot = placement.allocate();
+        let translation = placement.slot_translation(slot);
+        let Ok(mut destination) = world.get_entity_mut(id) else {
+            world
+                .resource_mut::<PortalDestinationPlacement>()
+                .free(slot);
+            return;
+        };
+        let Some(mut transform) = destination.get_mut::<Transform>() else {
+            world
+                .resource_mut::<PortalDestinationPlacement>()
+                .free(slot);
+            return;
+        };
+        transform.translation += translation;
+        let transform = *transform;
+        // Children are updated by the transform propagation
+        if !destination.contains::<Parent>() {
+            destination.insert(GlobalTransform::from(transform));
+        }
+        destination.insert(PortalDestinationSlot(slot));
+    }
+}
+
+/// Observer freeing the slot of a [PortalDestinationSlot] when it is removed
+fn free_portal_destination_slot(
+    trigger: Trigger<OnRemove, PortalDestinationSlot>,
+    slots: Query<&PortalDestinationSlot>,
+    mut placement: ResMut<PortalDestinationPlacement>,
+) {
+    if let Ok(slot) = slots.get(trigger.entity()) {
+        placement.free(slot.0);
+    }
+}
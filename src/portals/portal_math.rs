@@ -0,0 +1,274 @@
+//! Math to map positions and directions through portals
+//!
+//! These functions use the same math as the [PortalCamera]s, for teleportation, audio,
+//! AI awareness or anything that needs to know what is seen through a portal.
+//! They use the translation and rotation of the portal and destination, and the ratio of their
+//! scales (see [portal_to_destination_scale]): a destination twice as big as its portal
+//! shows a scene twice as small, like looking through a magnifying glass, and a portal stretched
+//! horizontally shows a scene squashed horizontally.
+
+use bevy_ecs::prelude::*;
+use bevy_math::{prelude::*, Affine3A, Mat3A, Quat, Vec2, Vec3};
+use bevy_render::{prelude::*, render_resource::Face};
+use bevy_transform::prelude::*;
+
+use super::*;
+
+/// Rotation from the space in front of a portal to the space around its destination
+fn portal_to_destination_rotation(
+    portal_transform: &Transform,
+    destination_transform: &Transform,
+) -> Quat {
+    portal_transform
+        .rotation
+        .inverse()
+        .mul_quat(destination_transform.rotation)
+}
+
+/// Scale from the space in front of a portal to the space around its destination, the ratio
+/// of the scale of the destination to the scale of the portal on each of their local axes.
+///
+/// The signs of the scales are ignored, a negative scale doesn't mirror what is seen through
+/// the portal (see [CreatePortal::cull_mode] for the portal mesh).
+pub fn portal_to_destination_scale(
+    portal_transform: &Transform,
+    destination_transform: &Transform,
+) -> Vec3 {
+    let portal_scale = portal_transform.scale.abs();
+    let destination_scale = destination_transform.scale.abs();
+    Vec3::select(
+        portal_scale.cmpgt(Vec3::splat(f32::EPSILON)),
+        destination_scale / portal_scale,
+        Vec3::ONE,
+    )
+}
+
+/// Faces of a portal mesh to cull for the [CreatePortal::cull_mode] `cull_mode`, swapped if
+/// the portal has a negative scale (an odd number of negative axes), which flips the winding
+/// of its triangles.
+///
+/// Used by [create_portals] and [update_portal_cameras], can be called from custom systems.
+pub fn portal_cull_mode(
+    cull_mode: Option<Face>,
+    portal_transform: &GlobalTransform,
+) -> Option<Face> {
+    if portal_transform
+        .affine()
+        .matrix3
+        .determinant()
+        .is_sign_negative()
+    {
+        cull_mode.map(|face| match face {
+            Face::Front => Face::Back,
+            Face::Back => Face::Front,
+        })
+    } else {
+        cull_mode
+    }
+}
+
+/// Linear part of [portal_to_destination_affine]: the rotation from the portal to the
+/// destination, then the ratio of their scales along the local axes of the destination
+fn portal_to_destination_linear(
+    portal_transform: &Transform,
+    destination_transform: &Transform,
+) -> Mat3A {
+    let destination_rotation = Mat3A::from_quat(destination_transform.rotation);
+    destination_rotation
+        * Mat3A::from_diagonal(portal_to_destination_scale(
+            portal_transform,
+            destination_transform,
+        ))
+        * destination_rotation.transpose()
+        * Mat3A::from_quat(portal_to_destination_rotation(
+            portal_transform,
+            destination_transform,
+        ))
+}
+
+/// Affine transformation mapping a point seen through a portal to the point at its destination,
+/// the inverse maps a point around the destination to where it is seen through the portal.
+pub fn portal_to_destination_affine(
+    portal_transform: &Transform,
+    destination_transform: &Transform,
+) -> Affine3A {
+    Affine3A::from_translation(destination_transform.translation)
+        * Affine3A::from_mat3(
+            portal_to_destination_linear(portal_transform, destination_transform).into(),
+        )
+        * Affine3A::from_translation(-portal_transform.translation)
+}
+
+/// Maps a point seen through a portal to the point at its destination,
+/// see [portal_to_destination_affine].
+pub fn transform_point_through_portal(
+    point: Vec3,
+    portal_transform: &Transform,
+    destination_transform: &Transform,
+) -> Vec3 {
+    portal_to_destination_affine(portal_transform, destination_transform).transform_point3(point)
+}
+
+/// Maps a direction seen through a portal to the direction at its destination,
+/// see [portal_to_destination_affine].
+pub fn transform_dir_through_portal(
+    direction: Dir3,
+    portal_transform: &Transform,
+    destination_transform: &Transform,
+) -> Dir3 {
+    // Non-uniform scales change directions too
+    Dir3::new(
+        portal_to_destination_linear(portal_transform, destination_transform).mul_vec3(*direction),
+    )
+    .unwrap_or(portal_to_destination_rotation(portal_transform, destination_transform) * direction)
+}
+
+/// Where a point at the destination of a portal is seen through the portal, in world space
+/// in front of the portal, the inverse of [transform_point_through_portal].
+///
+/// The point is not necessarily visible: it can be behind the destination or outside of
+/// the portal on the screen.
+pub fn apparent_position_through_portal(
+    point: Vec3,
+    portal_transform: &Transform,
+    destination_transform: &Transform,
+) -> Vec3 {
+    portal_to_destination_affine(portal_transform, destination_transform)
+        .inverse()
+        .transform_point3(point)
+}
+
+/// Where a point at the destination of a portal is seen through the portal on the viewport of
+/// the main camera, in logical pixels, for example to place UI markers over entities seen
+/// through a portal.
+///
+/// `None` if the apparent position is behind the main camera, see
+/// [apparent_position_through_portal].
+pub fn apparent_viewport_position_through_portal(
+    point: Vec3,
+    portal_transform: &Transform,
+    destination_transform: &Transform,
+    main_camera: &Camera,
+    main_camera_transform: &GlobalTransform,
+) -> Option<Vec2> {
+    let apparent_position =
+        apparent_position_through_portal(point, portal_transform, destination_transform);
+    main_camera
+        .world_to_viewport(main_camera_transform, apparent_position)
+        .ok()
+}
+
+/// Helper function to get the transform to change the main camera's transform into the portal camera's transform.
+///
+/// Equivalent to [transform_point_through_portal] and [transform_dir_through_portal] applied
+/// to the main camera.
+/// The portal camera is scaled by [portal_to_destination_scale], so that it sees the
+/// destination bigger, smaller or stretched with the same field of view.
+///
+/// With non-uniform scales the portal camera can be sheared, which a [Transform] can't
+/// represent, the result is then approximated, see [get_portal_camera_global_transform].
+pub fn get_portal_camera_transform(
+    main_camera_transform: &Transform,
+    portal_transform: &Transform,
+    destination_transform: &Transform,
+) -> Transform {
+    get_portal_camera_global_transform(
+        main_camera_transform,
+        portal_transform,
+        destination_transform,
+    )
+    .compute_transform()
+}
+
+/// Like [get_portal_camera_transform], exact with non-uniform scales.
+///
+/// Used by [update_portal_cameras], can be called from custom systems.
+pub fn get_portal_camera_global_transform(
+    main_camera_transform: &Transform,
+    portal_transform: &Transform,
+    destination_transform: &Transform,
+) -> GlobalTransform {
+    GlobalTransform::from(
+        portal_to_destination_affine(portal_transform, destination_transform)
+            * main_camera_transform.compute_affine(),
+    )
+}
+
+/// Continues a `ray` hitting a portal at `hit_point` on the other side of the portal,
+/// returning a ray starting at the destination that goes where the ray would go if the portal
+/// was a hole in space, for example to shoot through portals.
+///
+/// Uses the same math as the [PortalCamera]s (see [portal_to_destination_affine]), so the
+/// continued ray matches what is seen through the portal, including for mirrors.
+///
+/// The hit point should be on the faces of the portal mesh that are not culled,
+/// see [CreatePortal::cull_mode].
+pub fn portal_ray(
+    ray: Ray3d,
+    hit_point: Vec3,
+    portal_transform: &GlobalTransform,
+    destination_transform: &GlobalTransform,
+) -> Ray3d {
+    let portal_transform = portal_transform.compute_transform();
+    let destination_transform = destination_transform.compute_transform();
+    Ray3d {
+        origin: transform_point_through_portal(
+            hit_point,
+            &portal_transform,
+            &destination_transform,
+        ),
+        direction: transform_dir_through_portal(
+            ray.direction,
+            &portal_transform,
+            &destination_transform,
+        ),
+    }
+}
+
+/// Like [portal_ray], getting the transforms of the portal and its destination from the [Portal]
+/// component, `None` if one of them has no [GlobalTransform].
+pub fn portal_ray_through(
+    ray: Ray3d,
+    hit_point: Vec3,
+    portal: &Portal,
+    transforms: &Query<&GlobalTransform>,
+) -> Option<Ray3d> {
+    let portal_transform = transforms.get(portal.parts.portal).ok()?;
+    let destination_transform = transforms.get(portal.parts.destination).ok()?;
+    Some(portal_ray(
+        ray,
+        hit_point,
+        portal_transform,
+        destination_transform,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cull_mode_of_flipped_quad() {
+        let flipped = GlobalTransform::from_scale(Vec3::new(-1., 1., 1.));
+        assert_eq!(
+            portal_cull_mode(Some(Face::Back), &flipped),
+            Some(Face::Front)
+        );
+        assert_eq!(
+            portal_cull_mode(Some(Face::Front), &flipped),
+            Some(Face::Back)
+        );
+        assert_eq!(portal_cull_mode(None, &flipped), None);
+
+        // Two negative axes are a rotation, the winding is kept
+        let rotated = GlobalTransform::from_scale(Vec3::new(-1., -1., 1.));
+        assert_eq!(
+            portal_cull_mode(Some(Face::Back), &rotated),
+            Some(Face::Back)
+        );
+        assert_eq!(
+            portal_cull_mode(Some(Face::Back), &GlobalTransform::IDENTITY),
+            Some(Face::Back)
+        );
+    }
+}
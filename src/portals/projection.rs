@@ -9,6 +9,7 @@ use bevy_render::{
     camera::{camera_system, CameraProjection, CameraUpdateSystem, SubCameraView},
     prelude::*,
 };
+use std::fmt::Debug;
 
 /// Add the projection logic to [PortalsPlugin](super::PortalsPlugin)
 pub(super) fn build_projection(app: &mut App) {
@@ -38,7 +39,9 @@ pub(super) fn build_projection(app: &mut App) {
 pub enum PortalProjection {
     Perspective(PerspectiveProjection),
     Orthographic(OrthographicProjection),
-    //Other(Box<dyn CameraProjection>),
+    /// Any other projection, for example the one of a main camera using its own projection
+    /// component instead of [Projection], see [CreatePortal::projection](super::CreatePortal::projection).
+    Custom(#[reflect(ignore)] CustomPortalProjection),
     //Fitting
 }
 
@@ -50,6 +53,7 @@ impl Default for PortalProjection {
 
 impl From<Projection> for PortalProjection {
     fn from(p: Projection) -> Self {
+        // Should handle Projection::Custom when it exists
         match p {
             Projection::Perspective(projection) => Self::Perspective(projection),
             Projection::Orthographic(projection) => Self::Orthographic(projection),
@@ -69,11 +73,18 @@ impl From<OrthographicProjection> for PortalProjection {
     }
 }
 
+impl From<CustomPortalProjection> for PortalProjection {
+    fn from(p: CustomPortalProjection) -> Self {
+        Self::Custom(p)
+    }
+}
+
 impl CameraProjection for PortalProjection {
     fn get_clip_from_view(&self) -> Mat4 {
         match self {
             Self::Perspective(projection) => projection.get_clip_from_view(),
             Self::Orthographic(projection) => projection.get_clip_from_view(),
+            Self::Custom(projection) => projection.get_clip_from_view(),
         }
     }
 
@@ -81,6 +92,7 @@ impl CameraProjection for PortalProjection {
         match self {
             Self::Perspective(projection) => projection.get_clip_from_view_for_sub(sub_view),
             Self::Orthographic(projection) => projection.get_clip_from_view_for_sub(sub_view),
+            Self::Custom(projection) => projection.get_clip_from_view_for_sub(sub_view),
         }
     }
 
@@ -88,6 +100,7 @@ impl CameraProjection for PortalProjection {
         match self {
             Self::Perspective(projection) => projection.update(width, height),
             Self::Orthographic(projection) => projection.update(width, height),
+            Self::Custom(projection) => projection.update(width, height),
         }
     }
 
@@ -95,6 +108,7 @@ impl CameraProjection for PortalProjection {
         match self {
             Self::Perspective(projection) => projection.far(),
             Self::Orthographic(projection) => projection.far(),
+            Self::Custom(projection) => projection.far(),
         }
     }
 
@@ -102,6 +116,64 @@ impl CameraProjection for PortalProjection {
         match self {
             Self::Perspective(projection) => projection.get_frustum_corners(z_near, z_far),
             Self::Orthographic(projection) => projection.get_frustum_corners(z_near, z_far),
+            Self::Custom(projection) => projection.get_frustum_corners(z_near, z_far),
         }
     }
 }
+
+/// A projection usable by [PortalProjection::Custom], implemented for any cloneable [CameraProjection].
+pub trait PortalCustomProjection: CameraProjection + Debug + Send + Sync + 'static {
+    fn clone_box(&self) -> Box<dyn PortalCustomProjection>;
+}
+
+impl<P: CameraProjection + Clone + Debug + Send + Sync + 'static> PortalCustomProjection for P {
+    fn clone_box(&self) -> Box<dyn PortalCustomProjection> {
+        Box::new(self.clone())
+    }
+}
+
+/// Boxed [PortalCustomProjection], delegates the [CameraProjection] calls to it.
+///
+/// Defaults to a [PerspectiveProjection].
+#[derive(Debug)]
+pub struct CustomPortalProjection(pub Box<dyn PortalCustomProjection>);
+
+impl CustomPortalProjection {
+    pub fn new(projection: impl PortalCustomProjection) -> Self {
+        Self(Box::new(projection))
+    }
+}
+
+impl Default for CustomPortalProjection {
+    fn default() -> Self {
+        Self::new(PerspectiveProjection::default())
+    }
+}
+
+impl Clone for CustomPortalProjection {
+    fn clone(&self) -> Self {
+        Self(self.0.clone_box())
+    }
+}
+
+impl CameraProjection for CustomPortalProjection {
+    fn get_clip_from_view(&self) -> Mat4 {
+        self.0.get_clip_from_view()
+    }
+
+    fn get_clip_from_view_for_sub(&self, sub_view: &SubCameraView) -> Mat4 {
+        self.0.get_clip_from_view_for_sub(sub_view)
+    }
+
+    fn update(&mut self, width: f32, height: f32) {
+        self.0.update(width, height)
+    }
+
+    fn far(&self) -> f32 {
+        self.0.far()
+    }
+
+    fn get_frustum_corners(&self, z_near: f32, z_far: f32) -> [Vec3A; 8] {
+        self.0.get_frustum_corners(z_near, z_far)
+    }
+}
@@ -0,0 +1,121 @@
+//! Helpers to navigate the parts of portals
+
+use bevy_asset::Handle;
+use bevy_ecs::{prelude::*, system::SystemParam};
+use bevy_image::Image;
+use bevy_math::Vec3;
+use bevy_render::prelude::*;
+use bevy_transform::prelude::*;
+
+use super::*;
+
+impl PortalParts {
+    /// The entity with the [Portal] component.
+    pub fn portal(&self) -> Entity {
+        self.portal
+    }
+
+    /// The entity with the [PortalDestination] component.
+    pub fn destination(&self) -> Entity {
+        self.destination
+    }
+
+    /// The entity with the [PortalCamera] component.
+    pub fn portal_camera(&self) -> Entity {
+        self.portal_camera
+    }
+
+    /// The camera seeing the portal.
+    pub fn main_camera(&self) -> Entity {
+        self.main_camera
+    }
+
+    /// Whether `entity` is the portal, its destination or its portal camera.
+    ///
+    /// The main camera is not counted as it can see several portals.
+    pub fn contains(&self, entity: Entity) -> bool {
+        [self.portal, self.destination, self.portal_camera].contains(&entity)
+    }
+}
+
+/// The parts of a portal and their main components, see [PortalsQuery].
+pub struct PortalPartsItem<'a> {
+    pub parts: &'a PortalParts,
+    pub portal_transform: &'a GlobalTransform,
+    pub destination_transform: &'a GlobalTransform,
+    pub portal_camera: &'a PortalCamera,
+    pub portal_camera_transform: &'a GlobalTransform,
+    /// `None` if [PortalsPlugin::rendering] is false.
+    pub camera: Option<&'a Camera>,
+}
+
+impl PortalPartsItem<'_> {
+    /// The image the [PortalCamera] renders to.
+    pub fn image(&self) -> &Handle<Image> {
+        &self.portal_camera.image
+    }
+
+    /// Where a point at the destination is seen through the portal, in world space,
+    /// see [apparent_position_through_portal].
+    pub fn apparent_position(&self, point: Vec3) -> Vec3 {
+        apparent_position_through_portal(
+            point,
+            &self.portal_transform.compute_transform(),
+            &self.destination_transform.compute_transform(),
+        )
+    }
+}
+
+/// [SystemParam] to get all the parts of a portal from any of them,
+/// instead of following [PortalParts] through several queries.
+#[derive(SystemParam)]
+pub struct PortalsQuery<'w, 's> {
+    portals: Query<'w, 's, &'static Portal>,
+    destinations: Query<'w, 's, &'static PortalDestination>,
+    portal_cameras: Query<'w, 's, (&'static PortalCamera, Option<&'static Camera>)>,
+    transforms: Query<'w, 's, &'static GlobalTransform>,
+}
+
+impl PortalsQuery<'_, '_> {
+    /// The [PortalParts] of the portal that `entity` is a part of, if it is a portal,
+    /// a destination or a portal camera.
+    ///
+    /// A destination can be shared by several portals, the first one found is returned.
+    pub fn parts(&self, entity: Entity) -> Option<&PortalParts> {
+        if let Ok(portal) = self.portals.get(entity) {
+            return Some(&portal.parts);
+        }
+        if let Ok((portal_camera, _)) = self.portal_cameras.get(entity) {
+            return Some(&portal_camera.parts);
+        }
+        self.destinations
+            .get(entity)
+            .ok()
+            .map(|destination| &destination.parts)
+    }
+
+    /// The parts of the portal that `entity` is a part of and their main components,
+    /// `None` if `entity` is not a part of a portal or a part is missing.
+    pub fn get(&self, entity: Entity) -> Option<PortalPartsItem<'_>> {
+        self.resolve(self.parts(entity)?)
+    }
+
+    /// Iterates over the portals whose parts are all present.
+    pub fn iter(&self) -> impl Iterator<Item = PortalPartsItem<'_>> {
+        self.portals
+            .iter()
+            .filter_map(|portal| self.resolve(&portal.parts))
+    }
+
+    fn resolve<'a>(&'a self, parts: &'a PortalParts) -> Option<PortalPartsItem<'a>> {
+        let (portal_camera, camera) = self.portal_cameras.get(parts.portal_camera).ok()?;
+        Some(PortalPartsItem {
+            parts,
+            portal_transform: self.transforms.get(parts.portal).ok()?,
+            destination_transform: self.transforms.get(parts.destination).ok()?,
+            portal_camera,
+            portal_camera_transform: self.transforms.get(parts.portal_camera).ok()?,
+            camera,
+        })
+    }
+}
@@ -0,0 +1,184 @@
+//! Rebuild of the bind groups of the portal materials in the render world, when their images are
+//! resized or the viewport of their main camera changes
+
+use bevy_app::prelude::*;
+use bevy_asset::{prelude::*, AssetId};
+use bevy_ecs::{entity::EntityHashSet, prelude::*, system::StaticSystemParam};
+use bevy_math::Vec4;
+use bevy_pbr::{prelude::*, MaterialExtension, MaterialPipeline, PreparedMaterial};
+use bevy_render::{
+    prelude::*,
+    render_asset::{prepare_assets, RenderAssets},
+    renderer::RenderDevice,
+    texture::GpuImage,
+    Extract, ExtractSchedule, Render, RenderApp, RenderSet,
+};
+use std::collections::{HashMap, HashSet};
+
+use super::*;
+
+/// Add the rebind logic of the portal materials `M` to [PortalsPlugin]
+///
+/// A resized image is a new texture, which the bind group of a material keeps pointing to the
+/// old one until the material is prepared again (see <https://github.com/bevyengine/bevy/issues/8767>).
+/// Modifying the material asset would prepare it again entirely, each frame while a window is
+/// dragged, so only its bind group is rebuilt, from a copy of the material with the viewport
+/// of its main camera.
+pub(super) fn build_rebind<M: PortalViewportMaterial>(app: &mut App) {
+    let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+        return;
+    };
+    render_app
+        .init_resource::<PortalMaterialRebinds<M>>()
+        .add_systems(ExtractSchedule, extract_portal_material_rebinds::<M>)
+        .add_systems(
+            Render,
+            rebind_portal_materials::<M>
+                .in_set(RenderSet::PrepareAssets)
+                .after(prepare_assets::<GpuImage>)
+                .after(prepare_assets::<PreparedMaterial<M>>),
+        );
+}
+
+/// [Material] showing the image of a portal over the viewport of its main camera, which bind
+/// group is rebuilt by the render world, see [build_rebind]
+pub(super) trait PortalViewportMaterial: Material + Clone {
+    /// Viewport of the main camera, see [PortalMaterial::viewport]
+    fn portal_viewport(&self) -> Vec4;
+
+    fn set_portal_viewport(&mut self, viewport: Vec4);
+}
+
+impl PortalViewportMaterial for PortalMaterial {
+    fn portal_viewport(&self) -> Vec4 {
+        self.viewport
+    }
+
+    fn set_portal_viewport(&mut self, viewport: Vec4) {
+        self.viewport = viewport;
+    }
+}
+
+impl PortalViewportMaterial for LitPortalMaterial {
+    fn portal_viewport(&self) -> Vec4 {
+        self.extension.viewport
+    }
+
+    fn set_portal_viewport(&mut self, viewport: Vec4) {
+        self.extension.viewport = viewport;
+    }
+}
+
+impl<E: MaterialExtension> PortalViewportMaterial for ExtendedPortalMaterial<E> {
+    fn portal_viewport(&self) -> Vec4 {
+        self.base.viewport
+    }
+
+    fn set_portal_viewport(&mut self, viewport: Vec4) {
+        self.base.viewport = viewport;
+    }
+}
+
+/// Render world [Resource] with the portal materials `M` to rebind
+#[derive(Resource)]
+struct PortalMaterialRebinds<M: PortalViewportMaterial> {
+    /// Copies of the materials to rebind this frame, with the viewport of their main camera
+    pending: Vec<(AssetId<M>, M)>,
+    /// Viewport in the bind group of each material
+    viewports: HashMap<AssetId<M>, Vec4>,
+}
+
+impl<M: PortalViewportMaterial> Default for PortalMaterialRebinds<M> {
+    fn default() -> Self {
+        Self {
+            pending: Vec::new(),
+            viewports: HashMap::new(),
+        }
+    }
+}
+
+/// [System] of the [ExtractSchedule] that copies the portal materials `M` to rebind, because their
+/// image was resized or the viewport of their main camera changed
+#[allow(clippy::type_complexity)]
+fn extract_portal_material_rebinds<M: PortalViewportMaterial>(
+    mut rebinds: ResMut<PortalMaterialRebinds<M>>,
+    mut resized_events: Extract<EventReader<PortalImageResized>>,
+    mut material_events: Extract<EventReader<AssetEvent<M>>>,
+    portals: Extract<Query<(Entity, &Portal, &MeshMaterial3d<M>)>>,
+    main_cameras: Extract<Query<&Camera>>,
+    materials: Extract<Res<Assets<M>>>,
+) {
+    let rebinds = &mut *rebinds;
+    rebinds.pending.clear();
+
+    // Prepared again from the material asset, with its own viewport
+    for event in material_events.read() {
+        if let AssetEvent::Added { id } | AssetEvent::Modified { id } = event {
+            match materials.get(*id) {
+                Some(material) => rebinds.viewports.insert(*id, material.portal_viewport()),
+                None => rebinds.viewports.remove(id),
+            };
+        }
+    }
+    let resized: EntityHashSet = resized_events.read().map(|event| event.portal).collect();
+
+    let mut portal_materials = HashSet::new();
+    for (portal_entity, portal, portal_material) in portals.iter() {
+        let id = portal_material.id();
+        portal_materials.insert(id);
+        let Some(rect) = main_cameras
+            .get(portal.parts.main_camera)
+            .ok()
+            .and_then(Camera::physical_viewport_rect)
+        else {
+            continue;
+        };
+        let viewport = Vec4::new(
+            rect.min.x as f32,
+            rect.min.y as f32,
+            rect.width() as f32,
+            rect.height() as f32,
+        );
+        if !resized.contains(&portal_entity) && rebinds.viewports.get(&id) == Some(&viewport) {
+            continue;
+        }
+        let Some(material) = materials.get(id) else {
+            continue;
+        };
+        let mut material = material.clone();
+        material.set_portal_viewport(viewport);
+        rebinds.pending.push((id, material));
+    }
+    rebinds
+        .viewports
+        .retain(|id, _| portal_materials.contains(id));
+}
+
+/// [System] of the render world that rebuilds the bind groups of the portal materials `M`
+/// extracted by [extract_portal_material_rebinds]
+fn rebind_portal_materials<M: PortalViewportMaterial>(
+    mut rebinds: ResMut<PortalMaterialRebinds<M>>,
+    mut prepared_materials: ResMut<RenderAssets<PreparedMaterial<M>>>,
+    render_device: Res<RenderDevice>,
+    pipeline: Res<MaterialPipeline<M>>,
+    mut param: StaticSystemParam<M::Param>,
+) {
+    let rebinds = &mut *rebinds;
+    for (id, material) in rebinds.pending.drain(..) {
+        let Some(prepared_material) = prepared_materials.get_mut(id) else {
+            // Not prepared yet, or failed to, extracted again next frame
+            rebinds.viewports.remove(&id);
+            continue;
+        };
+        match material.as_bind_group(&pipeline.material_layout, &render_device, &mut param) {
+            Ok(prepared) => {
+                prepared_material.bindings = prepared.bindings;
+                prepared_material.bind_group = prepared.bind_group;
+                rebinds.viewports.insert(id, material.portal_viewport());
+            }
+            Err(_) => {
+                rebinds.viewports.remove(&id);
+            }
+        }
+    }
+}
@@ -0,0 +1,341 @@
+//! Commands to change the parts of a live portal
+
+use bevy_core_pipeline::{
+    bloom::Bloom,
+    prelude::*,
+    tonemapping::{DebandDither, Tonemapping},
+};
+use bevy_ecs::{prelude::*, system::EntityCommand};
+use bevy_hierarchy::prelude::*;
+use bevy_math::prelude::*;
+use bevy_pbr::{ScreenSpaceAmbientOcclusion, ShadowFilteringMethod};
+use bevy_render::{
+    camera::Exposure,
+    prelude::*,
+    view::{ColorGrading, Msaa},
+};
+use bevy_transform::prelude::*;
+use std::f32::consts::PI;
+use tracing::warn;
+
+use super::*;
+
+/// [EntityCommand] to switch a live [Portal] to another destination, without despawning
+/// and creating the portal again.
+///
+/// The new destination needs a [GlobalTransform], and must not already be the destination
+/// of another portal.
+/// It can also be created like at the creation of the portal, for example to turn a portal into
+/// a mirror with [RetargetPortalCommand::mirror].
+/// The [PortalDestination] component is moved from the old destination to the new one, with
+/// the entities spawned by this crate under the old destination (see [PortalSpawnedEntity]).
+/// If the old destination was spawned by this crate (see [PortalDestinationSource::Create] and
+/// [PortalDestinationSource::CreateMirror]), it is despawned, other old destinations are left as
+/// they are.
+///
+/// The back faces of the portal (see [CreatePortal::back_destination]) follow the front
+/// destination if their destination was created from it, like at creation.
+///
+/// The [PortalCamera] is moved at the next [update_portal_cameras], the virtual pointers
+/// picking through the portal (with the `picking` feature) are tied to the portal camera so they
+/// keep working.
+/// Queue it on the portal entity: `commands.entity(portal).queue(RetargetPortalCommand::new(destination))`.
+pub struct RetargetPortalCommand {
+    destination: PortalDestinationSource,
+}
+
+impl RetargetPortalCommand {
+    pub fn new(destination: Entity) -> Self {
+        Self {
+            destination: PortalDestinationSource::Use(destination),
+        }
+    }
+
+    /// Retargets to a destination created like [PortalDestinationSource::Create]
+    pub fn create(create_destination: CreatePortalDestination) -> Self {
+        Self {
+            destination: PortalDestinationSource::Create(create_destination),
+        }
+    }
+
+    /// Turns the portal into a mirror, like [PortalDestinationSource::CreateMirrorWith]
+    pub fn mirror(mirror_config: MirrorConfig) -> Self {
+        Self {
+            destination: PortalDestinationSource::CreateMirrorWith(mirror_config),
+        }
+    }
+}
+
+impl EntityCommand for RetargetPortalCommand {
+    fn apply(self, id: Entity, world: &mut World) {
+        let Some(parts) = world.get::<Portal>(id).map(|portal| portal.parts.clone()) else {
+            warn!("RetargetPortalCommand called on entity {id} which is not a portal");
+            return;
+        };
+
+        let destination_source = match self.destination {
+            PortalDestinationSource::Resolve(resolver) => match resolver.resolve(id, world) {
+                Some(destination) => PortalDestinationSource::Use(destination),
+                None => {
+                    warn!("Can't retarget portal {id}, its new destination is not resolved");
+                    return;
+                }
+            },
+            destination_source => destination_source,
+        };
+        let destination = match destination_source {
+            PortalDestinationSource::Use(destination) => {
+                if parts.destination == destination {
+                    return;
+                }
+                if !world
+                    .get_entity(destination)
+                    .is_ok_and(|destination| destination.contains::<GlobalTransform>())
+                {
+                    warn!("Can't retarget portal {id} to entity {destination} which doesn't exist or has no GlobalTransform");
+                    return;
+                }
+                if let Some(other_destination) = world.get::<PortalDestination>(destination) {
+                    warn!(
+                        "Can't retarget portal {id} to entity {destination} which is already the destination of portal {}",
+                        other_destination.parts.portal
+                    );
+                    return;
+                }
+                destination
+            }
+            ref destination_source => {
+                let mut commands = world.commands();
+                let destination = spawn_portal_destination(&mut commands, id, destination_source);
+                world.flush();
+                destination
+            }
+        };
+
+        // Back faces whose destination was created from the front destination, see create_portal
+        let back_portals: Vec<Entity> = world
+            .get::<Children>(id)
+            .into_iter()
+            .flatten()
+            .copied()
+            .filter(|child| world.get::<Portal>(*child).is_some())
+            .collect();
+        for back_portal in back_portals {
+            let Some(back_destination) = world
+                .get::<Portal>(back_portal)
+                .map(|back_portal| back_portal.parts.destination)
+            else {
+                continue;
+            };
+            let created_from_front = world.get::<PortalSpawnedEntity>(back_destination).is_some()
+                && world.get::<Parent>(back_destination).is_some_and(|parent| {
+                    parent.get() == parts.destination || parent.get() == back_portal
+                });
+            if !created_from_front {
+                continue;
+            }
+            let back_destination_source = match destination_source.mirror_config() {
+                Some(mirror_config) => {
+                    PortalDestinationSource::CreateMirrorWith(mirror_config.back())
+                }
+                // The back portal is rotated by PI around Y, so is the back of the destination
+                None => PortalDestinationSource::Create(CreatePortalDestination {
+                    parent: Some(destination),
+                    ..Transform::from_rotation(Quat::from_axis_angle(Vec3::Y, PI)).into()
+                }),
+            };
+            RetargetPortalCommand {
+                destination: back_destination_source,
+            }
+            .apply(back_portal, world);
+        }
+
+        let new_parts = PortalParts {
+            destination,
+            ..parts.clone()
+        };
+
+        // Old destination, the debug entities of the portal follow the new destination
+        let spawned_children: Vec<Entity> = world
+            .get::<Children>(parts.destination)
+            .into_iter()
+            .flatten()
+            .copied()
+            .filter(|child| world.get::<PortalSpawnedEntity>(*child).is_some())
+            .collect();
+        for spawned_child in spawned_children {
+            world.entity_mut(spawned_child).set_parent(destination);
+        }
+        if let Ok(mut old_destination) = world.get_entity_mut(parts.destination) {
+            if old_destination.contains::<PortalSpawnedEntity>() {
+                old_destination.despawn_recursive();
+            } else {
+                old_destination.remove::<PortalDestination>();
+            }
+        }
+
+        world.entity_mut(destination).insert(PortalDestination {
+            parts: new_parts.clone(),
+        });
+        if let Some(mut portal) = world.get_mut::<Portal>(id) {
+            portal.parts = new_parts.clone();
+        }
+        // Changing the portal camera makes update_portal_cameras move it
+        if let Some(mut portal_camera) = world.get_mut::<PortalCamera>(parts.portal_camera) {
+            portal_camera.parts = new_parts;
+        }
+    }
+}
+
+/// [EntityCommand] to make a live [Portal] follow another main camera, for example when
+/// the player switches between a first-person and a cinematic camera.
+///
+/// The render settings of the new main camera are copied to the [PortalCamera] like at
+/// creation (see [PortalRenderQuality]), except its projection, the [PortalProjection] is kept.
+/// The image of the portal is resized by [update_portal_cameras] if the viewport of the new
+/// main camera has another size.
+/// The back faces of the portal (see [CreatePortal::back_destination]) follow the new main
+/// camera too.
+/// The portal won't follow another camera automatically anymore, see [PortalCamera::auto_main_camera].
+pub struct SetPortalMainCameraCommand {
+    main_camera: Entity,
+}
+
+impl SetPortalMainCameraCommand {
+    pub fn new(main_camera: Entity) -> Self {
+        Self { main_camera }
+    }
+}
+
+impl EntityCommand for SetPortalMainCameraCommand {
+    fn apply(self, id: Entity, world: &mut World) {
+        let Some(parts) = world.get::<Portal>(id).map(|portal| portal.parts.clone()) else {
+            warn!("SetPortalMainCameraCommand called on entity {id} which is not a portal");
+            return;
+        };
+        let main_camera = self.main_camera;
+        let Ok(main_camera_entity) = world.get_entity(main_camera) else {
+            warn!("Can't set the main camera of portal {id} to entity {main_camera} which doesn't exist");
+            return;
+        };
+        if !main_camera_entity.contains::<Camera>() || main_camera_entity.contains::<PortalCamera>()
+        {
+            warn!("Can't set the main camera of portal {id} to entity {main_camera} which is not a Camera or is a PortalCamera");
+            return;
+        }
+
+        set_portal_main_camera(world, &parts, main_camera, false);
+
+        // Back faces, see CreatePortal::back_destination
+        let back_portals_parts: Vec<PortalParts> = world
+            .get::<Children>(id)
+            .into_iter()
+            .flatten()
+            .filter_map(|child| world.get::<Portal>(*child))
+            .map(|back_portal| back_portal.parts.clone())
+            .collect();
+        for back_portal_parts in back_portals_parts {
+            set_portal_main_camera(world, &back_portal_parts, main_camera, false);
+        }
+    }
+}
+
+/// Makes the portal with these `parts` follow `main_camera`, copying the render settings of
+/// `main_camera` to its [PortalCamera] like at creation.
+///
+/// Used by [SetPortalMainCameraCommand] and [rebind_portal_main_cameras], `main_camera` must
+/// be a [Camera].
+pub(super) fn set_portal_main_camera(
+    world: &mut World,
+    parts: &PortalParts,
+    main_camera: Entity,
+    auto_main_camera: bool,
+) {
+    let Ok(main_camera_entity) = world.get_entity(main_camera) else {
+        return;
+    };
+
+    // Render settings, like in create_portal
+    let camera3d = main_camera_entity.get::<Camera3d>().cloned();
+    let tonemapping = main_camera_entity.get::<Tonemapping>().cloned();
+    let deband_dither = main_camera_entity.get::<DebandDither>().cloned();
+    let color_grading = main_camera_entity.get::<ColorGrading>().cloned();
+    let exposure = main_camera_entity.get::<Exposure>().cloned();
+    let msaa = main_camera_entity.get::<Msaa>().copied();
+    let bloom = main_camera_entity.get::<Bloom>().cloned();
+    let ssao = main_camera_entity
+        .get::<ScreenSpaceAmbientOcclusion>()
+        .cloned();
+    let shadow_filtering_method = main_camera_entity.get::<ShadowFilteringMethod>().copied();
+
+    let new_parts = PortalParts {
+        main_camera,
+        ..parts.clone()
+    };
+    if let Some(mut portal) = world.get_mut::<Portal>(parts.portal) {
+        portal.parts = new_parts.clone();
+    }
+    if let Some(mut destination) = world.get_mut::<PortalDestination>(parts.destination) {
+        destination.parts = new_parts.clone();
+    }
+
+    let Ok(mut portal_camera_entity) = world.get_entity_mut(parts.portal_camera) else {
+        return;
+    };
+    let Some(mut portal_camera) = portal_camera_entity.get_mut::<PortalCamera>() else {
+        return;
+    };
+    // Changing the portal camera makes update_portal_cameras move it
+    portal_camera.parts = new_parts;
+    portal_camera.auto_main_camera = auto_main_camera;
+    let render_quality = portal_camera.render_quality;
+
+    // Headless portal cameras have no Camera, see PortalsHeadless
+    if !portal_camera_entity.contains::<Camera>() {
+        return;
+    }
+    let destination_depth = portal_camera_entity.contains::<PortalDestinationDepth>();
+    let mut camera3d = camera3d.unwrap_or_default();
+    if destination_depth {
+        camera3d.depth_texture_usages = portal_camera_entity
+            .get::<Camera3d>()
+            .map(|portal_camera3d| portal_camera3d.depth_texture_usages)
+            .unwrap_or(camera3d.depth_texture_usages);
+    }
+    portal_camera_entity.insert((
+        camera3d,
+        tonemapping.unwrap_or_default(),
+        deband_dither.unwrap_or_default(),
+        color_grading.unwrap_or_default(),
+        exposure.unwrap_or_default(),
+    ));
+
+    match render_quality {
+        PortalRenderQuality::Full => {
+            portal_camera_entity.insert(msaa.unwrap_or_default());
+            match bloom {
+                Some(bloom) => portal_camera_entity.insert(bloom),
+                None => portal_camera_entity.remove::<Bloom>(),
+            };
+            match ssao {
+                Some(ssao) => portal_camera_entity.insert(ssao),
+                None => portal_camera_entity.remove::<ScreenSpaceAmbientOcclusion>(),
+            };
+            portal_camera_entity.insert(shadow_filtering_method.unwrap_or_default());
+        }
+        PortalRenderQuality::Simplified => {
+            portal_camera_entity
+                .insert((msaa.unwrap_or_default(), ShadowFilteringMethod::Hardware2x2));
+        }
+        PortalRenderQuality::Fastest => {
+            portal_camera_entity.insert((
+                Msaa::Off,
+                DebandDither::Disabled,
+                ShadowFilteringMethod::Hardware2x2,
+            ));
+        }
+    }
+    if destination_depth {
+        portal_camera_entity.insert(Msaa::Off);
+    }
+}
@@ -0,0 +1,79 @@
+//! Serde helpers for the fields of the configuration types whose types don't implement serde
+//! traits, used with the `serde` feature
+
+/// `Option<Face>` as `"Front"`, `"Back"` or nothing
+pub(super) mod face {
+    use bevy_render::render_resource::Face;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    #[derive(Serialize, Deserialize)]
+    enum SerializedFace {
+        Front,
+        Back,
+    }
+
+    pub fn serialize<S: Serializer>(face: &Option<Face>, serializer: S) -> Result<S::Ok, S::Error> {
+        face.map(|face| match face {
+            Face::Front => SerializedFace::Front,
+            Face::Back => SerializedFace::Back,
+        })
+        .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Face>, D::Error> {
+        Ok(
+            Option::<SerializedFace>::deserialize(deserializer)?.map(|face| match face {
+                SerializedFace::Front => Face::Front,
+                SerializedFace::Back => Face::Back,
+            }),
+        )
+    }
+}
+
+/// `Option<HalfSpace>` as its normal and distance, see [HalfSpace::normal_d](bevy_render::primitives::HalfSpace::normal_d)
+pub(super) mod half_space {
+    use bevy_math::Vec4;
+    use bevy_render::primitives::HalfSpace;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        half_space: &Option<HalfSpace>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        half_space
+            .map(|half_space| half_space.normal_d())
+            .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<HalfSpace>, D::Error> {
+        Ok(Option::<Vec4>::deserialize(deserializer)?.map(HalfSpace::new))
+    }
+}
+
+/// [RenderLayers](bevy_render::view::RenderLayers) as the list of its layers
+pub(super) mod render_layers {
+    use bevy_render::view::RenderLayers;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        render_layers: &RenderLayers,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        render_layers
+            .iter()
+            .collect::<Vec<_>>()
+            .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<RenderLayers, D::Error> {
+        Ok(RenderLayers::from_layers(&Vec::<usize>::deserialize(
+            deserializer,
+        )?))
+    }
+}
@@ -0,0 +1,107 @@
+//! Bounds of the portals with a skinned mesh
+
+use bevy_app::prelude::*;
+use bevy_asset::prelude::*;
+use bevy_ecs::prelude::*;
+use bevy_math::{Mat4, Vec3};
+use bevy_render::{
+    mesh::{
+        skinning::{SkinnedMesh, SkinnedMeshInverseBindposes},
+        VertexAttributeValues,
+    },
+    prelude::*,
+    primitives::Aabb,
+};
+use bevy_transform::prelude::*;
+
+use super::*;
+
+/// Add the update of the bounds of skinned portals to [PortalsPlugin]
+pub(super) fn build_skinned(app: &mut App) {
+    app.add_systems(
+        PostUpdate,
+        update_skinned_portal_aabbs
+            .in_set(PortalSystems::UpdateCameras)
+            .before(update_portal_cameras),
+    );
+}
+
+/// [System] recomputing the [Aabb] of the portals with a [SkinnedMesh] from the current pose of
+/// their joints, so that the frustum of their [PortalCamera] (see [PortalMode]) and the culling
+/// of the portal mesh follow the animation, for example for a rippling cloth portal.
+///
+/// The vertices are skinned on the CPU like in Bevy's shader, which is fine for the few vertices
+/// of a portal mesh.
+/// Morph targets are not applied, the [Aabb] can be updated manually for them, the frustum of the
+/// portal camera is updated when it changes.
+pub fn update_skinned_portal_aabbs(
+    mut portals: Query<(&Mesh3d, &SkinnedMesh, &GlobalTransform, &mut Aabb), With<Portal>>,
+    joints: Query<&GlobalTransform>,
+    meshes: Res<Assets<Mesh>>,
+    inverse_bindposes: Res<Assets<SkinnedMeshInverseBindposes>>,
+) {
+    for (mesh, skinned_mesh, portal_transform, mut aabb) in &mut portals {
+        let (Some(mesh), Some(inverse_bindposes)) = (
+            meshes.get(&mesh.0),
+            inverse_bindposes.get(&skinned_mesh.inverse_bindposes),
+        ) else {
+            continue;
+        };
+        let (
+            Some(positions),
+            Some(VertexAttributeValues::Uint16x4(joint_indices)),
+            Some(VertexAttributeValues::Float32x4(joint_weights)),
+        ) = (
+            mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+                .and_then(VertexAttributeValues::as_float3),
+            mesh.attribute(Mesh::ATTRIBUTE_JOINT_INDEX),
+            mesh.attribute(Mesh::ATTRIBUTE_JOINT_WEIGHT),
+        )
+        else {
+            continue;
+        };
+        let Some(joint_matrices) = skinned_mesh
+            .joints
+            .iter()
+            .zip(inverse_bindposes.iter())
+            .map(|(joint, inverse_bindpose)| {
+                joints
+                    .get(*joint)
+                    .ok()
+                    .map(|joint_transform| joint_transform.compute_matrix() * *inverse_bindpose)
+            })
+            .collect::<Option<Vec<_>>>()
+        else {
+            continue;
+        };
+
+        // The skin replaces the transform of the mesh, the Aabb is in its local space
+        let local_from_world = portal_transform.affine().inverse();
+        let mut min = Vec3::splat(f32::MAX);
+        let mut max = Vec3::splat(f32::MIN);
+        for ((position, indices), weights) in positions.iter().zip(joint_indices).zip(joint_weights)
+        {
+            let skin = indices
+                .iter()
+                .zip(weights)
+                .filter_map(|(index, weight)| {
+                    joint_matrices
+                        .get(*index as usize)
+                        .map(|joint_matrix| *joint_matrix * *weight)
+                })
+                .fold(Mat4::ZERO, |skin, joint_matrix| skin + joint_matrix);
+            let local_position =
+                local_from_world.transform_point3(skin.transform_point3(Vec3::from(*position)));
+            min = min.min(local_position);
+            max = max.max(local_position);
+        }
+        if min.cmpgt(max).any() {
+            continue;
+        }
+
+        let new_aabb = Aabb::from_min_max(min, max);
+        if *aabb != new_aabb {
+            *aabb = new_aabb;
+        }
+    }
+}
@@ -0,0 +1,75 @@
+//! Components and systems to scale the time of the scenes behind portals
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use bevy_reflect::{std_traits::ReflectDefault, Reflect};
+use bevy_time::{prelude::*, TimeSystem};
+use std::time::Duration;
+
+/// Add the time logic to [PortalsPlugin](super::PortalsPlugin)
+pub(super) fn build_time(app: &mut App) {
+    app.register_type::<PortalTimeScale>()
+        .register_type::<PortalDestinationTime>()
+        .add_systems(First, update_portal_destination_times.after(TimeSystem));
+}
+
+/// [Component] to scale the time of the scene behind a portal, for example to make it
+/// run in slow motion (below 1) or pause it (0).
+///
+/// Put it on a [PortalDestination](super::PortalDestination), the scaled time is available
+/// to your systems in its [PortalDestinationTime].
+#[derive(Component, Clone, Copy, Debug, Reflect)]
+#[reflect(Component, Default)]
+#[require(PortalDestinationTime)]
+pub struct PortalTimeScale(pub f32);
+
+impl Default for PortalTimeScale {
+    fn default() -> Self {
+        Self(1.)
+    }
+}
+
+/// [Component] with the time of a destination, scaled by its [PortalTimeScale].
+///
+/// Updated during [First] from [`Time`](bevy_time::Time), use it instead of [`Res<Time>`]
+/// in the systems animating the scene behind the portal.
+#[derive(Component, Clone, Copy, Debug, Default, Reflect)]
+#[reflect(Component, Default)]
+pub struct PortalDestinationTime {
+    delta: Duration,
+    elapsed: Duration,
+}
+
+impl PortalDestinationTime {
+    /// Scaled time since the last update.
+    pub fn delta(&self) -> Duration {
+        self.delta
+    }
+
+    /// Scaled time since the last update, in seconds.
+    pub fn delta_secs(&self) -> f32 {
+        self.delta.as_secs_f32()
+    }
+
+    /// Scaled time since the [PortalTimeScale] was added.
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    /// Scaled time since the [PortalTimeScale] was added, in seconds.
+    pub fn elapsed_secs(&self) -> f32 {
+        self.elapsed.as_secs_f32()
+    }
+}
+
+/// [System] that advances the [PortalDestinationTime] of destinations with a [PortalTimeScale].
+pub fn update_portal_destination_times(
+    time: Res<Time>,
+    mut destinations: Query<(&PortalTimeScale, &mut PortalDestinationTime)>,
+) {
+    for (time_scale, mut destination_time) in destinations.iter_mut() {
+        let delta = time.delta().mul_f32(time_scale.0.max(0.));
+        destination_time.delta = delta;
+        destination_time.elapsed += delta;
+    }
+}
@@ -0,0 +1,232 @@
+//! Components and systems to display portal images in UI nodes
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use bevy_input::prelude::*;
+use bevy_math::{Rect, Vec2};
+use bevy_reflect::{std_traits::ReflectDefault, Reflect};
+use bevy_ui::{prelude::*, PositionType};
+
+use super::*;
+
+/// Add the UI logic to [PortalsPlugin]
+pub(super) fn build_ui(app: &mut App) {
+    app.register_type::<UiPortal>()
+        .register_type::<PortalPictureInPicture>()
+        .register_type::<PortalDebugTextureView>()
+        .add_systems(
+            PostUpdate,
+            (
+                update_ui_portals,
+                update_portal_pictures_in_picture,
+                update_portal_debug_texture_views,
+            )
+                .in_set(PortalSystems::UpdateCameras)
+                .after(update_portal_cameras),
+        );
+}
+
+/// [Component] to display the image of a portal in a UI node, for example
+/// a rear-view mirror in a HUD or a crystal ball in an inventory.
+///
+/// The [ImageNode] will use the image of the [PortalCamera] of the portal.
+/// The portal should usually use [PortalViewerMode::Entity], so that the image is
+/// defined by a "virtual viewer" instead of the main camera looking at the portal mesh.
+#[derive(Component, Clone, Reflect)]
+#[reflect(Component)]
+#[require(ImageNode)]
+pub struct UiPortal {
+    /// The entity with the [Portal] component.
+    pub portal: Entity,
+}
+
+/// [System] which sets the image of [UiPortal] nodes to the image of their portal.
+pub fn update_ui_portals(
+    mut ui_portals: Query<(&UiPortal, &mut ImageNode)>,
+    portal_query: Query<&Portal>,
+    portal_camera_query: Query<&PortalCamera>,
+) {
+    for (ui_portal, mut image_node) in ui_portals.iter_mut() {
+        let Some(portal_camera) = portal_query
+            .get(ui_portal.portal)
+            .ok()
+            .and_then(|portal| portal_camera_query.get(portal.parts.portal_camera).ok())
+        else {
+            continue;
+        };
+        if image_node.image != portal_camera.image {
+            image_node.image = portal_camera.image.clone();
+        }
+    }
+}
+
+/// [Component] to display the image of a portal as an overlay on the screen of its main camera,
+/// for example a rear-view mirror or a kill-cam.
+///
+/// Like [UiPortal] but the node is positioned automatically, the portal should usually use
+/// [PortalViewerMode::Entity] too.
+#[derive(Component, Clone, Reflect)]
+#[reflect(Component)]
+#[require(ImageNode)]
+pub struct PortalPictureInPicture {
+    /// The entity with the [Portal] component.
+    pub portal: Entity,
+    /// Position and size of the overlay, in logical pixels relative to the top-left corner
+    /// of the main camera's viewport.
+    pub rect: Rect,
+}
+
+/// [System] which sets the image, position and target camera of [PortalPictureInPicture] nodes.
+#[allow(clippy::type_complexity)]
+pub fn update_portal_pictures_in_picture(
+    mut commands: Commands,
+    mut pictures: Query<(
+        Entity,
+        Ref<PortalPictureInPicture>,
+        &mut ImageNode,
+        &mut Node,
+        Option<&TargetCamera>,
+    )>,
+    portal_query: Query<&Portal>,
+    portal_camera_query: Query<&PortalCamera>,
+) {
+    for (entity, picture, mut image_node, mut node, target_camera) in pictures.iter_mut() {
+        let Some(portal_camera) = portal_query
+            .get(picture.portal)
+            .ok()
+            .and_then(|portal| portal_camera_query.get(portal.parts.portal_camera).ok())
+        else {
+            continue;
+        };
+        if image_node.image != portal_camera.image {
+            image_node.image = portal_camera.image.clone();
+        }
+        let main_camera = portal_camera.parts.main_camera;
+        if target_camera.map(TargetCamera::entity) != Some(main_camera) {
+            commands.entity(entity).insert(TargetCamera(main_camera));
+        }
+        if picture.is_changed() {
+            node.position_type = PositionType::Absolute;
+            node.left = Val::Px(picture.rect.min.x);
+            node.top = Val::Px(picture.rect.min.y);
+            node.width = Val::Px(picture.rect.width());
+            node.height = Val::Px(picture.rect.height());
+        }
+    }
+}
+
+/// [Component] on a UI node to inspect the images of the portals, for debugging.
+///
+/// The node shows the image of a portal in the top-right corner of the screen of its main camera,
+/// or over the whole screen in fullscreen mode.
+/// The keys can be `None` to only change [visible](Self::visible),
+/// [fullscreen](Self::fullscreen) and [portal](Self::portal) from code.
+#[derive(Component, Clone, Reflect)]
+#[reflect(Component, Default)]
+#[require(ImageNode)]
+pub struct PortalDebugTextureView {
+    /// The entity with the [Portal] component, the first portal if `None` or not found.
+    pub portal: Option<Entity>,
+    /// Whether the node is shown, defaults to true.
+    pub visible: bool,
+    /// Whether the node covers the whole screen, defaults to false.
+    pub fullscreen: bool,
+    /// Size of the node when not in fullscreen mode, in logical pixels, defaults to 320x180.
+    pub size: Vec2,
+    /// Key showing or hiding the node, defaults to F9.
+    pub toggle_key: Option<KeyCode>,
+    /// Key switching the fullscreen mode, defaults to F10.
+    pub fullscreen_key: Option<KeyCode>,
+    /// Key showing the image of the next portal, defaults to F8.
+    pub next_key: Option<KeyCode>,
+}
+
+impl Default for PortalDebugTextureView {
+    fn default() -> Self {
+        Self {
+            portal: None,
+            visible: true,
+            fullscreen: false,
+            size: Vec2::new(320., 180.),
+            toggle_key: Some(KeyCode::F9),
+            fullscreen_key: Some(KeyCode::F10),
+            next_key: Some(KeyCode::F8),
+        }
+    }
+}
+
+/// [System] which handles the keys of [PortalDebugTextureView] nodes, and sets their image,
+/// position and target camera.
+#[allow(clippy::type_complexity)]
+pub fn update_portal_debug_texture_views(
+    mut commands: Commands,
+    keys: Option<Res<ButtonInput<KeyCode>>>,
+    mut views: Query<(
+        Entity,
+        &mut PortalDebugTextureView,
+        &mut ImageNode,
+        &mut Node,
+        Option<&TargetCamera>,
+    )>,
+    portal_query: Query<(Entity, &Portal)>,
+    portal_camera_query: Query<&PortalCamera>,
+) {
+    let pressed = |key: Option<KeyCode>| {
+        key.zip(keys.as_ref())
+            .is_some_and(|(key, keys)| keys.just_pressed(key))
+    };
+    let mut portals: Vec<_> = portal_query.iter().collect();
+    portals.sort_by_key(|(entity, _)| *entity);
+
+    for (entity, mut view, mut image_node, mut node, target_camera) in views.iter_mut() {
+        if pressed(view.toggle_key) {
+            view.visible = !view.visible;
+        }
+        if pressed(view.fullscreen_key) {
+            view.fullscreen = !view.fullscreen;
+        }
+        let index = view
+            .portal
+            .and_then(|portal| portals.iter().position(|(entity, _)| *entity == portal));
+        let index = match (index, pressed(view.next_key)) {
+            (Some(index), true) => (index + 1) % portals.len(),
+            (Some(index), false) => index,
+            (None, _) => 0,
+        };
+        let portal_camera = portals.get(index).and_then(|(portal_entity, portal)| {
+            if view.portal != Some(*portal_entity) {
+                view.portal = Some(*portal_entity);
+            }
+            portal_camera_query.get(portal.parts.portal_camera).ok()
+        });
+
+        // Hidden when there is no portal to show
+        let display = match portal_camera {
+            Some(_) if view.visible => Display::Flex,
+            _ => Display::None,
+        };
+        if node.display != display {
+            node.display = display;
+        }
+        if view.is_changed() {
+            node.position_type = PositionType::Absolute;
+            node.top = Val::Px(0.);
+            node.right = Val::Px(0.);
+            (node.width, node.height) = if view.fullscreen {
+                (Val::Percent(100.), Val::Percent(100.))
+            } else {
+                (Val::Px(view.size.x), Val::Px(view.size.y))
+            };
+        }
+        let Some(portal_camera) = portal_camera else {
+            continue;
+        };
+        if image_node.image != portal_camera.image {
+            image_node.image = portal_camera.image.clone();
+        }
+        let main_camera = portal_camera.parts.main_camera;
+        if target_camera.map(TargetCamera::entity) != Some(main_camera) {
+            commands.entity(entity).insert(TargetCamera(main_camera));
+        }
+    }
+}
@@ -1,16 +1,26 @@
 //! System and helpers for the update of portal cameras
 
 use bevy_app::prelude::*;
-use bevy_asset::{Assets, Handle};
-use bevy_ecs::{prelude::*, system::SystemParam};
+use bevy_asset::Assets;
+use bevy_core::FrameCount;
+use bevy_core_pipeline::prelude::Camera3d;
+use bevy_ecs::{
+    entity::{EntityHashMap, EntityHashSet},
+    prelude::*,
+    system::SystemParam,
+};
 use bevy_image::Image;
-use bevy_math::{Quat, UVec2, Vec3};
-use bevy_pbr::MeshMaterial3d;
+use bevy_math::{BVec3, Mat4, Quat, URect, UVec2, Vec2, Vec3, Vec4};
+use bevy_reflect::{std_traits::ReflectDefault, Reflect};
 use bevy_render::{
-    camera::{CameraProjection, ManualTextureViews, RenderTarget},
+    camera::{
+        CameraProjection, ManualTextureViews, NormalizedRenderTarget, RenderTarget, SubCameraView,
+        Viewport,
+    },
     prelude::*,
-    primitives::{Frustum, HalfSpace},
+    primitives::{Aabb, Frustum, HalfSpace},
     render_resource::Extent3d,
+    view::{RenderLayers, VisibilityRange, VisibleEntities, VisibleEntityRanges},
 };
 use bevy_transform::prelude::*;
 use bevy_window::{PrimaryWindow, Window, WindowRef};
@@ -19,37 +29,178 @@ use tracing::warn;
 use super::*;
 
 /// Add the update logic to [PortalsPlugin]
-pub(super) fn build_update(app: &mut App) {
+pub(super) fn build_update(app: &mut App, resize_delay: u32) {
+    app.register_type::<PortalImageResizeDelay>()
+        .register_type::<PortalDestinationPaused>()
+        .register_type::<PortalDeactivation>()
+        .insert_resource(PortalImageResizeDelay(resize_delay));
+
+    app.add_systems(
+        PostUpdate,
+        (
+            rebind_portal_main_cameras,
+            update_portal_camera_viewports,
+            update_portal_cameras,
+            update_portal_camera_orders,
+        )
+            .chain()
+            .in_set(PortalSystems::UpdateCameras),
+    );
+
     app.add_systems(
         PostUpdate,
-        update_portal_cameras.after(bevy_transform::TransformSystem::TransformPropagate),
+        (
+            check_portal_camera_feedback,
+            update_portal_camera_activation.after(update_portal_impostors),
+        )
+            .in_set(PortalSystems::UpdateVisibility),
     );
 }
 
-/// Moves the [PortalCamera] to follow the main camera relative to the portal and the destination.
-#[allow(clippy::too_many_arguments, clippy::type_complexity)]
-pub fn update_portal_cameras(
+/// Binds portals that picked their main camera automatically (see [PortalCamera::auto_main_camera])
+/// to another camera when their main camera has despawned or is not a [Camera] anymore.
+///
+/// The new main camera is a [Camera3d] rendering to the same target as the previous one,
+/// active cameras first, then the one with the lowest entity, so that it doesn't depend on the
+/// order of the query.
+/// Its render settings are copied to the [PortalCamera] like with [SetPortalMainCameraCommand],
+/// and the portal image is resized by [update_portal_cameras] if its viewport has another size.
+///
+/// If no such camera is available, the portal parts are left as is and will be dealt with
+/// by [update_portal_cameras] according to the [PortalPartsDespawnStrategy].
+#[allow(clippy::type_complexity)]
+pub fn rebind_portal_main_cameras(
     mut commands: Commands,
-    strategy: Res<PortalPartsDespawnStrategy>,
-    mut portal_cameras: Query<
+    portal_cameras: Query<&PortalCamera>,
+    main_cameras: Query<
+        (Entity, &Camera, Has<Camera3d>),
+        (Without<PortalCamera>, Without<PortalDebugCamera>),
+    >,
+    primary_window: Query<Entity, With<PrimaryWindow>>,
+    mut main_camera_targets: Local<EntityHashMap<NormalizedRenderTarget>>,
+    mut followed_main_cameras: Local<EntityHashSet>,
+) {
+    let primary_window = primary_window.get_single().ok();
+    followed_main_cameras.clear();
+    for portal_camera in portal_cameras.iter() {
+        if !portal_camera.auto_main_camera {
+            continue;
+        }
+        let main_camera = portal_camera.parts.main_camera;
+        followed_main_cameras.insert(main_camera);
+        // Remembered while the main camera exists, to find a camera like it once it's gone
+        if let Ok((_, camera, _)) = main_cameras.get(main_camera) {
+            if let Some(target) = camera.target.normalize(primary_window) {
+                main_camera_targets.insert(main_camera, target);
+            }
+            continue;
+        }
+        let Some(target) = main_camera_targets.get(&main_camera) else {
+            continue;
+        };
+
+        let Some(new_main_camera) = main_cameras
+            .iter()
+            .filter(|(_, camera, is_camera3d)| {
+                *is_camera3d && camera.target.normalize(primary_window).as_ref() == Some(target)
+            })
+            .min_by_key(|(entity, camera, _)| (!camera.is_active, *entity))
+            .map(|(entity, ..)| entity)
+        else {
+            continue;
+        };
+        let parts = portal_camera.parts.clone();
+        commands.queue(move |world: &mut World| {
+            set_portal_main_camera(world, &parts, new_main_camera, true);
+        });
+    }
+    main_camera_targets.retain(|main_camera, _| followed_main_cameras.contains(main_camera));
+}
+
+/// Moves the [PortalCamera] to follow the main camera (or the viewer, see [PortalViewerMode])
+/// relative to the portal and the destination, and updates its [Frustum].
+///
+/// See [update_portal_cameras_with] to run this logic from a custom system.
+pub fn update_portal_cameras(mut params: PortalCameraUpdateParams) {
+    update_portal_cameras_with(&mut params);
+}
+
+/// [SystemParam] needed by [update_portal_cameras_with]
+///
+/// The render resources are missing when [PortalsPlugin::rendering] is false, see [PortalsHeadless].
+#[allow(clippy::type_complexity)]
+#[derive(SystemParam)]
+pub struct PortalCameraUpdateParams<'w, 's> {
+    commands: Commands<'w, 's>,
+    strategy: Res<'w, PortalPartsDespawnStrategy>,
+    portal_cameras: Query<
+        'w,
+        's,
         (
-            &PortalCamera,
-            &mut Transform,
-            &mut GlobalTransform,
-            &mut Frustum,
-            &PortalProjection,
+            Ref<'static, PortalCamera>,
+            &'static mut Transform,
+            &'static mut GlobalTransform,
+            &'static mut Frustum,
+            &'static PortalProjection,
+            Option<Ref<'static, Camera>>,
         ),
-        With<Camera>,
     >,
-    main_camera_query: Query<(Ref<GlobalTransform>, &Camera), Without<PortalCamera>>,
+    main_camera_query:
+        Query<'w, 's, (Ref<'static, GlobalTransform>, &'static Camera), Without<PortalCamera>>,
+    viewer_query: Query<'w, 's, Ref<'static, GlobalTransform>, Without<PortalCamera>>,
     portal_query: Query<
-        (Ref<GlobalTransform>, &MeshMaterial3d<PortalMaterial>),
-        (With<Portal>, Without<Camera>),
+        'w,
+        's,
+        (
+            Ref<'static, GlobalTransform>,
+            Option<&'static PortalBaseMaterial>,
+            Option<Ref<'static, Aabb>>,
+        ),
+        (With<Portal>, Without<Camera>, Without<PortalCamera>),
     >,
-    destination_query: Query<Ref<GlobalTransform>, (With<PortalDestination>, Without<Camera>)>,
-    mut resize_params: PortalImageSizeParams,
-    mut materials: ResMut<Assets<PortalMaterial>>,
-) {
+    destination_query: Query<
+        'w,
+        's,
+        Ref<'static, GlobalTransform>,
+        (
+            With<PortalDestination>,
+            Without<Camera>,
+            Without<PortalCamera>,
+        ),
+    >,
+    resize_params: PortalImageSizeParams<'w, 's>,
+    materials: Option<ResMut<'w, Assets<PortalMaterial>>>,
+    resize_delay: Option<Res<'w, PortalImageResizeDelay>>,
+    pending_resizes: Local<'s, EntityHashMap<(UVec2, u32)>>,
+}
+
+/// Logic of [update_portal_cameras], to be called from custom systems, for example to update
+/// the portal cameras at another point of the frame or several times per frame around a custom
+/// camera rig.
+///
+/// The transforms and frusta are only updated when the parts of a portal changed since
+/// the last run of the calling system.
+/// See also [get_portal_camera_transform] and [get_frustum] to compute them manually.
+pub fn update_portal_cameras_with(params: &mut PortalCameraUpdateParams) {
+    let PortalCameraUpdateParams {
+        commands,
+        strategy,
+        portal_cameras,
+        main_camera_query,
+        viewer_query,
+        portal_query,
+        destination_query,
+        resize_params,
+        materials,
+        resize_delay,
+        pending_resizes,
+    } = params;
+
+    // Forget the pending resizes of despawned portal cameras
+    if !pending_resizes.is_empty() {
+        pending_resizes.retain(|portal_camera, _| portal_cameras.contains(*portal_camera));
+    }
+
     // For every portal camera
     for (
         portal_camera,
@@ -57,15 +208,24 @@ pub fn update_portal_cameras(
         mut portal_camera_global_transform,
         mut frustum,
         projection,
+        portal_camera_camera,
     ) in portal_cameras.iter_mut()
     {
-        // Main Camera
-        let main_camera_result = main_camera_query.get(portal_camera.parts.main_camera);
+        // Main Camera, any entity with a GlobalTransform for headless portal cameras without
+        // a Camera, see PortalsHeadless
+        let main_camera_result = match portal_camera_camera {
+            Some(_) => main_camera_query
+                .get(portal_camera.parts.main_camera)
+                .map(|(global_transform, camera)| (global_transform, Some(camera))),
+            None => viewer_query
+                .get(portal_camera.parts.main_camera)
+                .map(|global_transform| (global_transform, None)),
+        };
         if let Err(query_error) = main_camera_result {
             deal_with_part_query_error(
-                &mut commands,
+                commands,
                 &portal_camera.parts,
-                &strategy,
+                strategy,
                 &query_error,
                 "Main Camera",
             );
@@ -77,23 +237,23 @@ pub fn update_portal_cameras(
         let portal_result = portal_query.get(portal_camera.parts.portal);
         if let Err(query_error) = portal_result {
             deal_with_part_query_error(
-                &mut commands,
+                commands,
                 &portal_camera.parts,
-                &strategy,
+                strategy,
                 &query_error,
                 "Portal",
             );
             return;
         }
-        let (portal_global_transform, portal_material) = portal_result.unwrap();
+        let (portal_global_transform, portal_material, portal_aabb) = portal_result.unwrap();
 
         // Destination
         let destination_result = destination_query.get(portal_camera.parts.destination);
         if let Err(query_error) = destination_result {
             deal_with_part_query_error(
-                &mut commands,
+                commands,
                 &portal_camera.parts,
-                &strategy,
+                strategy,
                 &query_error,
                 "Destination",
             );
@@ -101,47 +261,150 @@ pub fn update_portal_cameras(
         }
         let destination_global_transform = destination_result.unwrap();
 
-        let portal_image_resized = resize_image_if_needed(
-            portal_camera,
-            main_camera,
-            &mut resize_params,
-            portal_material,
-            &mut materials,
-        );
+        // Viewer
+        let viewer_global_transform = match portal_camera.viewer {
+            PortalViewerMode::MainCamera => Some(main_camera_global_transform),
+            PortalViewerMode::Entity(viewer) => {
+                let Ok(viewer_global_transform) = viewer_query.get(viewer) else {
+                    warn!(
+                        "Viewer #{} of a portal has no GlobalTransform, skipping portal camera update",
+                        viewer.index()
+                    );
+                    continue;
+                };
+                Some(viewer_global_transform)
+            }
+            PortalViewerMode::FixedCamera(_) => None,
+        };
+
+        // Headless portals have no image nor material
+        let mut portal_image_resized = false;
+        if let (Some(main_camera), Some(portal_material), Some(materials)) =
+            (main_camera, portal_material, materials.as_deref_mut())
+        {
+            portal_image_resized = resize_image_if_needed(
+                &portal_camera,
+                main_camera,
+                resize_params,
+                resize_delay
+                    .as_ref()
+                    .map_or(0, |resize_delay| resize_delay.0),
+                pending_resizes,
+            );
+            if portal_image_resized {
+                if let Some(portal_image) = resize_params
+                    .images
+                    .as_ref()
+                    .and_then(|images| images.get(&portal_camera.image))
+                {
+                    commands.send_event(PortalImageResized {
+                        portal: portal_camera.parts.portal,
+                        new_size: portal_image.size(),
+                    });
+                }
+            }
+
+            // The sign of the scale of the portal can change, see portal_cull_mode
+            if portal_camera.is_changed() || portal_global_transform.is_changed() {
+                let cull_mode = portal_cull_mode(portal_camera.cull_mode, &portal_global_transform);
+                // Only touch the material when needed, it rebuilds its bind group
+                if materials
+                    .get(&portal_material.0)
+                    .is_some_and(|material| material.cull_mode != cull_mode)
+                {
+                    if let Some(material) = materials.get_mut(&portal_material.0) {
+                        material.cull_mode = cull_mode;
+                    }
+                }
+            }
+        }
 
         // Needed for update frustum later because of update_frusta
         let destination_transform = &destination_global_transform.compute_transform();
 
-        let should_update_transform = portal_global_transform.is_changed()
+        // The portal camera changes when its parts change, see rebind_portal_main_cameras
+        let should_update_transform = portal_camera.is_changed()
+            || portal_global_transform.is_changed()
             || destination_global_transform.is_changed()
-            || main_camera_global_transform.is_changed();
+            || viewer_global_transform
+                .as_ref()
+                .is_some_and(|viewer_global_transform| viewer_global_transform.is_changed());
 
         if should_update_transform {
-            let portal_transform = &portal_global_transform.compute_transform();
-            let main_camera_transform = &main_camera_global_transform.compute_transform();
-
             // Move portal camera
-            let new_portal_camera_transform = get_portal_camera_transform(
-                main_camera_transform,
-                portal_transform,
-                destination_transform,
-            );
-            *portal_camera_transform = new_portal_camera_transform;
+            let new_portal_camera_global_transform =
+                match (portal_camera.viewer, viewer_global_transform) {
+                    (PortalViewerMode::FixedCamera(fixed_transform), _) => {
+                        GlobalTransform::from(destination_transform.mul_transform(fixed_transform))
+                    }
+                    (_, Some(viewer_global_transform)) => get_portal_camera_global_transform(
+                        &viewer_global_transform.compute_transform(),
+                        &portal_global_transform.compute_transform(),
+                        destination_transform,
+                    ),
+                    (_, None) => unreachable!("Only fixed cameras don't have a viewer"),
+                };
+            // Approximated with non-uniform scales, the GlobalTransform is exact
+            *portal_camera_transform = new_portal_camera_global_transform.compute_transform();
             // We update the global transform manually here for two reasons:
             // 1) This system is run after global transform propagation
             // so if we don't do that the portal camera's global transform would be lagging behind one frame
             // 2) The portal camera should not be in a hierarchy in theory (?)
-            *portal_camera_global_transform = GlobalTransform::from(new_portal_camera_transform);
+            *portal_camera_global_transform = new_portal_camera_global_transform;
         }
 
-        if portal_image_resized || should_update_transform {
+        // The camera changes when its viewport changes, see update_portal_camera_viewports,
+        // and the Aabb when the portal mesh is animated, see update_skinned_portal_aabbs
+        let portal_aabb_changed = portal_aabb.as_ref().is_some_and(Ref::is_changed);
+        if portal_image_resized
+            || should_update_transform
+            || portal_camera_camera.as_ref().is_some_and(Ref::is_changed)
+            || portal_aabb_changed
+        {
             // Update frustum
-            let new_frustum = get_frustum(
-                portal_camera,
-                &portal_camera_transform,
+            let sub_camera_view = portal_camera_camera
+                .as_ref()
+                .and_then(|camera| camera.sub_camera_view.as_ref());
+            let mut new_frustum = get_frustum(
+                &portal_camera,
+                &portal_camera_global_transform,
                 destination_transform,
                 projection,
+                sub_camera_view,
             );
+            let fixed_camera = matches!(portal_camera.viewer, PortalViewerMode::FixedCamera(_));
+            match (&portal_camera.portal_mode, portal_aabb.as_deref()) {
+                (PortalMode::MaskedImageAabbHalfSpaceFrustum(_), Some(portal_aabb))
+                    if !fixed_camera =>
+                {
+                    let clip_from_view = match sub_camera_view {
+                        Some(sub_camera_view) => {
+                            projection.get_clip_from_view_for_sub(sub_camera_view)
+                        }
+                        None => projection.get_clip_from_view(),
+                    };
+                    fit_frustum_to_portal_aabb(
+                        &mut new_frustum,
+                        &portal_camera_global_transform,
+                        clip_from_view,
+                        &portal_global_transform,
+                        portal_aabb,
+                        destination_transform,
+                    );
+                }
+                (PortalMode::MaskedImageRectangleHalfSpaceFrustum(_), Some(portal_aabb))
+                    if !fixed_camera =>
+                {
+                    fit_frustum_to_portal_rectangle(
+                        &mut new_frustum,
+                        &portal_camera_global_transform,
+                        &portal_global_transform,
+                        portal_aabb,
+                        destination_transform,
+                    );
+                }
+                _ => (),
+            }
             *frustum = new_frustum;
         }
 
@@ -149,65 +412,424 @@ pub fn update_portal_cameras(
     }
 }
 
+/// [Component] with the reasons why the [PortalCamera] of a [Portal] doesn't render,
+/// inserted with the [Portal] component.
+///
+/// [update_portal_camera_activation] is the only system of this crate changing
+/// [Camera::is_active] on portal cameras: it activates a portal camera when none of these
+/// reasons apply, and deactivates it otherwise.
+/// Set [manual](Self::manual) to deactivate a portal camera yourself, changing
+/// [Camera::is_active] directly is overwritten.
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+#[reflect(Component, Default)]
+pub struct PortalDeactivation {
+    /// Deactivated by the user.
+    pub manual: bool,
+    /// The portal has a [VisibilityRange] and is out of range of its main camera,
+    /// updated by [update_portal_camera_activation].
+    pub out_of_range: bool,
+    /// The destination of the portal has a [PortalDestinationPaused],
+    /// updated by [update_portal_camera_activation].
+    pub paused: bool,
+    /// The portal is closed, updated by [animate_portals] from its [PortalAnimation].
+    pub closed: bool,
+    /// The portal shows the snapshot of its [PortalImpostor], updated by [update_portal_impostors].
+    pub impostor: bool,
+}
+
+impl PortalDeactivation {
+    /// Whether the [PortalCamera] should not render.
+    pub fn is_deactivated(&self) -> bool {
+        self.manual || self.out_of_range || self.paused || self.closed || self.impostor
+    }
+}
+
+/// [System] activating the [PortalCamera]s of portals without a [PortalDeactivation] reason,
+/// and deactivating the others, see [PortalViewInfo::active].
+///
+/// It also updates [PortalDeactivation::out_of_range], which needs the visibility ranges
+/// computed by Bevy: a portal with a [VisibilityRange] stops rendering when it is out of range
+/// of its main camera, like its mesh is hidden.
+/// And [PortalDeactivation::paused], so that all the portals of a [PortalDestinationPaused]
+/// stop and resume together.
+#[allow(clippy::type_complexity)]
+pub fn update_portal_camera_activation(
+    mut portals: Query<(
+        Entity,
+        &Portal,
+        &mut PortalDeactivation,
+        &mut PortalViewInfo,
+        Has<VisibilityRange>,
+    )>,
+    visible_entity_ranges: Option<Res<VisibleEntityRanges>>,
+    paused_destinations: Query<(), With<PortalDestinationPaused>>,
+    mut portal_cameras: Query<&mut Camera, With<PortalCamera>>,
+) {
+    for (portal_entity, portal, mut deactivation, mut view_info, has_visibility_range) in
+        portals.iter_mut()
+    {
+        let out_of_range = has_visibility_range
+            && visible_entity_ranges.as_ref().is_some_and(|ranges| {
+                !ranges.entity_is_in_range_of_view(portal_entity, portal.parts.main_camera)
+            });
+        if deactivation.out_of_range != out_of_range {
+            deactivation.out_of_range = out_of_range;
+        }
+        let paused = paused_destinations.contains(portal.parts.destination);
+        if deactivation.paused != paused {
+            deactivation.paused = paused;
+        }
+
+        let Ok(mut portal_camera) = portal_cameras.get_mut(portal.parts.portal_camera) else {
+            if view_info.active {
+                view_info.active = false;
+            }
+            continue;
+        };
+        let is_active = !deactivation.is_deactivated();
+        if portal_camera.is_active != is_active {
+            portal_camera.is_active = is_active;
+        }
+        if view_info.active != is_active {
+            view_info.active = is_active;
+        }
+    }
+}
+
+/// Marker [Component] to add to a [PortalDestination] to pause all the [PortalCamera]s
+/// rendering it, for example while that area of the world is being modified or streamed.
+///
+/// The portals keep showing the last image rendered by their camera (or their
+/// [CreatePortal::initial_color] if it never rendered), and resume together when the marker
+/// is removed, see [PortalDeactivation::paused].
+#[derive(Component, Clone, Copy, Debug, Default, Reflect)]
+#[reflect(Component, Default)]
+pub struct PortalDestinationPaused;
+
+/// Number of frames the viewport of a main camera must keep the same size before
+/// the images of its portals are resized.
+///
+/// Resizing a portal image re-uploads it and rebuilds the bind group of its [PortalMaterial],
+/// which can cause hitches with a lot of portals while the window is being dragged.
+/// The image is stretched to the viewport until it is resized.
+///
+/// Defaults to 0 (resize on the same frame), can be set with
+/// [`PortalsPlugin::resize_delay`](PortalsPlugin) or changed during execution.
+#[derive(Resource, Clone, Copy, Default, Reflect)]
+#[reflect(Resource, Default)]
+pub struct PortalImageResizeDelay(pub u32);
+
+/// Restricts the rendering of [PortalCamera]s with [PortalCamera::scissor_to_portal] to the
+/// rectangle covered by their portal on the screen of the main camera.
+///
+/// The image keeps the size of the main camera's viewport, only the covered region is rendered
+/// using a [Viewport] and a [SubCameraView].
+/// The whole image is rendered when part of the portal is behind the main camera,
+/// or when the portal camera doesn't use a perspective projection.
+///
+/// The region is [PortalViewInfo::screen_aabb], scaled to the image while it is not resized yet.
+#[allow(clippy::type_complexity)]
+pub fn update_portal_camera_viewports(
+    mut portal_cameras: Query<(&PortalCamera, &mut Camera, &PortalProjection)>,
+    main_cameras: Query<&Camera, Without<PortalCamera>>,
+    portals: Query<&PortalViewInfo>,
+    images: Res<Assets<Image>>,
+) {
+    for (portal_camera, mut camera, projection) in portal_cameras.iter_mut() {
+        if !portal_camera.scissor_to_portal {
+            continue;
+        }
+        let (Ok(main_camera), Ok(view_info), Some(image)) = (
+            main_cameras.get(portal_camera.parts.main_camera),
+            portals.get(portal_camera.parts.portal),
+            images.get(&portal_camera.image),
+        ) else {
+            continue;
+        };
+
+        let image_size = image.size();
+        let viewport_size = main_camera.physical_viewport_size().unwrap_or_default();
+        let rect = match (projection, view_info.screen_aabb) {
+            (PortalProjection::Perspective(_), Some(screen_aabb))
+                if image_size.cmpgt(UVec2::ZERO).all()
+                    && viewport_size.cmpgt(UVec2::ZERO).all() =>
+            {
+                let scale = image_size.as_vec2() / viewport_size.as_vec2();
+                let rect_min = (screen_aabb.min.as_vec2() * scale)
+                    .floor()
+                    .as_uvec2()
+                    .min(image_size - UVec2::ONE);
+                let rect_max = (screen_aabb.max.as_vec2() * scale)
+                    .ceil()
+                    .as_uvec2()
+                    .min(image_size)
+                    .max(rect_min + UVec2::ONE);
+                Some(URect::from_corners(rect_min, rect_max))
+            }
+            _ => None,
+        };
+        let sub_camera_view = rect.map(|rect| SubCameraView {
+            full_size: image_size,
+            offset: rect.min.as_vec2(),
+            size: rect.size(),
+        });
+
+        if camera.sub_camera_view != sub_camera_view {
+            camera.viewport = rect.map(|rect| Viewport {
+                physical_position: rect.min,
+                physical_size: rect.size(),
+                ..Viewport::default()
+            });
+            camera.sub_camera_view = sub_camera_view;
+        }
+    }
+}
+
+/// Helper function to get the rectangle covered by a portal on the image of its portal camera,
+/// in physical pixels, by projecting the corners of its [Aabb] with the main camera,
+/// `clip_from_view` being its [Camera::clip_from_view].
+///
+/// Returns `None` if a corner is behind the main camera.
+pub(super) fn get_portal_image_rect(
+    clip_from_view: Mat4,
+    main_camera_transform: &GlobalTransform,
+    portal_transform: &GlobalTransform,
+    portal_aabb: &Aabb,
+    image_size: UVec2,
+) -> Option<URect> {
+    if image_size.x == 0 || image_size.y == 0 {
+        return None;
+    }
+
+    let clip_from_world = clip_from_view * main_camera_transform.compute_matrix().inverse();
+    let (aabb_min, aabb_max) = (Vec3::from(portal_aabb.min()), Vec3::from(portal_aabb.max()));
+    let mut rect_min = Vec2::MAX;
+    let mut rect_max = Vec2::MIN;
+    for corner_index in 0..8 {
+        let corner = Vec3::select(
+            BVec3::new(
+                corner_index & 1 == 0,
+                corner_index & 2 == 0,
+                corner_index & 4 == 0,
+            ),
+            aabb_min,
+            aabb_max,
+        );
+        // Like Camera::world_to_ndc
+        let ndc = clip_from_world.project_point3(portal_transform.transform_point(corner));
+        // Behind the camera (reversed z)
+        if ndc.is_nan() || ndc.z < 0. {
+            return None;
+        }
+        let pixel = Vec2::new(ndc.x + 1., 1. - ndc.y) * 0.5 * image_size.as_vec2();
+        rect_min = rect_min.min(pixel);
+        rect_max = rect_max.max(pixel);
+    }
+
+    let rect_min = rect_min
+        .floor()
+        .max(Vec2::ZERO)
+        .as_uvec2()
+        .min(image_size - UVec2::ONE);
+    let rect_max = rect_max
+        .ceil()
+        .max(Vec2::ZERO)
+        .as_uvec2()
+        .min(image_size)
+        .max(rect_min + UVec2::ONE);
+    Some(URect::from_corners(rect_min, rect_max))
+}
+
+/// Sets the [Camera::order] of the [PortalCamera]s so that portals farther from their
+/// main camera are rendered first.
+///
+/// Farther portals get a lower order, so when a farther portal can be seen through a nearer one,
+/// the image of the farther one is already rendered for the current frame when the nearer
+/// portal camera renders. A farther portal seen through a nearer one is usually smaller on the
+/// screen, so it is the one showing a one-frame-old image when the opposite happens.
+/// Portal cameras at the same distance are ordered by entity, so the order is stable.
+pub fn update_portal_camera_orders(
+    mut portal_cameras: Query<(Entity, &PortalCamera, &mut Camera)>,
+    transforms: Query<&GlobalTransform>,
+    mut sorted_portal_cameras: Local<Vec<(f32, Entity)>>,
+) {
+    sorted_portal_cameras.clear();
+    for (portal_camera_entity, portal_camera, _) in portal_cameras.iter() {
+        let (Ok(main_camera_transform), Ok(portal_transform)) = (
+            transforms.get(portal_camera.parts.main_camera),
+            transforms.get(portal_camera.parts.portal),
+        ) else {
+            continue;
+        };
+        let distance = main_camera_transform
+            .translation()
+            .distance_squared(portal_transform.translation());
+        sorted_portal_cameras.push((distance, portal_camera_entity));
+    }
+
+    // Farthest first
+    sorted_portal_cameras.sort_unstable_by(|(distance_a, entity_a), (distance_b, entity_b)| {
+        distance_b
+            .total_cmp(distance_a)
+            .then_with(|| entity_a.cmp(entity_b))
+    });
+
+    let count = sorted_portal_cameras.len() as isize;
+    for (index, (_, portal_camera_entity)) in sorted_portal_cameras.iter().enumerate() {
+        if let Ok((_, _, mut camera)) = portal_cameras.get_mut(*portal_camera_entity) {
+            // From -count to -1, the main cameras usually have an order of 0
+            let order = index as isize - count;
+            if camera.order != order {
+                camera.order = order;
+            }
+        }
+    }
+}
+
+/// [System] which warns when a [PortalCamera] can see its own portal.
+///
+/// The portal material samples the image the portal camera renders to, so the portal camera
+/// should never render the portal mesh, this creates a feedback loop.
+/// This usually means the portal mesh is on the render layer of the portal camera
+/// (see [CreatePortal::render_layer]) while being in view at the destination.
+///
+/// Warns once each time a portal camera starts seeing its portal.
+pub fn check_portal_camera_feedback(
+    portal_cameras: Query<(Entity, &PortalCamera, &VisibleEntities)>,
+    render_layers: Query<&RenderLayers>,
+    mut feedback_portal_cameras: Local<EntityHashSet>,
+) {
+    for (portal_camera_entity, portal_camera, visible_entities) in portal_cameras.iter() {
+        let portal = portal_camera.parts.portal;
+        let sees_portal = visible_entities
+            .iter::<With<Mesh3d>>()
+            .any(|&entity| entity == portal);
+
+        if !sees_portal {
+            feedback_portal_cameras.remove(&portal_camera_entity);
+        } else if feedback_portal_cameras.insert(portal_camera_entity) {
+            let default_render_layers = RenderLayers::default();
+            warn!(
+                "Portal Camera #{} (render layers {:?}) can see its own Portal #{} (render layers {:?}), \
+                this creates a feedback loop as the portal would render its own image. \
+                Use different render layers for the portal camera (CreatePortal::render_layer) and the portal mesh.",
+                portal_camera_entity.index(),
+                render_layers
+                    .get(portal_camera_entity)
+                    .unwrap_or(&default_render_layers),
+                portal.index(),
+                render_layers.get(portal).unwrap_or(&default_render_layers),
+            );
+        }
+    }
+}
+
 /// Resize the image used to render a portal, if needed
+///
+/// The image is resized in place, the bind groups of the materials showing it are rebuilt
+/// by the render world, see [PortalImageResized].
 fn resize_image_if_needed(
     portal_camera: &PortalCamera,
     main_camera: &Camera,
     size_params: &mut PortalImageSizeParams,
-    portal_material: &Handle<PortalMaterial>,
-    materials: &mut Assets<PortalMaterial>,
+    resize_delay: u32,
+    pending_resizes: &mut EntityHashMap<(UVec2, u32)>,
 ) -> bool {
-    let portal_image = size_params.images.get(&portal_camera.image).unwrap();
+    let Some(portal_image) = size_params
+        .images
+        .as_ref()
+        .and_then(|images| images.get(&portal_camera.image))
+    else {
+        warn!(
+            "Image of portal camera {} not found, skipping portal resize",
+            portal_camera.parts.portal_camera
+        );
+        return false;
+    };
     let portal_image_size = portal_image.size();
-    let Some(main_camera_viewport_size) = get_viewport_size(main_camera, size_params) else {
+    let Some(main_camera_viewport_size) =
+        size_params.viewport_size(portal_camera.parts.main_camera, main_camera)
+    else {
         warn!("Viewport size not found, skipping portal resize");
         return false;
     };
 
-    let resize = portal_image_size.x != main_camera_viewport_size.x
+    let mut resize = portal_image_size.x != main_camera_viewport_size.x
         || portal_image_size.y != main_camera_viewport_size.y;
-    if resize {
-        let size = Extent3d {
-            width: main_camera_viewport_size.x,
-            height: main_camera_viewport_size.y,
-            ..Extent3d::default()
-        };
-        if let (Some(portal_image), Some(_)) = (
-            size_params.images.get_mut(&portal_camera.image),
-            // This is needed so that the material is aware the image changed,
-            // see https://github.com/bevyengine/bevy/issues/8767
-            materials.get_mut(portal_material),
-        ) {
-            portal_image.texture_descriptor.size = size;
-            portal_image.resize(size);
-        } else {
-            warn!("No portal image or material.");
+    // The viewport went back to the size of the image before the delay ended
+    if !resize {
+        pending_resizes.remove(&portal_camera.parts.portal_camera);
+    }
+
+    // Wait for the viewport size to be stable before resizing
+    if resize && resize_delay > 0 {
+        let pending_resize = pending_resizes
+            .entry(portal_camera.parts.portal_camera)
+            .or_insert((main_camera_viewport_size, 0));
+        if pending_resize.0 != main_camera_viewport_size {
+            *pending_resize = (main_camera_viewport_size, 0);
         }
+        pending_resize.1 += 1;
+        resize = pending_resize.1 > resize_delay;
     }
+    if !resize {
+        return false;
+    }
+    pending_resizes.remove(&portal_camera.parts.portal_camera);
 
-    resize
+    let size = Extent3d {
+        width: main_camera_viewport_size.x,
+        height: main_camera_viewport_size.y,
+        ..Extent3d::default()
+    };
+    if let Some(portal_image) = size_params
+        .images
+        .as_deref_mut()
+        .and_then(|images| images.get_mut(&portal_camera.image))
+    {
+        portal_image.texture_descriptor.size = size;
+        portal_image.resize(size);
+    } else {
+        warn!("No portal image.");
+    }
+
+    true
 }
 
 /// Get the [Frustum] for the [PortalCamera] from the [PortalProjection] and
 /// modifying it depending on the [PortalMode].
-fn get_frustum(
+///
+/// Used by [update_portal_cameras], can be called from custom systems.
+pub fn get_frustum(
     portal_camera: &PortalCamera,
-    portal_camera_transform: &Transform,
+    portal_camera_transform: &GlobalTransform,
     destination_transform: &Transform,
     projection: &PortalProjection,
+    sub_camera_view: Option<&SubCameraView>,
 ) -> Frustum {
-    let view_projection =
-        projection.get_clip_from_view() * portal_camera_transform.compute_matrix().inverse();
+    let clip_from_view = match sub_camera_view {
+        Some(sub_camera_view) => projection.get_clip_from_view_for_sub(sub_camera_view),
+        None => projection.get_clip_from_view(),
+    };
+    let view_projection = clip_from_view * portal_camera_transform.compute_matrix().inverse();
 
+    // The far plane is scaled with the portal camera, see get_portal_camera_global_transform
     let mut frustum = Frustum::from_clip_from_world_custom_far(
         &view_projection,
-        &portal_camera_transform.translation,
+        &portal_camera_transform.translation(),
         &portal_camera_transform.back(),
-        projection.far(),
+        projection.far() * portal_camera_transform.affine().matrix3.z_axis.length(),
     );
 
+    // A fixed camera doesn't look through the portal
+    if let PortalViewerMode::FixedCamera(_) = portal_camera.viewer {
+        return frustum;
+    }
+
     match portal_camera.portal_mode {
-        PortalMode::MaskedImageHalfSpaceFrustum(Some(half_space)) => {
+        PortalMode::MaskedImageHalfSpaceFrustum(Some(half_space))
+        | PortalMode::MaskedImageAabbHalfSpaceFrustum(Some(half_space))
+        | PortalMode::MaskedImageRectangleHalfSpaceFrustum(Some(half_space)) => {
             let rot = Quat::from_rotation_arc(
                 Vec3::NEG_Z,
                 destination_transform.forward().normalize_or_zero(),
@@ -222,7 +844,9 @@ fn get_frustum(
             frustum.half_spaces[4] =
                 HalfSpace::new(near_half_space_normal.extend(near_half_space_distance))
         }
-        PortalMode::MaskedImageHalfSpaceFrustum(None) => {
+        PortalMode::MaskedImageHalfSpaceFrustum(None)
+        | PortalMode::MaskedImageAabbHalfSpaceFrustum(None)
+        | PortalMode::MaskedImageRectangleHalfSpaceFrustum(None) => {
             let near_half_space_normal = destination_transform.forward();
             let near_half_space_distance = -destination_transform
                 .translation
@@ -236,7 +860,157 @@ fn get_frustum(
     frustum
 }
 
+/// Replaces the lateral planes of the [Frustum] of a [PortalCamera] by planes going through
+/// the corners of the [Aabb] of its portal seen from the portal camera, if they are tighter,
+/// for [PortalMode::MaskedImageAabbHalfSpaceFrustum].
+///
+/// The edges of the [Aabb] going behind the portal camera are clipped, so that the frustum is
+/// still fitted when the camera is close to the portal. Does nothing if the whole [Aabb] is behind it.
+///
+/// Used by [update_portal_cameras], can be called from custom systems.
+pub fn fit_frustum_to_portal_aabb(
+    frustum: &mut Frustum,
+    portal_camera_transform: &GlobalTransform,
+    clip_from_view: Mat4,
+    portal_transform: &GlobalTransform,
+    portal_aabb: &Aabb,
+    destination_transform: &Transform,
+) {
+    let clip_from_world = clip_from_view * portal_camera_transform.compute_matrix().inverse();
+    let portal_rigid_transform = portal_transform.compute_transform();
+    let (aabb_min, aabb_max) = (Vec3::from(portal_aabb.min()), Vec3::from(portal_aabb.max()));
+    let clip_corners: [Vec4; 8] = std::array::from_fn(|corner_index| {
+        let corner = Vec3::select(
+            BVec3::new(
+                corner_index & 1 == 0,
+                corner_index & 2 == 0,
+                corner_index & 4 == 0,
+            ),
+            aabb_min,
+            aabb_max,
+        );
+        // Where the corner of the portal is seen from the portal camera
+        let destination_corner = transform_point_through_portal(
+            portal_transform.transform_point(corner),
+            &portal_rigid_transform,
+            destination_transform,
+        );
+        clip_from_world * destination_corner.extend(1.)
+    });
+
+    // The corners in front of the camera, and where the edges of the Aabb cross the plane
+    // of the camera (they project far away, which keeps the frustum wide on their side)
+    let in_front = |clip: &Vec4| clip.w > f32::EPSILON;
+    let edges = (0..8).flat_map(|corner_index: usize| {
+        [1, 2, 4]
+            .into_iter()
+            .filter(move |axis| corner_index & axis == 0)
+            .map(move |axis| {
+                (
+                    clip_corners[corner_index],
+                    clip_corners[corner_index | axis],
+                )
+            })
+    });
+    let clipped_edges = edges
+        .filter(|(start, end)| in_front(start) != in_front(end))
+        .map(|(start, end)| start.lerp(end, (f32::EPSILON - start.w) / (end.w - start.w)));
+    let mut ndc_min = Vec2::splat(1.);
+    let mut ndc_max = Vec2::splat(-1.);
+    let mut any_in_front = false;
+    for clip in clip_corners
+        .into_iter()
+        .filter(in_front)
+        .chain(clipped_edges)
+    {
+        let ndc = clip.truncate().truncate() / clip.w.max(f32::EPSILON);
+        ndc_min = ndc_min.min(ndc);
+        ndc_max = ndc_max.max(ndc);
+        any_in_front = true;
+    }
+    if !any_in_front {
+        return;
+    }
+    let ndc_min = ndc_min.max(Vec2::splat(-1.));
+    let ndc_max = ndc_max.min(Vec2::splat(1.));
+
+    // x / w >= min and x / w <= max in clip space, with w > 0
+    let row_x = clip_from_world.row(0);
+    let row_y = clip_from_world.row(1);
+    let row_w = clip_from_world.row(3);
+    frustum.half_spaces[0] = HalfSpace::new(row_x - ndc_min.x * row_w);
+    frustum.half_spaces[1] = HalfSpace::new(ndc_max.x * row_w - row_x);
+    frustum.half_spaces[2] = HalfSpace::new(row_y - ndc_min.y * row_w);
+    frustum.half_spaces[3] = HalfSpace::new(ndc_max.y * row_w - row_y);
+}
+
+/// Replaces the lateral planes of the [Frustum] of a [PortalCamera] by planes going through
+/// the portal camera and the edges of its portal, for [PortalMode::MaskedImageRectangleHalfSpaceFrustum].
+///
+/// The portal is the rectangle of its [Aabb] flattened along its thinnest axis.
+/// The parts of the rectangle outside of the screen are not culled, unlike with
+/// [fit_frustum_to_portal_aabb]. Does nothing if the portal camera is in the plane of the portal.
+///
+/// Used by [update_portal_cameras], can be called from custom systems.
+pub fn fit_frustum_to_portal_rectangle(
+    frustum: &mut Frustum,
+    portal_camera_transform: &GlobalTransform,
+    portal_transform: &GlobalTransform,
+    portal_aabb: &Aabb,
+    destination_transform: &Transform,
+) {
+    let portal_rigid_transform = portal_transform.compute_transform();
+    let half_extents = Vec3::from(portal_aabb.half_extents);
+    // The two widest axes of the Aabb span the rectangle
+    let thinnest_axis = if half_extents.x <= half_extents.y && half_extents.x <= half_extents.z {
+        0
+    } else if half_extents.y <= half_extents.z {
+        1
+    } else {
+        2
+    };
+    let u = Vec3::AXES[(thinnest_axis + 1) % 3] * half_extents[(thinnest_axis + 1) % 3];
+    let v = Vec3::AXES[(thinnest_axis + 2) % 3] * half_extents[(thinnest_axis + 2) % 3];
+    let center = Vec3::from(portal_aabb.center);
+    // Where the corners of the portal are seen from the portal camera, in order around the rectangle
+    let corners = [
+        center - u - v,
+        center + u - v,
+        center + u + v,
+        center - u + v,
+    ]
+    .map(|corner| {
+        transform_point_through_portal(
+            portal_transform.transform_point(corner),
+            &portal_rigid_transform,
+            destination_transform,
+        )
+    });
+    let rectangle_center = (corners[0] + corners[2]) / 2.;
+
+    let apex = portal_camera_transform.translation();
+    let mut half_spaces = [HalfSpace::default(); 4];
+    for (index, half_space) in half_spaces.iter_mut().enumerate() {
+        let (start, end) = (corners[index], corners[(index + 1) % 4]);
+        let mut normal = (start - apex).cross(end - apex);
+        // Facing the inside of the pyramid, which is flat if the camera is in the plane of the portal
+        let side = normal.dot(rectangle_center - apex);
+        if side.abs() <= f32::EPSILON {
+            return;
+        }
+        if side < 0. {
+            normal = -normal;
+        }
+        *half_space = HalfSpace::new(normal.extend(-normal.dot(apex)));
+    }
+    frustum.half_spaces[..4].copy_from_slice(&half_spaces);
+}
+
 /// Helper function to get the size of the viewport of the main camera, to be used for the size of the render image.
+///
+/// The size of the render target is read each time, so the portal images follow windows and
+/// [RenderTarget::Image]s resized at runtime. The [Viewport] of the main camera is clamped to
+/// its render target, which may have shrunk since the viewport was set.
 pub(super) fn get_viewport_size(
     main_camera: &Camera,
     PortalImageSizeParams {
@@ -244,51 +1018,108 @@ pub(super) fn get_viewport_size(
         primary_window_query,
         windows_query,
         texture_views,
+        ..
     }: &PortalImageSizeParams,
 ) -> Option<UVec2> {
-    match main_camera.viewport.as_ref() {
-        Some(viewport) => Some(viewport.physical_size),
-        None => match &main_camera.target {
-            RenderTarget::Window(window_ref) => (match window_ref {
-                WindowRef::Primary => primary_window_query.get_single().ok(),
-                WindowRef::Entity(entity) => windows_query.get(*entity).ok(),
-            })
-            .map(|window| UVec2::new(window.physical_width(), window.physical_height())),
-            RenderTarget::Image(handle) => images.get(handle).map(|image| image.size()),
-            RenderTarget::TextureView(handle) => texture_views
-                .get(handle)
-                .map(|texture_view| texture_view.size),
-        },
+    let target_size = match &main_camera.target {
+        RenderTarget::Window(window_ref) => (match window_ref {
+            WindowRef::Primary => primary_window_query.get_single().ok(),
+            WindowRef::Entity(entity) => windows_query.get(*entity).ok(),
+        })
+        .map(|window| UVec2::new(window.physical_width(), window.physical_height())),
+        RenderTarget::Image(handle) => images
+            .as_ref()
+            .and_then(|images| images.get(handle))
+            .map(|image| image.size()),
+        RenderTarget::TextureView(handle) => texture_views
+            .as_ref()
+            .and_then(|texture_views| texture_views.get(handle))
+            .map(|texture_view| texture_view.size),
+    };
+    match (main_camera.viewport.as_ref(), target_size) {
+        (Some(viewport), Some(target_size)) => Some(
+            viewport
+                .physical_size
+                .min(target_size.saturating_sub(viewport.physical_position))
+                .max(UVec2::ONE),
+        ),
+        (Some(viewport), None) => Some(viewport.physical_size),
+        (None, target_size) => target_size,
     }
 }
 
 /// [SystemParam] needed to compute the size of the portal image
+///
+/// The render resources are missing when [PortalsPlugin::rendering] is false, see [PortalsHeadless].
+///
+/// Each system using it keeps its own cache of the viewport sizes: the sizes are not shared
+/// between [create_portals], [CreatePortalCommand] and [update_portal_cameras], as a viewport
+/// can change between them during a frame.
 #[derive(SystemParam)]
 pub struct PortalImageSizeParams<'w, 's> {
-    pub(super) images: ResMut<'w, Assets<Image>>,
+    pub(super) images: Option<ResMut<'w, Assets<Image>>>,
     primary_window_query: Query<'w, 's, &'static Window, With<PrimaryWindow>>,
     windows_query: Query<'w, 's, &'static Window>,
-    texture_views: Res<'w, ManualTextureViews>,
+    texture_views: Option<Res<'w, ManualTextureViews>>,
+    frame_count: Option<Res<'w, FrameCount>>,
+    viewport_sizes: Local<'s, ViewportSizeCache>,
 }
 
-/// Helper function to get the transform to change the main camera's transform into the portal camera's transform.
-fn get_portal_camera_transform(
-    main_camera_transform: &Transform,
-    portal_transform: &Transform,
-    destination_transform: &Transform,
-) -> Transform {
-    let portal_camera_translation = main_camera_transform.translation
-        - portal_transform.translation
-        + destination_transform.translation;
-    let rotation = portal_transform
-        .rotation
-        .inverse()
-        .mul_quat(destination_transform.rotation);
-    let mut portal_camera_transform = Transform {
-        translation: portal_camera_translation,
-        rotation: main_camera_transform.rotation,
-        scale: main_camera_transform.scale,
-    };
-    portal_camera_transform.rotate_around(destination_transform.translation, rotation);
-    portal_camera_transform
+/// Viewport sizes of the main cameras computed during a frame, see [PortalImageSizeParams::viewport_size]
+#[derive(Default)]
+pub(super) struct ViewportSizeCache {
+    frame: Option<u32>,
+    sizes: EntityHashMap<CachedViewportSize>,
+}
+
+/// Entry of [ViewportSizeCache], with the inputs of [get_viewport_size] it was computed from
+struct CachedViewportSize {
+    /// `None` for the primary window
+    target: Option<NormalizedRenderTarget>,
+    /// Physical position and size of the [Viewport]
+    viewport: Option<(UVec2, UVec2)>,
+    size: Option<UVec2>,
+}
+
+impl PortalImageSizeParams<'_, '_> {
+    /// Size of the viewport of a main camera, computed once per frame and per main camera
+    /// by the calling system, the portals sharing a main camera reuse it.
+    ///
+    /// The size is computed again if the render target or the [Viewport] of the main camera
+    /// changed since it was cached, for example between two runs of [update_portal_cameras_with]
+    /// in the same frame.
+    /// Without the [FrameCount] resource, the size is computed at each call.
+    pub(super) fn viewport_size(
+        &mut self,
+        main_camera_entity: Entity,
+        main_camera: &Camera,
+    ) -> Option<UVec2> {
+        let Some(frame) = self.frame_count.as_ref().map(|frame_count| frame_count.0) else {
+            return get_viewport_size(main_camera, self);
+        };
+        if self.viewport_sizes.frame != Some(frame) {
+            self.viewport_sizes.frame = Some(frame);
+            self.viewport_sizes.sizes.clear();
+        }
+        let target = main_camera.target.normalize(None);
+        let viewport = main_camera
+            .viewport
+            .as_ref()
+            .map(|viewport| (viewport.physical_position, viewport.physical_size));
+        if let Some(cached) = self.viewport_sizes.sizes.get(&main_camera_entity) {
+            if cached.target == target && cached.viewport == viewport {
+                return cached.size;
+            }
+        }
+        let size = get_viewport_size(main_camera, self);
+        self.viewport_sizes.sizes.insert(
+            main_camera_entity,
+            CachedViewportSize {
+                target,
+                viewport,
+                size,
+            },
+        );
+        size
+    }
 }
@@ -0,0 +1,261 @@
+//! Data about how portals are seen, derived once per frame
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use bevy_math::{Mat4, URect, UVec2, Vec2};
+use bevy_reflect::{std_traits::ReflectDefault, Reflect};
+use bevy_render::{prelude::*, primitives::Aabb};
+use bevy_tasks::{block_on, poll_once, AsyncComputeTaskPool, Task};
+use bevy_transform::prelude::*;
+
+use super::*;
+
+/// Add the view info logic to [PortalsPlugin]
+pub(super) fn build_view_info(app: &mut App) {
+    app.register_type::<PortalViewInfo>()
+        .register_type::<PortalViewInfoSettings>()
+        .init_resource::<PortalViewInfoSettings>()
+        .add_systems(
+            PostUpdate,
+            (
+                update_portal_view_infos
+                    .run_if(|settings: Res<PortalViewInfoSettings>| !settings.asynchronous),
+                update_portal_view_infos_async
+                    .run_if(|settings: Res<PortalViewInfoSettings>| settings.asynchronous),
+            )
+                .in_set(PortalSystems::UpdateCameras)
+                .after(rebind_portal_main_cameras)
+                .before(update_portal_camera_viewports),
+        );
+}
+
+/// [Resource] to configure how the [PortalViewInfo]s are computed.
+#[derive(Resource, Clone, Debug, Default, Reflect)]
+#[reflect(Resource, Default)]
+pub struct PortalViewInfoSettings {
+    /// If true, the [PortalViewInfo]s are computed in the [AsyncComputeTaskPool] by
+    /// [update_portal_view_infos_async] and are at least one frame late, to keep the main
+    /// schedule lean with hundreds of portals, defaults to false.
+    ///
+    /// The scissoring of the portal images (see [CreatePortal::scissor_to_portal]) and the
+    /// [PortalImpostor]s are then late too, so portals moving fast on the screen can show
+    /// the edge of their image.
+    pub asynchronous: bool,
+}
+
+/// [Component] with data about how a [Portal] is seen by its main camera, updated every frame
+/// by [update_portal_view_infos], for culling, level of detail, audio or gameplay.
+///
+/// Computed at the start of [PortalSystems::UpdateCameras], and used by this crate to scissor
+/// the portal images (see [CreatePortal::scissor_to_portal]) and for the [PortalImpostor]s.
+/// Can be computed in another thread, see [PortalViewInfoSettings].
+///
+/// Inserted with the [Portal] component.
+#[derive(Component, Clone, Debug, Default, PartialEq, Reflect)]
+#[reflect(Component, Default)]
+pub struct PortalViewInfo {
+    /// Rectangle covered by the [Aabb] of the portal on the viewport of the main camera,
+    /// in physical pixels, `None` if part of it is behind the camera.
+    pub screen_aabb: Option<URect>,
+    /// Fraction of the viewport covered by [screen_aabb](Self::screen_aabb), from 0 to 1,
+    /// 1 if part of the portal is behind the camera.
+    pub coverage: f32,
+    /// Side of the portal plane the main camera is on.
+    pub viewer_side: PortalViewerSide,
+    /// Signed distance from the main camera to the portal plane, positive on the front side.
+    pub distance_to_plane: f32,
+    /// Distance from the main camera to the origin of the portal.
+    pub distance: f32,
+    /// Position of the origin of the portal on the viewport of the main camera, from `(0, 0)`
+    /// at the top left to `(1, 1)` at the bottom right, `None` if it is behind the camera
+    /// or beyond its far plane.
+    pub screen_position: Option<Vec2>,
+    /// Depth of the origin of the portal in the view space of the main camera, positive in
+    /// front of it.
+    pub depth: f32,
+    /// Whether the [PortalCamera] renders this frame, written by
+    /// [update_portal_camera_activation] at the end of the frame.
+    pub active: bool,
+}
+
+/// Side of the plane of a portal (its local XY plane), see [PortalViewInfo::viewer_side].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+#[reflect(Default)]
+pub enum PortalViewerSide {
+    /// In the direction of the local Z axis of the portal, where the faces of a [Rectangle](bevy_math::prelude::Rectangle) look.
+    #[default]
+    Front,
+    /// Opposite to the local Z axis of the portal.
+    Back,
+}
+
+/// [System] that updates the [PortalViewInfo] of portals.
+pub fn update_portal_view_infos(
+    mut portals: Query<(&Portal, &GlobalTransform, &Aabb, &mut PortalViewInfo)>,
+    main_cameras: Query<(&Camera, &GlobalTransform), Without<PortalCamera>>,
+) {
+    for (portal, portal_transform, portal_aabb, mut view_info) in portals.iter_mut() {
+        let Ok((main_camera, main_camera_transform)) = main_cameras.get(portal.parts.main_camera)
+        else {
+            continue;
+        };
+        let new_view_info = PortalViewInfo {
+            active: view_info.active,
+            ..compute_portal_view_info(
+                main_camera.clip_from_view(),
+                main_camera.physical_viewport_size().unwrap_or_default(),
+                main_camera_transform,
+                portal_transform,
+                portal_aabb,
+            )
+        };
+        // Keeps change detection meaningful
+        if *view_info != new_view_info {
+            *view_info = new_view_info;
+        }
+    }
+}
+
+/// Inputs of [compute_portal_view_info] for a portal, copied to be sent to another thread
+type PortalViewInfoInputs = (Entity, Mat4, UVec2, GlobalTransform, GlobalTransform, Aabb);
+
+/// [System] that updates the [PortalViewInfo] of portals like [update_portal_view_infos],
+/// but computes them in the [AsyncComputeTaskPool], see [PortalViewInfoSettings::asynchronous].
+///
+/// The results of a task are applied when it is finished, a new task is then started with the
+/// data of the current frame.
+/// Only the [Copy] data needed by [compute_portal_view_info] is gathered on the main thread.
+#[allow(clippy::type_complexity)]
+pub fn update_portal_view_infos_async(
+    mut task: Local<Option<Task<Vec<(Entity, PortalViewInfo)>>>>,
+    mut portals: Query<(
+        Entity,
+        &Portal,
+        &GlobalTransform,
+        &Aabb,
+        &mut PortalViewInfo,
+    )>,
+    main_cameras: Query<(&Camera, &GlobalTransform), Without<PortalCamera>>,
+) {
+    if let Some(running_task) = task.as_mut() {
+        if !running_task.is_finished() {
+            return;
+        }
+        if let Some(view_infos) = block_on(poll_once(running_task)) {
+            for (portal_entity, new_view_info) in view_infos {
+                if let Ok((.., mut view_info)) = portals.get_mut(portal_entity) {
+                    let new_view_info = PortalViewInfo {
+                        active: view_info.active,
+                        ..new_view_info
+                    };
+                    // Keeps change detection meaningful
+                    if *view_info != new_view_info {
+                        *view_info = new_view_info;
+                    }
+                }
+            }
+        }
+        *task = None;
+    }
+
+    let inputs: Vec<PortalViewInfoInputs> = portals
+        .iter()
+        .filter_map(
+            |(portal_entity, portal, portal_transform, portal_aabb, _)| {
+                let (main_camera, main_camera_transform) =
+                    main_cameras.get(portal.parts.main_camera).ok()?;
+                Some((
+                    portal_entity,
+                    main_camera.clip_from_view(),
+                    main_camera.physical_viewport_size().unwrap_or_default(),
+                    *main_camera_transform,
+                    *portal_transform,
+                    *portal_aabb,
+                ))
+            },
+        )
+        .collect();
+    if inputs.is_empty() {
+        return;
+    }
+    *task = Some(AsyncComputeTaskPool::get().spawn(async move {
+        inputs
+            .iter()
+            .map(
+                |(
+                    portal_entity,
+                    clip_from_view,
+                    viewport_size,
+                    main_camera_transform,
+                    portal_transform,
+                    portal_aabb,
+                )| {
+                    (
+                        *portal_entity,
+                        compute_portal_view_info(
+                            *clip_from_view,
+                            *viewport_size,
+                            main_camera_transform,
+                            portal_transform,
+                            portal_aabb,
+                        ),
+                    )
+                },
+            )
+            .collect()
+    }));
+}
+
+/// Helper function to compute the [PortalViewInfo] of a portal seen by its main camera,
+/// from the [Camera::clip_from_view] and the [Camera::physical_viewport_size] of the main camera.
+///
+/// [PortalViewInfo::active] is left to false, it is written by [update_portal_camera_activation].
+pub fn compute_portal_view_info(
+    clip_from_view: Mat4,
+    viewport_size: UVec2,
+    main_camera_transform: &GlobalTransform,
+    portal_transform: &GlobalTransform,
+    portal_aabb: &Aabb,
+) -> PortalViewInfo {
+    let screen_aabb = get_portal_image_rect(
+        clip_from_view,
+        main_camera_transform,
+        portal_transform,
+        portal_aabb,
+        viewport_size,
+    );
+    let coverage = match screen_aabb {
+        Some(rect) => rect.size().element_product() as f32 / viewport_size.element_product() as f32,
+        None => 1.,
+    };
+    let distance_to_plane = (main_camera_transform.translation() - portal_transform.translation())
+        .dot(portal_transform.back().into());
+    let viewer_side = if distance_to_plane >= 0. {
+        PortalViewerSide::Front
+    } else {
+        PortalViewerSide::Back
+    };
+
+    // Like Camera::world_to_viewport, divided by the size of the viewport
+    let portal_position = portal_transform.translation();
+    let ndc = (clip_from_view * main_camera_transform.compute_matrix().inverse())
+        .project_point3(portal_position);
+    let screen_position = (!ndc.is_nan() && (0. ..=1.).contains(&ndc.z))
+        .then(|| Vec2::new(ndc.x + 1., 1. - ndc.y) * 0.5);
+    let depth = -main_camera_transform
+        .affine()
+        .inverse()
+        .transform_point3(portal_position)
+        .z;
+
+    PortalViewInfo {
+        screen_aabb,
+        coverage,
+        viewer_side,
+        distance_to_plane,
+        distance: portal_position.distance(main_camera_transform.translation()),
+        screen_position,
+        depth,
+        active: false,
+    }
+}
@@ -0,0 +1,206 @@
+//! Views of a portal for several main cameras, see [CreatePortal::main_camera_views]
+
+use bevy_app::prelude::*;
+use bevy_core::Name;
+use bevy_core_pipeline::prelude::Camera3d;
+use bevy_ecs::{
+    entity::{EntityHashMap, EntityHashSet},
+    prelude::*,
+};
+use bevy_hierarchy::prelude::*;
+use bevy_reflect::Reflect;
+use bevy_render::{prelude::*, view::RenderLayers};
+use bevy_transform::prelude::*;
+
+use super::*;
+
+/// Add the views of the portals to [PortalsPlugin]
+pub(super) fn build_views(app: &mut App) {
+    app.register_type::<PortalView>()
+        .add_observer(free_portal_view_layer)
+        .add_systems(
+            PostUpdate,
+            update_portal_views.in_set(PortalSystems::Create),
+        );
+}
+
+/// [Component] of the view of a [Portal] for one of the main cameras,
+/// see [CreatePortal::main_camera_views].
+///
+/// The portal is the view of its own main camera.
+/// The views of the other main cameras are portals created on children of the portal, with the
+/// portal mesh and a destination created under the destination of the portal, each with its own
+/// [PortalCamera] and image.
+/// The mesh of each view is on its own render layer, added to the [RenderLayers] of its main
+/// camera, so that each main camera only sees its view.
+#[derive(Component, Clone, Copy, Debug, Reflect)]
+#[reflect(Component)]
+pub struct PortalView {
+    /// The portal seen through this view.
+    pub portal: Entity,
+    /// The main camera seeing this view.
+    pub main_camera: Entity,
+    /// Render layer of this view, taken from the [PortalLayerAllocator] and freed when this
+    /// component is removed.
+    pub layer: usize,
+}
+
+/// [Component] of a portal with [PortalView]s, with the [CreatePortal] its other views
+/// are created from.
+#[derive(Component, Clone)]
+pub struct PortalViewConfig(pub(super) CreatePortal);
+
+/// [System] creating and removing the [PortalView]s of the portals as main cameras are spawned
+/// and despawned, added by [PortalsPlugin] in [PostUpdate].
+///
+/// A view is created for each [Camera3d] that is not a [PortalCamera], and removed with
+/// [RemovePortalCommand] when its main camera is despawned or becomes the main camera of the
+/// portal itself (see [rebind_portal_main_cameras]).
+/// [PortalParts::views] is then updated on all the parts of the portal.
+///
+/// Also adds the layer of each view to the [RenderLayers] of its main camera.
+#[allow(clippy::type_complexity)]
+pub fn update_portal_views(
+    mut commands: Commands,
+    portals: Query<(
+        Entity,
+        &Portal,
+        &PortalView,
+        &PortalViewConfig,
+        &GlobalTransform,
+        &Mesh3d,
+    )>,
+    views: Query<(&PortalView, Has<Portal>)>,
+    main_cameras: Query<
+        (Entity, Option<&RenderLayers>),
+        (
+            With<Camera3d>,
+            Without<PortalCamera>,
+            Without<PortalDebugCamera>,
+        ),
+    >,
+    mut layer_allocator: ResMut<PortalLayerAllocator>,
+) {
+    // Accumulated so that several views of the same frame don't override each other's layer
+    let mut main_camera_layers = EntityHashMap::<RenderLayers>::default();
+    let mut add_main_camera_layer = |main_camera: Entity, layer: usize| {
+        let layers = main_camera_layers
+            .entry(main_camera)
+            .or_insert_with(RenderLayers::none);
+        *layers = layers.clone().with(layer);
+    };
+
+    for (portal_entity, portal, portal_view, config, portal_transform, portal_mesh) in &portals {
+        let parts = &portal.parts;
+        // The portal follows its main camera, see rebind_portal_main_cameras
+        if portal_view.main_camera != parts.main_camera {
+            commands.entity(portal_entity).insert(PortalView {
+                main_camera: parts.main_camera,
+                ..*portal_view
+            });
+        }
+        add_main_camera_layer(parts.main_camera, portal_view.layer);
+
+        let mut new_views = vec![portal_entity];
+        let mut viewing_cameras = EntityHashSet::default();
+        viewing_cameras.insert(parts.main_camera);
+        for &view_entity in parts.views.iter().filter(|view| **view != portal_entity) {
+            let Ok((view, is_portal)) = views.get(view_entity) else {
+                continue;
+            };
+            if main_cameras.contains(view.main_camera) && viewing_cameras.insert(view.main_camera) {
+                add_main_camera_layer(view.main_camera, view.layer);
+                new_views.push(view_entity);
+                continue;
+            }
+            if is_portal {
+                commands.entity(view_entity).queue(RemovePortalCommand);
+            }
+            commands.entity(view_entity).despawn_recursive();
+        }
+
+        for (main_camera, _) in &main_cameras {
+            if !viewing_cameras.insert(main_camera) {
+                continue;
+            }
+            let layer = layer_allocator.allocate();
+            add_main_camera_layer(main_camera, layer);
+            // The GlobalTransform of the portal is used at creation, before it is propagated
+            let view_entity = commands
+                .spawn((
+                    Mesh3d(portal_mesh.0.clone()),
+                    Transform::default(),
+                    *portal_transform,
+                    RenderLayers::layer(layer),
+                    PortalView {
+                        portal: portal_entity,
+                        main_camera,
+                        layer,
+                    },
+                    Name::new(format!("Portal View {portal_entity} {main_camera}")),
+                    PortalSpawnedEntity,
+                ))
+                .set_parent(portal_entity)
+                .queue(CreatePortalCommand {
+                    config: Some(CreatePortal {
+                        destination: PortalDestinationSource::Create(CreatePortalDestination {
+                            parent: Some(parts.destination),
+                            ..Default::default()
+                        }),
+                        main_camera: Some(main_camera),
+                        ..config.0.clone()
+                    }),
+                })
+                .id();
+            new_views.push(view_entity);
+        }
+
+        if new_views != parts.views {
+            let parts = parts.clone();
+            commands.queue(move |world: &mut World| {
+                set_portal_views(world, &parts, new_views);
+            });
+        }
+    }
+
+    for (main_camera, layers) in main_camera_layers {
+        let Ok((_, current_layers)) = main_cameras.get(main_camera) else {
+            continue;
+        };
+        let current_layers = current_layers.cloned().unwrap_or_default();
+        let new_layers = current_layers.union(&layers);
+        if new_layers != current_layers {
+            commands.entity(main_camera).try_insert(new_layers);
+        }
+    }
+}
+
+/// Sets the [PortalParts::views] of all the parts of the portal with these `parts`
+fn set_portal_views(world: &mut World, parts: &PortalParts, views: Vec<Entity>) {
+    if let Some(mut portal) = world.get_mut::<Portal>(parts.portal) {
+        portal.parts.views = views.clone();
+    }
+    if let Some(mut destination) = world.get_mut::<PortalDestination>(parts.destination) {
+        destination.parts.views = views.clone();
+    }
+    if let Some(mut portal_camera) = world.get_mut::<PortalCamera>(parts.portal_camera) {
+        portal_camera.parts.views = views;
+    }
+}
+
+/// Observer freeing the layer of a [PortalView] when it is removed, and removing it from the
+/// [RenderLayers] of its main camera
+fn free_portal_view_layer(
+    trigger: Trigger<OnRemove, PortalView>,
+    views: Query<&PortalView>,
+    mut render_layers: Query<&mut RenderLayers, Without<PortalView>>,
+    mut allocator: ResMut<PortalLayerAllocator>,
+) {
+    let Ok(view) = views.get(trigger.entity()) else {
+        return;
+    };
+    allocator.free(view.layer);
+    if let Ok(mut main_camera_layers) = render_layers.get_mut(view.main_camera) {
+        *main_camera_layers = main_camera_layers.clone().without(view.layer);
+    }
+}
@@ -0,0 +1,22 @@
+//! Commonly used items, to be imported with `use bevy_basic_portals::prelude::*;`
+
+#[doc(hidden)]
+pub use crate::portals::{
+    CreatePortal, CreatePortalCommand, CreatePortalDestination, DebugPortal,
+    DespawnPortalPartsCommand, MirrorConfig, Portal, PortalAnimation, PortalAnimationState,
+    PortalCamera, PortalCreated, PortalCreationFailed, PortalDeactivation, PortalDestination,
+    PortalDestinationSource, PortalMode, PortalSystems, PortalViewInfo, PortalViewerMode,
+    PortalsPlugin, RemovePortalCommand, RetargetPortalCommand, SetPortalMainCameraCommand,
+};
+
+#[cfg(feature = "gizmos")]
+#[doc(hidden)]
+pub use crate::portals::PortalDebugGizmos;
+
+#[cfg(feature = "ui")]
+#[doc(hidden)]
+pub use crate::portals::{PortalDebugTextureView, PortalPictureInPicture, UiPortal};
+
+#[cfg(feature = "picking")]
+#[doc(hidden)]
+pub use crate::picking::{PortalPickingSettings, PortalPointer};
@@ -0,0 +1,194 @@
+//! Checks that the systems of PortalsPlugin can be initialized, their parameters conflicting
+//! (error B0001) would only panic when the app starts
+
+use bevy::{
+    log::LogPlugin,
+    prelude::*,
+    render::{
+        camera::RenderTarget,
+        render_resource::{Extent3d, TextureDimension, TextureFormat},
+        settings::WgpuSettings,
+        view::RenderLayers,
+        RenderPlugin,
+    },
+    window::ExitCondition,
+    winit::WinitPlugin,
+};
+use bevy_basic_portals::{
+    portals::{PortalLayerAllocator, PortalView, RemovePortalCommand},
+    prelude::*,
+};
+
+/// Initializes all the schedules of `app`, which validates the parameters of their systems
+fn initialize_schedules(app: &mut App) {
+    let labels: Vec<_> = app
+        .world()
+        .resource::<Schedules>()
+        .iter()
+        .map(|(_, schedule)| schedule.label())
+        .collect();
+    for label in labels {
+        app.world_mut().schedule_scope(label, |world, schedule| {
+            schedule
+                .initialize(world)
+                .unwrap_or_else(|error| panic!("Schedule {label:?} failed to build: {error}"));
+        });
+    }
+}
+
+#[test]
+fn headless_plugin_runs() {
+    let mut app = App::new();
+    app.add_plugins((
+        MinimalPlugins,
+        PortalsPlugin {
+            rendering: false,
+            ..default()
+        },
+    ));
+    app.update();
+}
+
+#[test]
+fn rendering_plugin_schedules_initialize() {
+    let mut app = App::new();
+    // The assets of the render plugins, without a renderer
+    app.add_plugins((MinimalPlugins, AssetPlugin::default()))
+        .init_asset::<Shader>()
+        .init_asset::<Image>()
+        .init_asset::<Mesh>()
+        .init_asset::<StandardMaterial>()
+        .add_plugins(PortalsPlugin::default());
+    app.finish();
+    app.cleanup();
+    initialize_schedules(&mut app);
+}
+
+/// App with the render plugins, without a window nor a GPU, in which portals can be created
+fn app_without_gpu() -> App {
+    let mut app = App::new();
+    app.add_plugins((
+        DefaultPlugins
+            .set(WindowPlugin {
+                primary_window: None,
+                exit_condition: ExitCondition::DontExit,
+                ..default()
+            })
+            .set(RenderPlugin {
+                render_creation: WgpuSettings {
+                    backends: None,
+                    ..default()
+                }
+                .into(),
+                ..default()
+            })
+            .disable::<LogPlugin>()
+            .disable::<WinitPlugin>(),
+        PortalsPlugin::MINIMAL,
+    ));
+    app.finish();
+    app.cleanup();
+    app
+}
+
+/// Spawns a main camera rendering to `target`
+fn spawn_main_camera(app: &mut App, target: &Handle<Image>) -> Entity {
+    app.world_mut()
+        .spawn((
+            Camera3d::default(),
+            Camera {
+                target: RenderTarget::Image(target.clone()),
+                ..default()
+            },
+        ))
+        .id()
+}
+
+fn portal_views(app: &App, portal: Entity) -> Vec<Entity> {
+    app.world()
+        .get::<Portal>(portal)
+        .unwrap()
+        .parts
+        .views
+        .clone()
+}
+
+fn camera_layers(app: &App, camera: Entity) -> RenderLayers {
+    app.world().get::<RenderLayers>(camera).unwrap().clone()
+}
+
+#[test]
+fn portal_views_follow_main_cameras() {
+    let mut app = app_without_gpu();
+    let world = app.world_mut();
+    let target = world.resource_mut::<Assets<Image>>().add(Image::new_fill(
+        Extent3d {
+            width: 64,
+            height: 64,
+            ..default()
+        },
+        TextureDimension::D2,
+        &[0; 4],
+        TextureFormat::Bgra8UnormSrgb,
+        default(),
+    ));
+    let mesh = world
+        .resource_mut::<Assets<Mesh>>()
+        .add(Rectangle::new(1., 1.));
+    let first_camera = spawn_main_camera(&mut app, &target);
+    let portal = app
+        .world_mut()
+        .spawn((
+            CreatePortal {
+                main_camera: Some(first_camera),
+                main_camera_views: true,
+                ..CreatePortal::to(Transform::from_xyz(0., 0., -10.))
+            },
+            Mesh3d(mesh),
+        ))
+        .id();
+    app.update();
+    assert_eq!(portal_views(&app, portal), [portal]);
+
+    // A view is created for the new camera, which only sees its view
+    let second_camera = spawn_main_camera(&mut app, &target);
+    app.update();
+    let views = portal_views(&app, portal);
+    assert_eq!(views.len(), 2);
+    let view = *app.world().get::<PortalView>(views[1]).unwrap();
+    assert_eq!(view.portal, portal);
+    assert_eq!(view.main_camera, second_camera);
+    assert!(app.world().get::<Portal>(views[1]).is_some());
+    let portal_layer = app.world().get::<PortalView>(portal).unwrap().layer;
+    assert!(camera_layers(&app, first_camera).intersects(&RenderLayers::layer(portal_layer)));
+    assert!(!camera_layers(&app, first_camera).intersects(&RenderLayers::layer(view.layer)));
+    assert!(camera_layers(&app, second_camera).intersects(&RenderLayers::layer(view.layer)));
+    assert!(!camera_layers(&app, second_camera).intersects(&RenderLayers::layer(portal_layer)));
+
+    // The view is removed with its camera, and its layer freed
+    app.world_mut().despawn(second_camera);
+    app.update();
+    assert_eq!(portal_views(&app, portal), [portal]);
+    assert!(app.world().get_entity(views[1]).is_err());
+    let allocated = app.world().resource::<PortalLayerAllocator>().allocated();
+    assert!(!allocated.contains(&view.layer));
+
+    // Removing the portal removes its views
+    spawn_main_camera(&mut app, &target);
+    app.update();
+    let views = portal_views(&app, portal);
+    assert_eq!(views.len(), 2);
+    app.world_mut()
+        .commands()
+        .entity(portal)
+        .queue(RemovePortalCommand);
+    app.update();
+    assert!(app.world().get::<PortalView>(portal).is_none());
+    assert!(app.world().get_entity(views[1]).is_err());
+    assert!(app
+        .world()
+        .resource::<PortalLayerAllocator>()
+        .allocated()
+        .is_empty());
+    assert!(!camera_layers(&app, first_camera).intersects(&RenderLayers::layer(portal_layer)));
+}